@@ -0,0 +1,408 @@
+use super::*;
+
+const USAGE: &str = "Usage: mouse_recorder [play [--speed <factor>] [--faithful] [--on-error <policy>] [--report <file>]
+                      [--remap <mode>] <recording.yaml>]
+
+Without arguments the recorder GUI starts.
+
+Commands:
+  play <file>   Replay a saved recording without opening a window.
+                Ctrl-C cancels. Exits 1 when a row fails, 130 when cancelled.
+                Failed rows save screenshots to <recording>-failures/.
+
+Play options:
+  --speed <factor>  Scale waits, moves and click holds (0.25 to 10, default 1).
+  --faithful        Replay the recorded gaps between rows instead of row waits.
+  --on-error <policy>
+                    What rows without their own policy do when they fail:
+                    abort (default), skip, retry:<times>[:<backoff ms>] or
+                    goto:<label>.
+  --report <file>   Write a per-row run report: JUnit XML when the file ends
+                    in .xml, JSON otherwise.
+  --remap <mode>    Move recorded positions onto this screen when it differs
+                    from the recording's: scale, top-left, top-right,
+                    bottom-left, bottom-right (keep the distance to that
+                    corner) or offset:<dx>,<dy>. Default: as recorded.";
+
+/// Handles command-line invocations. Returns the process exit code, or `None`
+/// when no command was given and the GUI should start.
+pub(crate) fn run_from_args() -> Option<i32> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    run(&args, play)
+}
+
+/// Parses `args` and hands a `play` command to `play`.
+fn run(args: &[String], play: impl FnOnce(&str, PlaybackOptions, Option<&str>, RemapArg) -> i32) -> Option<i32> {
+    let command = args.first()?;
+
+    attach_parent_console();
+
+    match command.as_str() {
+        "play" => match parse_play_args(&args[1..]) {
+            Ok((path, options, report_path, remap)) => Some(play(path, options, report_path, remap)),
+            Err(err) => {
+                eprintln!("{err}\n\n{USAGE}");
+                Some(2)
+            }
+        },
+        "-h" | "--help" | "help" => {
+            println!("{USAGE}");
+            Some(0)
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            Some(2)
+        }
+    }
+}
+
+/// Remap mode and offset from `--remap`; the screens come from the recording.
+type RemapArg = (RemapMode, (i32, i32));
+
+fn parse_play_args(args: &[String]) -> Result<(&str, PlaybackOptions, Option<&str>, RemapArg), String> {
+    let mut options = PlaybackOptions::default();
+    let mut path = None;
+    let mut report_path = None;
+    let mut remap = (RemapMode::Off, (0, 0));
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--faithful" => options.faithful_timing = true,
+            "--speed" => {
+                let value = args.next().ok_or("--speed needs a value")?;
+                options.speed = value
+                    .trim_end_matches('x')
+                    .parse::<f32>()
+                    .ok()
+                    .filter(|speed| {
+                        (PlaybackOptions::MIN_SPEED..=PlaybackOptions::MAX_SPEED).contains(speed)
+                    })
+                    .ok_or_else(|| format!("invalid --speed {value}; expected 0.25 to 10"))?;
+            }
+            "--on-error" => {
+                let value = args.next().ok_or("--on-error needs a value")?;
+                options.on_error = parse_on_error(value)?;
+            }
+            "--report" => report_path = Some(args.next().ok_or("--report needs a file")?.as_str()),
+            "--remap" => remap = parse_remap(args.next().ok_or("--remap needs a mode")?)?,
+            _ if path.is_none() && !arg.starts_with("--") => path = Some(arg.as_str()),
+            _ => return Err(format!("unexpected argument {arg}")),
+        }
+    }
+
+    Ok((path.ok_or("missing recording file")?, options, report_path, remap))
+}
+
+fn parse_remap(value: &str) -> Result<RemapArg, String> {
+    let invalid = || {
+        format!("invalid --remap {value}; expected scale, top-left, top-right, bottom-left, bottom-right or offset:<dx>,<dy>")
+    };
+    let mode = match value {
+        "off" => RemapMode::Off,
+        "scale" => RemapMode::Scale,
+        "top-left" => RemapMode::AnchorTopLeft,
+        "top-right" => RemapMode::AnchorTopRight,
+        "bottom-left" => RemapMode::AnchorBottomLeft,
+        "bottom-right" => RemapMode::AnchorBottomRight,
+        _ => {
+            let (dx, dy) = value
+                .strip_prefix("offset:")
+                .and_then(|rest| rest.split_once(','))
+                .ok_or_else(invalid)?;
+            let offset = (
+                dx.trim().parse().map_err(|_| invalid())?,
+                dy.trim().parse().map_err(|_| invalid())?,
+            );
+            return Ok((RemapMode::Offset, offset));
+        }
+    };
+    Ok((mode, (0, 0)))
+}
+
+fn parse_on_error(value: &str) -> Result<OnError, String> {
+    let invalid = || format!("invalid --on-error {value}; expected abort, skip, retry:<times>[:<ms>] or goto:<label>");
+    let mut parts = value.splitn(2, ':');
+    match (parts.next().unwrap_or_default(), parts.next()) {
+        ("abort", None) => Ok(OnError::Abort),
+        ("skip", None) => Ok(OnError::Skip),
+        ("retry", Some(rest)) => {
+            let (times, backoff_ms) = rest.split_once(':').unwrap_or((rest, "500"));
+            Ok(OnError::Retry {
+                times: times.parse().ok().filter(|times| *times > 0).ok_or_else(invalid)?,
+                backoff_ms: backoff_ms.parse().map_err(|_| invalid())?,
+            })
+        }
+        ("goto", Some(label)) if !label.is_empty() => Ok(OnError::GotoLabel(label.to_string())),
+        _ => Err(invalid()),
+    }
+}
+
+fn play(path: &str, options: PlaybackOptions, report_path: Option<&str>, remap: RemapArg) -> i32 {
+    let (events, header) = match load_events_from_file(path) {
+        Ok(loaded) => loaded,
+        Err(err) => {
+            eprintln!("{err}");
+            return 1;
+        }
+    };
+    if events.is_empty() {
+        eprintln!("{path} has no events to play.");
+        return 1;
+    }
+
+    if options.faithful_timing && !header.real_timestamps {
+        eprintln!("{path} has made-up timestamps (older file or rows added in the editor); using row waits instead of --faithful.");
+    }
+    let options = PlaybackOptions {
+        faithful_timing: options.faithful_timing && header.real_timestamps,
+        ..options
+    };
+
+    let mut prev_pos = None;
+    let labels: Vec<String> = events
+        .iter()
+        .map(|ev| {
+            let (action, value, next_pos) = format_event_with_prev(ev, prev_pos);
+            prev_pos = next_pos;
+            format!("{action}  {value}")
+        })
+        .collect();
+    let total = events.len();
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    let progress = Arc::new(AtomicUsize::new(usize::MAX));
+
+    let handler_cancel = cancel.clone();
+    if let Err(err) = ctrlc::set_handler(move || handler_cancel.store(true, Ordering::Relaxed)) {
+        eprintln!("Ctrl-C handler unavailable: {err}");
+    }
+
+    let current_screen = screen_layout();
+    let recorded_screen = header.recording_screen(current_screen, &monitors());
+    let remap = match (remap, current_screen.or(recorded_screen)) {
+        ((RemapMode::Off, _), _) => None,
+        ((mode, offset), Some(to)) => Some(ScreenRemap {
+            from: recorded_screen.unwrap_or(to),
+            to,
+            mode,
+            offset,
+        }),
+        (_, None) => {
+            eprintln!("Screen size unknown; playing positions as recorded.");
+            None
+        }
+    };
+    if let (Some(recorded), Some(current), None) = (recorded_screen, current_screen, &remap) {
+        if recorded != current {
+            eprintln!("Recorded on a {recorded} screen, this one is {current}; see --remap.");
+        }
+    }
+
+    let options = PlaybackOptions {
+        capture_dir: Some(failure_capture_dir(path)),
+        remap,
+        ..options
+    };
+
+    println!("Playing {total} rows from {path}");
+
+    let worker = {
+        let cancel = cancel.clone();
+        let progress = progress.clone();
+        std::thread::spawn(move || playback(events, options, cancel, Arc::default(), progress))
+    };
+
+    // Rows can finish between polls, so report every index up to the current one.
+    // A lower index means a repeat block started its next iteration.
+    let mut next_to_report = 0usize;
+    let mut report_up_to = |index: usize| {
+        if index < next_to_report {
+            next_to_report = index;
+        }
+        while next_to_report <= index.min(total - 1) {
+            println!("[row {}] {}", next_to_report, labels[next_to_report]);
+            next_to_report += 1;
+        }
+    };
+
+    while !worker.is_finished() {
+        let index = progress.load(Ordering::Relaxed);
+        if index != usize::MAX {
+            report_up_to(index);
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+
+    let report = worker.join().unwrap_or_else(|_| PlaybackReport {
+        error: Some("Playback thread panicked".to_string()),
+        ..PlaybackReport::started_now()
+    });
+    let index = progress.load(Ordering::Relaxed);
+
+    if let Some(report_path) = report_path {
+        let suite = std::path::Path::new(path)
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "playback".to_string());
+        match save_report(&report, report_path, &suite) {
+            Ok(msg) => println!("{msg}"),
+            Err(err) => eprintln!("Could not write report: {err}"),
+        }
+    }
+
+    // The row that stopped the run; cancelling between rows leaves only the
+    // progress index.
+    let stopped_at = report.failed_row().or((index != usize::MAX).then_some(index));
+    match stopped_at {
+        Some(row) => report_up_to(row),
+        None if report.error.is_none() => report_up_to(total - 1),
+        None => {}
+    }
+    let (code, message) = outcome(&report, stopped_at, cancel.load(Ordering::Relaxed));
+    if code == 0 {
+        println!("{message}");
+    } else {
+        eprintln!("{message}");
+    }
+    code
+}
+
+/// Exit code and closing message for a finished run.
+fn outcome(report: &PlaybackReport, stopped_at: Option<usize>, cancelled: bool) -> (i32, String) {
+    let at_row = stopped_at.map(|row| format!(" at row {row}")).unwrap_or_default();
+    match &report.error {
+        None => {
+            let details = report.describe();
+            if details.is_empty() {
+                (0, "Playback finished.".to_string())
+            } else {
+                (0, format!("Playback finished.\n{details}"))
+            }
+        }
+        Some(_) if cancelled => (130, format!("Playback cancelled{at_row}.")),
+        Some(err) => (1, format!("Playback failed{at_row}: {err}")),
+    }
+}
+
+// The release build uses the GUI subsystem on Windows, which starts without a
+// console; borrow the one from the invoking shell so output is visible.
+fn attach_parent_console() {
+    #[cfg(windows)]
+    unsafe {
+        use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::test_support::row_report;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn play_arguments_fill_in_the_options() {
+        let full = args("--speed 2x --faithful --on-error retry:3:250 --report out.xml --remap offset:-5,10 run.yaml");
+        let (path, options, report_path, remap) = parse_play_args(&full).unwrap();
+
+        assert_eq!(path, "run.yaml");
+        assert_eq!(options.speed, 2.0);
+        assert!(options.faithful_timing);
+        assert_eq!(options.on_error, OnError::Retry { times: 3, backoff_ms: 250 });
+        assert_eq!(report_path, Some("out.xml"));
+        assert_eq!(remap, (RemapMode::Offset, (-5, 10)));
+
+        let bare = args("run.yaml");
+        let (_, options, report_path, remap) = parse_play_args(&bare).unwrap();
+        assert_eq!(options.speed, 1.0);
+        assert!(!options.faithful_timing);
+        assert_eq!(options.on_error, OnError::Abort);
+        assert_eq!(report_path, None);
+        assert_eq!(remap, (RemapMode::Off, (0, 0)));
+    }
+
+    #[test]
+    fn bad_play_arguments_are_rejected() {
+        for line in [
+            "",
+            "--speed 20 run.yaml",
+            "--speed fast run.yaml",
+            "--speed",
+            "--on-error retry:0 run.yaml",
+            "--on-error goto: run.yaml",
+            "--on-error ignore run.yaml",
+            "--remap offset:1 run.yaml",
+            "--remap sideways run.yaml",
+            "--verbose run.yaml",
+            "one.yaml two.yaml",
+        ] {
+            assert!(parse_play_args(&args(line)).is_err(), "{line:?} was accepted");
+        }
+    }
+
+    #[test]
+    fn on_error_policies_parse() {
+        assert_eq!(parse_on_error("abort"), Ok(OnError::Abort));
+        assert_eq!(parse_on_error("skip"), Ok(OnError::Skip));
+        assert_eq!(parse_on_error("retry:2"), Ok(OnError::Retry { times: 2, backoff_ms: 500 }));
+        assert_eq!(parse_on_error("goto:cleanup"), Ok(OnError::GotoLabel("cleanup".to_string())));
+        assert!(parse_on_error("skip:1").is_err());
+        assert!(parse_on_error("retry:2:soon").is_err());
+    }
+
+    #[test]
+    fn commands_return_their_exit_codes() {
+        let no_play = |_: &str, _: PlaybackOptions, _: Option<&str>, _: RemapArg| -> i32 { panic!("played") };
+        assert_eq!(run(&[], no_play), None);
+        assert_eq!(run(&args("--help"), no_play), Some(0));
+        assert_eq!(run(&args("replay run.yaml"), no_play), Some(2));
+        assert_eq!(run(&args("play --speed 0 run.yaml"), no_play), Some(2));
+        assert_eq!(run(&args("play"), no_play), Some(2));
+
+        let mut played = None;
+        let code = run(&args("play --on-error skip run.yaml"), |path, options, _, _| {
+            played = Some((path.to_string(), options.on_error));
+            1
+        });
+        assert_eq!(code, Some(1));
+        assert_eq!(played, Some(("run.yaml".to_string(), OnError::Skip)));
+    }
+
+    #[test]
+    fn outcome_names_the_row_from_the_report() {
+        let passed = PlaybackReport {
+            rows: vec![row_report(0, RowOutcome::Passed), row_report(1, RowOutcome::Passed)],
+            ..PlaybackReport::default()
+        };
+        assert_eq!(outcome(&passed, None, false), (0, "Playback finished.".to_string()));
+
+        let mut failed = passed.clone();
+        failed.rows.push(row_report(4, RowOutcome::Failed));
+        failed.error = Some("Target not found".to_string());
+        assert_eq!(failed.failed_row(), Some(4));
+        assert_eq!(
+            outcome(&failed, failed.failed_row(), false),
+            (1, "Playback failed at row 4: Target not found".to_string())
+        );
+
+        let cancelled = PlaybackReport {
+            error: Some("Cancelled".to_string()),
+            ..passed.clone()
+        };
+        assert_eq!(cancelled.failed_row(), None);
+        assert_eq!(outcome(&cancelled, Some(2), true), (130, "Playback cancelled at row 2.".to_string()));
+
+        let invalid = PlaybackReport {
+            error: Some("row 3: Else has no matching IfImage".to_string()),
+            ..PlaybackReport::default()
+        };
+        assert_eq!(
+            outcome(&invalid, invalid.failed_row(), false),
+            (1, "Playback failed: row 3: Else has no matching IfImage".to_string())
+        );
+    }
+}
@@ -7,6 +7,74 @@ mod runtime;
 
 impl App {
     pub(crate) fn update(&mut self, message: Message) -> Task<Message> {
+        let before = match self.edit_label(&message) {
+            Some(label) if self.update_depth == 0 => Some(EditSnapshot {
+                label,
+                events: self.events.clone(),
+                selected_index: self.selected_index,
+            }),
+            _ => None,
+        };
+        // Rows from a recording or a file bring their own timestamps.
+        let edits_by_hand = !matches!(
+            message,
+            Message::StartRecording | Message::FileOpFinished(Ok(FileOpResult::Loaded(..)))
+        );
+
+        self.update_depth += 1;
+        let task = self.dispatch(message);
+        self.update_depth -= 1;
+
+        if let Some(before) = before {
+            self.selection_anchor = None;
+            if before.events != self.events {
+                // New rows get made-up timestamps from the rows around them.
+                if edits_by_hand && self.events.len() > before.events.len() {
+                    self.recording_real_timestamps = false;
+                }
+                self.edit_history.record(before);
+            }
+        }
+
+        task
+    }
+
+    /// Undo-history label for messages that may change the event list.
+    fn edit_label(&self, message: &Message) -> Option<String> {
+        let label = match message {
+            Message::EditorInsertOrApply => match self.selected_index {
+                Some(index) => format!("apply to row {index}"),
+                None => "insert click row".to_string(),
+            },
+            Message::EditorInsertBelowSelected => match self.selected_index {
+                Some(index) => format!("insert below row {index}"),
+                None => return None,
+            },
+            Message::EditorInsertOrApplyKey => "key row edit".to_string(),
+            Message::EditorInsertOrApplyText => "text row edit".to_string(),
+            Message::EditorInsertOrApplyRepeat => "repeat block edit".to_string(),
+            Message::EditorInsertOrApplyIfImage => "image condition edit".to_string(),
+            Message::EditorInsertOrApplyWaitImage => "image wait edit".to_string(),
+            Message::EditorInsertOrApplyLabel => "label edit".to_string(),
+            Message::EditorApplyOnError => "failure policy edit".to_string(),
+            Message::EditorInsertOrApplyWindow => "window block edit".to_string(),
+            Message::RowClone(index) => format!("clone row {index}"),
+            Message::RowDelete(index) => format!("delete row {index}"),
+            Message::ClearSelection => match self.selected_index {
+                Some(index) => format!("delete row {index}"),
+                None => return None,
+            },
+            Message::Clear => "clear all rows".to_string(),
+            Message::WaitOk => "add wait row".to_string(),
+            Message::FindTargetOk => "add find-target row".to_string(),
+            Message::StartRecording => "new recording".to_string(),
+            Message::FileOpFinished(Ok(FileOpResult::Loaded(..))) => "load file".to_string(),
+            _ => return None,
+        };
+        Some(label)
+    }
+
+    fn dispatch(&mut self, message: Message) -> Task<Message> {
         let message = match self.handle_editor_message(message) {
             Ok(task) => return task,
             Err(message) => message,
@@ -43,6 +111,21 @@ impl App {
         let resized = iced::window::resize_events()
             .map(|(_id, size)| Message::WindowResized(size.width, size.height));
 
-        Subscription::batch(vec![record, pos, capture, resized])
+        let shortcuts = iced::keyboard::listen().filter_map(|event| match event {
+            iced::keyboard::Event::KeyPressed {
+                key: iced::keyboard::Key::Character(c),
+                modifiers,
+                ..
+            } if modifiers.command() => match c.as_str() {
+                "z" if modifiers.shift() => Some(Message::Redo),
+                "z" => Some(Message::Undo),
+                "y" => Some(Message::Redo),
+                _ => None,
+            },
+            iced::keyboard::Event::ModifiersChanged(modifiers) => Some(Message::ModifiersChanged(modifiers)),
+            _ => None,
+        });
+
+        Subscription::batch(vec![record, pos, capture, resized, shortcuts])
     }
 }
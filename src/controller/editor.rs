@@ -80,6 +80,66 @@ impl App {
                 self.editor_use_find_image = enabled;
                 Ok(Task::none())
             }
+            Message::EditorTargetOffsetXChanged(txt) => {
+                self.editor_target_offset_x_text = txt;
+                Ok(Task::none())
+            }
+            Message::EditorTargetOffsetYChanged(txt) => {
+                self.editor_target_offset_y_text = txt;
+                Ok(Task::none())
+            }
+            Message::EditorScaleSweepToggled(enabled) => {
+                self.editor_scale_sweep_enabled = enabled;
+                Ok(Task::none())
+            }
+            Message::EditorScaleMinChanged(txt) => {
+                self.editor_scale_min_text = txt;
+                Ok(Task::none())
+            }
+            Message::EditorScaleMaxChanged(txt) => {
+                self.editor_scale_max_text = txt;
+                Ok(Task::none())
+            }
+            Message::EditorScaleStepChanged(txt) => {
+                self.editor_scale_step_text = txt;
+                Ok(Task::none())
+            }
+            Message::EditorMatchFilterSelected(filter) => {
+                self.editor_match_filter = filter;
+                Ok(Task::none())
+            }
+            Message::EditorSearchMonitorSelected(monitor) => {
+                self.editor_search_monitor = monitor;
+                Ok(Task::none())
+            }
+            Message::EditorPreviewHovered(point) => {
+                self.editor_preview_hover = Some(point);
+                Ok(Task::none())
+            }
+            Message::EditorPickTargetOffset => {
+                let (Some(point), Some((w, h))) = (self.editor_preview_hover, self.preview_patch_size()) else {
+                    return Ok(Task::none());
+                };
+                let dx = (point.x.clamp(0.0, 1.0) * w as f32 - w as f32 / 2.0).round() as i32;
+                let dy = (point.y.clamp(0.0, 1.0) * h as f32 - h as f32 / 2.0).round() as i32;
+                self.editor_target_offset_x_text = dx.to_string();
+                self.editor_target_offset_y_text = dy.to_string();
+                self.status = format!("Click offset set to ({dx},{dy}) from the match center; apply to keep it.");
+                Ok(Task::none())
+            }
+            Message::EditorMatchStrategySelected(strategy) => {
+                self.editor_match_strategy = strategy;
+                Ok(Task::none())
+            }
+            Message::EditorMatchNthChanged(txt) => {
+                if let Ok(n) = txt.trim().parse::<u32>() {
+                    if matches!(self.editor_match_strategy, MatchStrategy::Nth(_)) {
+                        self.editor_match_strategy = MatchStrategy::Nth(n.max(1));
+                    }
+                }
+                self.editor_match_nth_text = txt;
+                Ok(Task::none())
+            }
             Message::EditorStartGetXY => {
                 if self.mode != Mode::Idle {
                     self.status = "Stop recording or playback first.".to_string();
@@ -92,7 +152,7 @@ impl App {
                     self.editor_static_preview_patch_b64 = capture_patch_png_base64(x, y, 128).ok();
                 }
 
-                #[cfg(windows)]
+                #[cfg(any(windows, target_os = "linux"))]
                 {
                     if let Err(err) = ensure_get_capture_hook_thread() {
                         self.status = format!("GET capture failed: {err}");
@@ -105,9 +165,9 @@ impl App {
                     self.status = "GET (X,Y) armed: click Left, Right, or Middle to set coordinates (ESC cancels).".to_string();
                 }
 
-                #[cfg(not(windows))]
+                #[cfg(not(any(windows, target_os = "linux")))]
                 {
-                    self.status = "GET (X,Y) capture is currently Windows-only.".to_string();
+                    self.status = "GET (X,Y) capture is not supported on this platform.".to_string();
                 }
 
                 Ok(Task::none())
@@ -227,18 +287,18 @@ impl App {
 
                     let replacement_kind = {
                         let kind_matches_selected = |candidate: &RecordedEventKind| -> bool {
-                            match (selected_kind.as_ref(), candidate) {
-                                (Some(RecordedEventKind::LeftDown { .. }), RecordedEventKind::LeftDown { .. }) => true,
-                                (Some(RecordedEventKind::LeftUp { .. }), RecordedEventKind::LeftUp { .. }) => true,
-                                (Some(RecordedEventKind::LeftClick { .. }), RecordedEventKind::LeftClick { .. }) => true,
-                                (Some(RecordedEventKind::RightDown { .. }), RecordedEventKind::RightDown { .. }) => true,
-                                (Some(RecordedEventKind::RightUp { .. }), RecordedEventKind::RightUp { .. }) => true,
-                                (Some(RecordedEventKind::RightClick { .. }), RecordedEventKind::RightClick { .. }) => true,
-                                (Some(RecordedEventKind::MiddleDown { .. }), RecordedEventKind::MiddleDown { .. }) => true,
-                                (Some(RecordedEventKind::MiddleUp { .. }), RecordedEventKind::MiddleUp { .. }) => true,
-                                (Some(RecordedEventKind::MiddleClick { .. }), RecordedEventKind::MiddleClick { .. }) => true,
-                                _ => false,
-                            }
+                            matches!(
+                                (selected_kind.as_ref(), candidate),
+                                (Some(RecordedEventKind::LeftDown { .. }), RecordedEventKind::LeftDown { .. })
+                                    | (Some(RecordedEventKind::LeftUp { .. }), RecordedEventKind::LeftUp { .. })
+                                    | (Some(RecordedEventKind::LeftClick { .. }), RecordedEventKind::LeftClick { .. })
+                                    | (Some(RecordedEventKind::RightDown { .. }), RecordedEventKind::RightDown { .. })
+                                    | (Some(RecordedEventKind::RightUp { .. }), RecordedEventKind::RightUp { .. })
+                                    | (Some(RecordedEventKind::RightClick { .. }), RecordedEventKind::RightClick { .. })
+                                    | (Some(RecordedEventKind::MiddleDown { .. }), RecordedEventKind::MiddleDown { .. })
+                                    | (Some(RecordedEventKind::MiddleUp { .. }), RecordedEventKind::MiddleUp { .. })
+                                    | (Some(RecordedEventKind::MiddleClick { .. }), RecordedEventKind::MiddleClick { .. })
+                            )
                         };
 
                         kinds
@@ -304,6 +364,8 @@ impl App {
                             kind,
                             pos: Some((x, y)),
                             click_meta: click_meta.clone(),
+                            on_error: None,
+                            monitor: None,
                         });
                     }
                     self.selected_index = Some(insert_at);
@@ -361,6 +423,8 @@ impl App {
                             kind,
                             pos: Some((x, y)),
                             click_meta: click_meta.clone(),
+                            on_error: None,
+                            monitor: None,
                         },
                     );
                 }
@@ -369,6 +433,453 @@ impl App {
                 self.status = format!("Inserted {} row(s) below selected (multi-row)", inserted);
                 Ok(Task::none())
             }
+            Message::EditorKeyChanged(key) => {
+                self.editor_key_text = key;
+                Ok(Task::none())
+            }
+            Message::EditorKeyModifiersChanged(modifiers) => {
+                self.editor_key_modifiers = modifiers;
+                Ok(Task::none())
+            }
+            Message::EditorTypeTextChanged(text) => {
+                self.editor_type_text = text;
+                Ok(Task::none())
+            }
+            Message::EditorInsertOrApplyKey => {
+                if self.mode != Mode::Idle {
+                    self.status = "Stop recording or playback first.".to_string();
+                    return Ok(Task::none());
+                }
+
+                let key = self.editor_key_text.trim().to_lowercase();
+                if key.is_empty() {
+                    self.status = "Enter a key name first.".to_string();
+                    return Ok(Task::none());
+                }
+                let modifiers = self.editor_key_modifiers;
+                let click_meta = Some(self.plain_row_meta());
+
+                if let Some(index) = self.selected_index {
+                    if let Some(cur) = self.events.get_mut(index) {
+                        if let RecordedEventKind::KeyDown { key: k, modifiers: m }
+                        | RecordedEventKind::KeyUp { key: k, modifiers: m } = &mut cur.kind
+                        {
+                            *k = key;
+                            *m = modifiers;
+                            cur.click_meta = click_meta;
+                            self.status = format!("Updated key at row {}", index);
+                            return Ok(Task::none());
+                        }
+                    }
+                }
+
+                let kinds = vec![
+                    RecordedEventKind::KeyDown { key: key.clone(), modifiers },
+                    RecordedEventKind::KeyUp { key, modifiers },
+                ];
+                let insert_at = self.insert_rows_after_selection(kinds, click_meta);
+                self.status = format!("Inserted key press at row {}", insert_at);
+                Ok(Task::none())
+            }
+            Message::EditorInsertOrApplyText => {
+                if self.mode != Mode::Idle {
+                    self.status = "Stop recording or playback first.".to_string();
+                    return Ok(Task::none());
+                }
+
+                if self.editor_type_text.is_empty() {
+                    self.status = "Enter text to type first.".to_string();
+                    return Ok(Task::none());
+                }
+                let text = self.editor_type_text.clone();
+                let click_meta = Some(self.plain_row_meta());
+
+                if let Some(index) = self.selected_index {
+                    if let Some(cur) = self.events.get_mut(index) {
+                        if let RecordedEventKind::TypeText { text: t } = &mut cur.kind {
+                            *t = text;
+                            cur.click_meta = click_meta;
+                            self.status = format!("Updated text at row {}", index);
+                            return Ok(Task::none());
+                        }
+                    }
+                }
+
+                let insert_at =
+                    self.insert_rows_after_selection(vec![RecordedEventKind::TypeText { text }], click_meta);
+                self.status = format!("Inserted text row at row {}", insert_at);
+                Ok(Task::none())
+            }
+            Message::EditorRepeatCountChanged(value) => {
+                self.editor_repeat_count_text = value;
+                Ok(Task::none())
+            }
+            Message::EditorInsertOrApplyRepeat => {
+                if self.mode != Mode::Idle {
+                    self.status = "Stop recording or playback first.".to_string();
+                    return Ok(Task::none());
+                }
+
+                let Ok(count) = self.editor_repeat_count_text.trim().parse::<u32>() else {
+                    self.status = "Repeat count must be a whole number.".to_string();
+                    return Ok(Task::none());
+                };
+
+                if let Some(index) = self.selected_index {
+                    if let Some(RecordedEventKind::RepeatStart { count: c }) =
+                        self.events.get_mut(index).map(|ev| &mut ev.kind)
+                    {
+                        *c = count;
+                        self.status = format!("Updated repeat count at row {}", index);
+                        return Ok(Task::none());
+                    }
+                }
+
+                self.status = match self.wrap_selection_in_block(
+                    RecordedEventKind::RepeatStart { count },
+                    None,
+                    vec![RecordedEventKind::RepeatEnd],
+                ) {
+                    Ok((first, last)) => format!("Inserted repeat block at rows {first}-{last}"),
+                    Err(err) => err,
+                };
+                Ok(Task::none())
+            }
+            Message::EditorInsertOrApplyIfImage => {
+                if self.mode != Mode::Idle {
+                    self.status = "Stop recording or playback first.".to_string();
+                    return Ok(Task::none());
+                }
+
+                let Some(patch_png_base64) = self.editor_static_preview_patch_b64.clone() else {
+                    self.status = "Press GET (X,Y) to capture the image to look for first.".to_string();
+                    return Ok(Task::none());
+                };
+                let condition = RecordedEventKind::IfImage {
+                    patch_png_base64,
+                    patch_size: 128,
+                    precision: (self.editor_target_precision_percent as f32 / 100.0).clamp(0.5, 1.0),
+                    timeout_ms: self.editor_target_timeout_ms as u64,
+                    search_anchor: SearchAnchor::RecordedClick,
+                    search_region_size: Some(self.find_image_region_size),
+                };
+                let pos = self.parse_editor_xy();
+
+                if let Some(index) = self.selected_index {
+                    if let Some(ev) = self
+                        .events
+                        .get_mut(index)
+                        .filter(|ev| matches!(ev.kind, RecordedEventKind::IfImage { .. }))
+                    {
+                        ev.kind = condition;
+                        ev.pos = pos;
+                        self.status = format!("Updated image condition at row {}", index);
+                        return Ok(Task::none());
+                    }
+                }
+
+                self.status = match self.wrap_selection_in_block(
+                    condition,
+                    pos,
+                    vec![RecordedEventKind::Else, RecordedEventKind::EndIf],
+                ) {
+                    Ok((first, last)) => format!("Inserted IF IMAGE block at rows {first}-{last}"),
+                    Err(err) => err,
+                };
+                Ok(Task::none())
+            }
+            Message::EditorWaitImageGoneToggled(gone) => {
+                self.editor_wait_image_gone = gone;
+                Ok(Task::none())
+            }
+            Message::EditorWaitPollMsChanged(value) => {
+                self.editor_wait_poll_ms_text = value;
+                Ok(Task::none())
+            }
+            Message::EditorOnTimeoutChanged(choice) => {
+                self.editor_on_timeout = choice;
+                Ok(Task::none())
+            }
+            Message::EditorLabelChanged(value) => {
+                self.editor_label_text = value;
+                Ok(Task::none())
+            }
+            Message::EditorInsertOrApplyWaitImage => {
+                if self.mode != Mode::Idle {
+                    self.status = "Stop recording or playback first.".to_string();
+                    return Ok(Task::none());
+                }
+
+                let Some(patch_png_base64) = self.editor_static_preview_patch_b64.clone() else {
+                    self.status = "Press GET (X,Y) to capture the image to wait for first.".to_string();
+                    return Ok(Task::none());
+                };
+                let Ok(poll_ms) = self.editor_wait_poll_ms_text.trim().parse::<u64>() else {
+                    self.status = "Poll interval must be a whole number of milliseconds.".to_string();
+                    return Ok(Task::none());
+                };
+                let on_timeout = match self.editor_on_timeout {
+                    OnTimeoutChoice::Fail => OnTimeout::Fail,
+                    OnTimeoutChoice::Continue => OnTimeout::Continue,
+                    OnTimeoutChoice::GotoLabel => {
+                        let name = self.editor_label_text.trim();
+                        if name.is_empty() {
+                            self.status = "Enter the label to jump to on timeout.".to_string();
+                            return Ok(Task::none());
+                        }
+                        OnTimeout::GotoLabel(name.to_string())
+                    }
+                };
+
+                let precision = (self.editor_target_precision_percent as f32 / 100.0).clamp(0.5, 1.0);
+                let timeout_ms = self.editor_target_timeout_ms as u64;
+                let search_region_size = Some(self.find_image_region_size);
+                let wait = if self.editor_wait_image_gone {
+                    RecordedEventKind::WaitUntilImageGone {
+                        patch_png_base64,
+                        patch_size: 128,
+                        precision,
+                        timeout_ms,
+                        poll_ms,
+                        search_anchor: SearchAnchor::RecordedClick,
+                        search_region_size,
+                        on_timeout,
+                    }
+                } else {
+                    RecordedEventKind::WaitForImage {
+                        patch_png_base64,
+                        patch_size: 128,
+                        precision,
+                        timeout_ms,
+                        poll_ms,
+                        search_anchor: SearchAnchor::RecordedClick,
+                        search_region_size,
+                        on_timeout,
+                    }
+                };
+                let pos = self.parse_editor_xy();
+
+                if let Some(index) = self.selected_index {
+                    if let Some(ev) = self.events.get_mut(index).filter(|ev| {
+                        matches!(
+                            ev.kind,
+                            RecordedEventKind::WaitForImage { .. } | RecordedEventKind::WaitUntilImageGone { .. }
+                        )
+                    }) {
+                        ev.kind = wait;
+                        ev.pos = pos;
+                        self.status = format!("Updated image wait at row {}", index);
+                        return Ok(Task::none());
+                    }
+                }
+
+                let insert_at = self.insert_rows_after_selection(vec![wait], None);
+                self.events[insert_at].pos = pos;
+                self.status = format!("Inserted image wait at row {}", insert_at);
+                Ok(Task::none())
+            }
+            Message::EditorInsertOrApplyLabel => {
+                if self.mode != Mode::Idle {
+                    self.status = "Stop recording or playback first.".to_string();
+                    return Ok(Task::none());
+                }
+
+                let name = self.editor_label_text.trim().to_string();
+                if name.is_empty() {
+                    self.status = "Enter a label name first.".to_string();
+                    return Ok(Task::none());
+                }
+                if self
+                    .events
+                    .iter()
+                    .any(|ev| matches!(&ev.kind, RecordedEventKind::Label { name: n } if *n == name))
+                {
+                    self.status = format!("Label \"{name}\" already exists.");
+                    return Ok(Task::none());
+                }
+
+                let renamed = self.selected_index.and_then(|index| match &mut self.events.get_mut(index)?.kind {
+                    RecordedEventKind::Label { name: old } => Some((index, std::mem::replace(old, name.clone()))),
+                    _ => None,
+                });
+                if let Some((index, old)) = renamed {
+                    // Keep jumps pointing at the renamed label.
+                    for ev in &mut self.events {
+                        if let RecordedEventKind::WaitForImage { on_timeout, .. }
+                        | RecordedEventKind::WaitUntilImageGone { on_timeout, .. } = &mut ev.kind
+                        {
+                            if *on_timeout == OnTimeout::GotoLabel(old.clone()) {
+                                *on_timeout = OnTimeout::GotoLabel(name.clone());
+                            }
+                        }
+                        if ev.on_error == Some(OnError::GotoLabel(old.clone())) {
+                            ev.on_error = Some(OnError::GotoLabel(name.clone()));
+                        }
+                    }
+                    self.status = format!("Renamed label at row {index} to \"{name}\"");
+                    return Ok(Task::none());
+                }
+
+                let insert_at = self.insert_rows_after_selection(vec![RecordedEventKind::Label { name }], None);
+                self.status = format!("Inserted label at row {}", insert_at);
+                Ok(Task::none())
+            }
+            Message::EditorWindowTitleChanged(value) => {
+                self.editor_window_title_text = value;
+                Ok(Task::none())
+            }
+            Message::EditorWindowClassChanged(value) => {
+                self.editor_window_class_text = value;
+                Ok(Task::none())
+            }
+            Message::EditorInsertOrApplyWindow => {
+                if self.mode != Mode::Idle {
+                    self.status = "Stop recording or playback first.".to_string();
+                    return Ok(Task::none());
+                }
+
+                let title = self.editor_window_title_text.trim().to_string();
+                let class = self.editor_window_class_text.trim().to_string();
+                if title.is_empty() && class.is_empty() {
+                    self.status = "Enter a window title or class pattern first.".to_string();
+                    return Ok(Task::none());
+                }
+                if let Err(err) = WindowMatcher::new(&title, &class) {
+                    self.status = err;
+                    return Ok(Task::none());
+                }
+
+                if let Some(index) = self.selected_index {
+                    if let Some(RecordedEventKind::BindWindow { title: t, class: c }) =
+                        self.events.get_mut(index).map(|ev| &mut ev.kind)
+                    {
+                        *t = title;
+                        *c = class;
+                        self.status = format!("Updated window patterns at row {}", index);
+                        return Ok(Task::none());
+                    }
+                }
+
+                // The wrapped rows hold desktop positions; they are made relative
+                // to where the window is now.
+                let window = match find_window(&title, &class) {
+                    Ok(Some(window)) => window,
+                    Ok(None) => {
+                        self.status = format!(
+                            "No open window matches {}; open it so the rows can be made relative to it.",
+                            window_pattern_label(&title, &class)
+                        );
+                        return Ok(Task::none());
+                    }
+                    Err(err) => {
+                        self.status = err;
+                        return Ok(Task::none());
+                    }
+                };
+
+                self.status = match self.wrap_selection_in_block(
+                    RecordedEventKind::BindWindow { title, class },
+                    None,
+                    vec![RecordedEventKind::EndWindow],
+                ) {
+                    Ok((first, last)) => {
+                        let (ox, oy) = window.origin;
+                        let scopes = window_scopes(&self.events);
+                        for (ev, scope) in self.events.iter_mut().zip(scopes) {
+                            if scope == Some(first) {
+                                ev.map_positions(|(x, y)| (x - ox, y - oy));
+                            }
+                        }
+                        format!(
+                            "Bound rows {first}-{last} to \"{}\" (client area at {ox},{oy})",
+                            window.title
+                        )
+                    }
+                    Err(err) => err,
+                };
+                Ok(Task::none())
+            }
+            Message::EditorOnErrorChanged(choice) => {
+                self.editor_on_error = choice;
+                Ok(Task::none())
+            }
+            Message::EditorRetryTimesChanged(value) => {
+                self.editor_retry_times_text = value;
+                Ok(Task::none())
+            }
+            Message::EditorRetryBackoffMsChanged(value) => {
+                self.editor_retry_backoff_ms_text = value;
+                Ok(Task::none())
+            }
+            Message::EditorApplyOnError => {
+                if self.mode != Mode::Idle {
+                    self.status = "Stop recording or playback first.".to_string();
+                    return Ok(Task::none());
+                }
+
+                let Some((first, last)) = self.selected_range() else {
+                    self.status = "Select the rows to set the failure policy for first.".to_string();
+                    return Ok(Task::none());
+                };
+                let on_error = match self.parse_on_error(
+                    self.editor_on_error,
+                    &self.editor_retry_times_text,
+                    &self.editor_retry_backoff_ms_text,
+                ) {
+                    Ok(on_error) => on_error,
+                    Err(err) => {
+                        self.status = err;
+                        return Ok(Task::none());
+                    }
+                };
+
+                let policy = on_error
+                    .as_ref()
+                    .map_or("the run default".to_string(), |on_error| on_error.label());
+                for ev in &mut self.events[first..=last] {
+                    ev.on_error = on_error.clone();
+                }
+                self.status = if first == last {
+                    format!("Row {first} now uses {policy} on error")
+                } else {
+                    format!("Rows {first}-{last} now use {policy} on error")
+                };
+                Ok(Task::none())
+            }
+            Message::ModifiersChanged(modifiers) => {
+                self.keyboard_modifiers = modifiers;
+                Ok(Task::none())
+            }
+            Message::Undo | Message::Redo => {
+                if self.mode != Mode::Idle {
+                    self.status = "Stop recording or playback first.".to_string();
+                    return Ok(Task::none());
+                }
+
+                let is_undo = matches!(message, Message::Undo);
+                let current = EditSnapshot {
+                    label: String::new(),
+                    events: std::mem::take(&mut self.events),
+                    selected_index: self.selected_index,
+                };
+                let restored = if is_undo {
+                    self.edit_history.undo(current.clone())
+                } else {
+                    self.edit_history.redo(current.clone())
+                };
+
+                let Some(restored) = restored else {
+                    self.events = current.events;
+                    self.status = if is_undo { "Nothing to undo." } else { "Nothing to redo." }.to_string();
+                    return Ok(Task::none());
+                };
+
+                self.events = restored.events;
+                self.selected_index = restored.selected_index.filter(|&i| i < self.events.len());
+                self.selection_anchor = None;
+                self.status = format!("{}: {}", if is_undo { "Undid" } else { "Redid" }, restored.label);
+                Ok(Task::none())
+            }
             Message::RowJump(index) => {
                 if self.mode != Mode::Idle {
                     self.status = "Stop recording or playback first.".to_string();
@@ -409,10 +920,18 @@ impl App {
                 Ok(self.update(Message::ClearSelection))
             }
             Message::SelectRow(index) => {
+                if !self.keyboard_modifiers.shift() || self.selected_index.is_none() {
+                    self.selection_anchor = Some(index);
+                } else if self.selection_anchor.is_none() {
+                    self.selection_anchor = self.selected_index;
+                }
                 self.selected_index = Some(index);
                 if let Some(ev) = self.events.get(index) {
                     let event_patch_b64: Option<String> = match &ev.kind {
-                        RecordedEventKind::FindTarget { patch_png_base64, .. } => {
+                        RecordedEventKind::FindTarget { patch_png_base64, .. }
+                        | RecordedEventKind::IfImage { patch_png_base64, .. }
+                        | RecordedEventKind::WaitForImage { patch_png_base64, .. }
+                        | RecordedEventKind::WaitUntilImageGone { patch_png_base64, .. } => {
                             Some(patch_png_base64.clone())
                         }
                         RecordedEventKind::LeftDown { patch_png_base64 }
@@ -545,12 +1064,105 @@ impl App {
                         self.editor_target_timeout_ms = meta.target_timeout_ms.clamp(200, 10000) as u16;
                         self.selected_wait_ms_text = meta.wait_ms.to_string();
                         self.editor_use_find_image = meta.use_find_image;
+                        self.editor_match_strategy = meta.match_strategy;
+                        self.editor_target_offset_x_text = meta.target_offset.0.to_string();
+                        self.editor_target_offset_y_text = meta.target_offset.1.to_string();
+                        if let MatchStrategy::Nth(n) = meta.match_strategy {
+                            self.editor_match_nth_text = n.to_string();
+                        }
+                        self.editor_match_filter = meta.match_filter;
+                        self.editor_search_monitor = meta.search_monitor;
+                        self.editor_scale_sweep_enabled = meta.scale_sweep.is_some();
+                        if let Some(sweep) = meta.scale_sweep {
+                            self.editor_scale_min_text = sweep.min.to_string();
+                            self.editor_scale_max_text = sweep.max.to_string();
+                            self.editor_scale_step_text = sweep.step.to_string();
+                        }
                     }
 
+                    self.editor_on_error = match &ev.on_error {
+                        None => OnErrorChoice::RunDefault,
+                        Some(OnError::Abort) => OnErrorChoice::Abort,
+                        Some(OnError::Skip) => OnErrorChoice::Skip,
+                        Some(OnError::Retry { times, backoff_ms }) => {
+                            self.editor_retry_times_text = times.to_string();
+                            self.editor_retry_backoff_ms_text = backoff_ms.to_string();
+                            OnErrorChoice::Retry
+                        }
+                        Some(OnError::GotoLabel(name)) => {
+                            self.editor_label_text = name.clone();
+                            OnErrorChoice::GotoLabel
+                        }
+                    };
+
                     match &ev.kind {
                         RecordedEventKind::Wait { ms } => {
                             self.selected_wait_ms_text = ms.to_string();
                         }
+                        RecordedEventKind::KeyDown { key, modifiers }
+                        | RecordedEventKind::KeyUp { key, modifiers } => {
+                            self.editor_key_text = key.clone();
+                            self.editor_key_modifiers = *modifiers;
+                        }
+                        RecordedEventKind::TypeText { text } => {
+                            self.editor_type_text = text.clone();
+                        }
+                        RecordedEventKind::RepeatStart { count } => {
+                            self.editor_repeat_count_text = count.to_string();
+                        }
+                        RecordedEventKind::IfImage {
+                            precision,
+                            timeout_ms,
+                            ..
+                        } => {
+                            self.editor_target_precision_percent =
+                                (precision.clamp(0.5, 1.0) * 100.0).round() as u16;
+                            self.editor_target_timeout_ms = (*timeout_ms).clamp(200, 10000) as u16;
+                            if let Some((x, y)) = ev.pos {
+                                self.editor_x_text = x.to_string();
+                                self.editor_y_text = y.to_string();
+                            }
+                        }
+                        RecordedEventKind::WaitForImage {
+                            precision,
+                            timeout_ms,
+                            poll_ms,
+                            on_timeout,
+                            ..
+                        }
+                        | RecordedEventKind::WaitUntilImageGone {
+                            precision,
+                            timeout_ms,
+                            poll_ms,
+                            on_timeout,
+                            ..
+                        } => {
+                            self.editor_wait_image_gone =
+                                matches!(ev.kind, RecordedEventKind::WaitUntilImageGone { .. });
+                            self.editor_target_precision_percent =
+                                (precision.clamp(0.5, 1.0) * 100.0).round() as u16;
+                            self.editor_target_timeout_ms = (*timeout_ms).clamp(200, 10000) as u16;
+                            self.editor_wait_poll_ms_text = poll_ms.to_string();
+                            self.editor_on_timeout = match on_timeout {
+                                OnTimeout::Fail => OnTimeoutChoice::Fail,
+                                OnTimeout::Continue => OnTimeoutChoice::Continue,
+                                OnTimeout::GotoLabel(name) => {
+                                    self.editor_label_text = name.clone();
+                                    OnTimeoutChoice::GotoLabel
+                                }
+                            };
+                            if let Some((x, y)) = ev.pos {
+                                self.editor_x_text = x.to_string();
+                                self.editor_y_text = y.to_string();
+                            }
+                        }
+                        RecordedEventKind::Label { name } => {
+                            self.editor_label_text = name.clone();
+                        }
+                        RecordedEventKind::BindWindow { title, class } => {
+                            self.editor_window_title_text = title.clone();
+                            self.editor_window_class_text = class.clone();
+                        }
                         RecordedEventKind::FindTarget {
                             precision,
                             timeout_ms,
@@ -34,6 +34,8 @@ impl App {
                             kind: RecordedEventKind::Moves { points },
                             pos: last_pos,
                             click_meta: move_meta,
+                            on_error: None,
+                            monitor: None,
                         });
                         row_map.push(j - 1);
                     } else {
@@ -54,17 +56,286 @@ impl App {
         (out, row_map)
     }
 
+    /// Faithful timing as picked, unless the rows carry made-up timestamps;
+    /// those play with their row waits instead.
+    pub(super) fn playback_faithful_timing(&self) -> bool {
+        self.playback_faithful_timing && self.recording_real_timestamps
+    }
+
+    /// Rows as handed to playback. MOVES groups are merged unless faithful timing
+    /// needs every move's own timestamp.
+    pub(super) fn playback_events_with_row_map(&self) -> (Vec<RecordedEvent>, Vec<usize>) {
+        if self.playback_faithful_timing() {
+            (self.events.clone(), (0..self.events.len()).collect())
+        } else {
+            self.materialize_moves_grouped_events_with_row_map()
+        }
+    }
+
+    /// Materialized rows whose source rows fall in `first..=last`, preceded by a
+    /// move to the last known cursor position before them.
+    pub(super) fn materialize_playback_range(
+        &self,
+        first: usize,
+        last: usize,
+    ) -> (Vec<RecordedEvent>, Vec<usize>) {
+        let (events, row_map) = self.playback_events_with_row_map();
+        let start = row_map
+            .iter()
+            .position(|&row| row >= first)
+            .unwrap_or(row_map.len());
+        let end = row_map
+            .iter()
+            .rposition(|&row| row <= last)
+            .map_or(start, |i| i + 1)
+            .max(start);
+        if start == end {
+            return (Vec::new(), Vec::new());
+        }
+
+        let mut out: Vec<RecordedEvent> = Vec::with_capacity(end - start + 1);
+        let mut out_map: Vec<usize> = Vec::with_capacity(end - start + 1);
+
+        // Window blocks around the range are reopened so its rows stay relative
+        // to their window; the cursor move only applies within the same window.
+        let scopes = window_scopes(&events);
+        let mut window_openers = Vec::new();
+        let mut scope = scopes[start];
+        while let Some(bind) = scope {
+            window_openers.insert(0, events[bind].clone());
+            scope = scopes[bind];
+        }
+        for opener in window_openers {
+            out.push(RecordedEvent {
+                ms_from_start: events[start].ms_from_start,
+                ..opener
+            });
+            out_map.push(row_map[start]);
+        }
+
+        let last_pos = events[..start]
+            .iter()
+            .enumerate()
+            .rev()
+            .find_map(|(index, ev)| match &ev.kind {
+                RecordedEventKind::Move { x, y } => Some(((*x, *y), index)),
+                RecordedEventKind::Moves { points } => points.last().map(|&point| (point, index)),
+                _ => ev.pos.map(|pos| (pos, index)),
+            })
+            .filter(|&(_, index)| scopes[index] == scopes[start])
+            .map(|(pos, _)| pos);
+        if let Some((x, y)) = last_pos {
+            out.push(RecordedEvent {
+                ms_from_start: events[start].ms_from_start,
+                kind: RecordedEventKind::Move { x, y },
+                pos: Some((x, y)),
+                click_meta: None,
+                on_error: None,
+                monitor: None,
+            });
+            out_map.push(row_map[start]);
+        }
+
+        out.extend_from_slice(&events[start..end]);
+        out_map.extend_from_slice(&row_map[start..end]);
+
+        // The range can cut through blocks. Closers whose opener is before the
+        // range are dropped, an orphan `Else` skips the rest of its branch (we
+        // started inside the taken one) and unclosed openers get a closer at the end.
+        // Closer of each open block
+        let mut keep = vec![true; out.len()];
+        let mut open: Vec<RecordedEventKind> = Vec::new();
+        let mut skip_depth: Option<usize> = None;
+        for (index, ev) in out.iter().enumerate() {
+            if let Some(depth) = skip_depth.as_mut() {
+                keep[index] = false;
+                match ev.kind {
+                    RecordedEventKind::RepeatStart { .. }
+                    | RecordedEventKind::IfImage { .. }
+                    | RecordedEventKind::BindWindow { .. } => *depth += 1,
+                    RecordedEventKind::RepeatEnd | RecordedEventKind::EndIf | RecordedEventKind::EndWindow
+                        if *depth > 0 =>
+                    {
+                        *depth -= 1
+                    }
+                    RecordedEventKind::EndIf => skip_depth = None,
+                    _ => {}
+                }
+                continue;
+            }
+            match ev.kind {
+                RecordedEventKind::RepeatStart { .. } => open.push(RecordedEventKind::RepeatEnd),
+                RecordedEventKind::IfImage { .. } => open.push(RecordedEventKind::EndIf),
+                RecordedEventKind::BindWindow { .. } => open.push(RecordedEventKind::EndWindow),
+                RecordedEventKind::RepeatEnd | RecordedEventKind::EndIf | RecordedEventKind::EndWindow => {
+                    keep[index] = open.pop().is_some()
+                }
+                RecordedEventKind::Else if open.last() != Some(&RecordedEventKind::EndIf) => {
+                    keep[index] = false;
+                    skip_depth = Some(0);
+                }
+                _ => {}
+            }
+        }
+        let mut keep_iter = keep.iter();
+        out.retain(|_| *keep_iter.next().unwrap_or(&true));
+        let mut keep_iter = keep.iter();
+        out_map.retain(|_| *keep_iter.next().unwrap_or(&true));
+
+        let closer_row = row_map[end - 1];
+        for closer in open.into_iter().rev() {
+            out.push(RecordedEvent {
+                ms_from_start: events[end - 1].ms_from_start,
+                kind: closer,
+                pos: None,
+                click_meta: None,
+                on_error: None,
+                monitor: None,
+            });
+            out_map.push(closer_row);
+        }
+
+        // A jump to a label outside the range cannot be followed; fail instead.
+        let labels: Vec<String> = out
+            .iter()
+            .filter_map(|ev| match &ev.kind {
+                RecordedEventKind::Label { name } => Some(name.clone()),
+                _ => None,
+            })
+            .collect();
+        for ev in &mut out {
+            if let RecordedEventKind::WaitForImage { on_timeout, .. }
+            | RecordedEventKind::WaitUntilImageGone { on_timeout, .. } = &mut ev.kind
+            {
+                if matches!(on_timeout, OnTimeout::GotoLabel(name) if !labels.contains(name)) {
+                    *on_timeout = OnTimeout::Fail;
+                }
+            }
+            if matches!(&ev.on_error, Some(OnError::GotoLabel(name)) if !labels.contains(name)) {
+                ev.on_error = Some(OnError::Abort);
+            }
+        }
+
+        (out, out_map)
+    }
+
+    /// Selected row plus the shift-click anchor, as an inclusive `(first, last)`.
+    pub(crate) fn selected_range(&self) -> Option<(usize, usize)> {
+        let selected = self.selected_index.filter(|&i| i < self.events.len())?;
+        let anchor = self
+            .selection_anchor
+            .filter(|&i| i < self.events.len())
+            .unwrap_or(selected);
+        Some((selected.min(anchor), selected.max(anchor)))
+    }
+
+    pub(super) fn start_playback(
+        &mut self,
+        events: Vec<RecordedEvent>,
+        row_map: Vec<usize>,
+        step: bool,
+    ) -> Task<Message> {
+        let on_error = match block_jumps(&self.events)
+            .and_then(|_| label_rows(&self.events))
+            .and_then(|_| {
+                self.parse_on_error(
+                    self.playback_on_error,
+                    &self.playback_retry_times_text,
+                    &self.playback_retry_backoff_ms_text,
+                )
+            }) {
+            Ok(on_error) => on_error.unwrap_or_default(),
+            Err(err) => {
+                self.status = format!("Cannot play: {err}");
+                return Task::none();
+            }
+        };
+
+        let options = PlaybackOptions {
+            speed: self.playback_speed_percent as f32 / 100.0,
+            faithful_timing: self.playback_faithful_timing(),
+            on_error,
+            capture_dir: Some(failure_capture_dir(&self.file_path)),
+            source_rows: row_map.clone(),
+            remap: self.playback_screen_remap(),
+        };
+        let pause = Arc::new(PlaybackPause::default());
+        if step {
+            pause.step();
+            self.status = "Stepping (materialized MOVES)...".to_string();
+        } else {
+            self.status = "Playing (materialized MOVES)...".to_string();
+        }
+        self.playback_pause = Some(pause.clone());
+
+        self.mode = Mode::Playing;
+        self.playback_active_index = None;
+        self.playback_last_scrolled_index = None;
+
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.playback_cancel = Some(cancel.clone());
+
+        let progress = Arc::new(AtomicUsize::new(usize::MAX));
+        self.playback_progress = Some(progress.clone());
+
+        self.playback_progress_row_map = row_map;
+        Task::perform(
+            async move { playback(events, options, cancel, pause, progress) },
+            Message::PlaybackFinished,
+        )
+    }
+
+    /// The remap picked in the playback panel, from the recording's screen to
+    /// this one. A recording without a stored screen counts as made here.
+    fn playback_screen_remap(&self) -> Option<ScreenRemap> {
+        if self.playback_remap == RemapMode::Off {
+            return None;
+        }
+        let to = screen_layout().or(self.recording_screen)?;
+        Some(ScreenRemap {
+            from: self.recording_screen.unwrap_or(to),
+            to,
+            mode: self.playback_remap,
+            offset: (
+                self.playback_remap_offset_x_text.trim().parse().unwrap_or(0),
+                self.playback_remap_offset_y_text.trim().parse().unwrap_or(0),
+            ),
+        })
+    }
+
     pub(super) fn materialize_moves_grouped_events(&self) -> Vec<RecordedEvent> {
         self.materialize_moves_grouped_events_with_row_map().0
     }
 
     pub(super) fn append_recorded_events_compacting_moves(
         &mut self,
-        new_events: Vec<RecordedEvent>,
+        mut new_events: Vec<RecordedEvent>,
     ) -> bool {
         let mut changed = false;
 
+        // Monitors are numbered from the live desktop position, before any
+        // window shift below.
+        for ev in &mut new_events {
+            let at = match ev.kind {
+                RecordedEventKind::Move { x, y } => Some((x, y)),
+                _ => ev.pos,
+            };
+            ev.monitor = at.and_then(|pos| monitor_at(&self.recording_monitors, pos));
+        }
+
+        // A window-bound recording stores positions relative to the window.
+        if let Some((ox, oy)) = self.recording_window_origin {
+            for ev in &mut new_events {
+                ev.map_positions(|(x, y)| (x - ox, y - oy));
+            }
+        }
+
         for ev in new_events {
+            let typed = match &ev.kind {
+                RecordedEventKind::KeyUp { key, modifiers } => typed_char(key, *modifiers),
+                _ => None,
+            };
+
             match (&mut self.events.last_mut(), &ev.kind) {
                 (
                     Some(last),
@@ -83,6 +354,48 @@ impl App {
                         changed = true;
                     }
                 }
+                (
+                    Some(last),
+                    RecordedEventKind::Scroll { dx, dy },
+                ) if matches!(last.kind, RecordedEventKind::Scroll { .. }) && last.pos == ev.pos => {
+                    // Consecutive wheel ticks at the same spot collapse into one row.
+                    if let RecordedEventKind::Scroll { dx: last_dx, dy: last_dy } = &mut last.kind {
+                        *last_dx += dx;
+                        *last_dy += dy;
+                        if *last_dx == 0 && *last_dy == 0 {
+                            self.events.pop();
+                        }
+                    }
+                    changed = true;
+                }
+                (
+                    Some(last),
+                    RecordedEventKind::KeyUp { key, modifiers },
+                ) if typed.is_some()
+                    && matches!(
+                        &last.kind,
+                        RecordedEventKind::KeyDown { key: down_key, modifiers: down_modifiers }
+                            if down_key == key && down_modifiers == modifiers
+                    ) =>
+                {
+                    // A plain press/release of a printable key becomes typed text,
+                    // appended to the previous TypeText row when there is one.
+                    let (Some(c), Some(down)) = (typed, self.events.pop()) else {
+                        continue;
+                    };
+
+                    match self.events.last_mut() {
+                        Some(RecordedEvent {
+                            kind: RecordedEventKind::TypeText { text },
+                            ..
+                        }) => text.push(c),
+                        _ => self.events.push(RecordedEvent {
+                            kind: RecordedEventKind::TypeText { text: c.to_string() },
+                            ..down
+                        }),
+                    }
+                    changed = true;
+                }
                 _ => {
                     self.events.push(ev);
                     changed = true;
@@ -93,6 +406,129 @@ impl App {
         changed
     }
 
+    /// Puts `start` before the selected rows (or at the end) and the `end` markers
+    /// after them, then selects the opener. Returns the first and last row of the
+    /// block, or a status message when it would split an existing block.
+    pub(super) fn wrap_selection_in_block(
+        &mut self,
+        start: RecordedEventKind,
+        start_pos: Option<(i32, i32)>,
+        end: Vec<RecordedEventKind>,
+    ) -> Result<(usize, usize), String> {
+        let (start_at, end_at) = match self.selected_range() {
+            Some((first, last)) => (first, last + 1),
+            None => (self.events.len(), self.events.len()),
+        };
+        let ms_at = |index: usize| {
+            self.events
+                .get(index)
+                .or(self.events.last())
+                .map(|e| e.ms_from_start)
+                .unwrap_or(0)
+        };
+        let start_ms = ms_at(start_at);
+        let end_ms = ms_at(end_at.saturating_sub(1));
+        let end_len = end.len();
+
+        let mut events = self.events.clone();
+        for (offset, kind) in end.into_iter().enumerate() {
+            events.insert(
+                end_at + offset,
+                RecordedEvent {
+                    ms_from_start: end_ms,
+                    kind,
+                    pos: None,
+                    click_meta: None,
+                    on_error: None,
+                    monitor: None,
+                },
+            );
+        }
+        events.insert(
+            start_at,
+            RecordedEvent {
+                ms_from_start: start_ms,
+                kind: start,
+                pos: start_pos,
+                click_meta: None,
+                on_error: None,
+                monitor: None,
+            },
+        );
+        block_jumps(&events).map_err(|err| format!("The selection would split a block ({err})."))?;
+
+        self.events = events;
+        self.selected_index = Some(start_at);
+        Ok((start_at, end_at + end_len))
+    }
+
+    /// Inserts rows below the selected one (or at the end) and selects the first.
+    pub(super) fn insert_rows_after_selection(
+        &mut self,
+        kinds: Vec<RecordedEventKind>,
+        click_meta: Option<ClickListMeta>,
+    ) -> usize {
+        let (insert_at, base_ms) = match self.selected_index.filter(|&i| i < self.events.len()) {
+            Some(index) => (index + 1, self.events[index].ms_from_start),
+            None => (
+                self.events.len(),
+                self.events.last().map(|e| e.ms_from_start).unwrap_or(0),
+            ),
+        };
+
+        for (offset, kind) in kinds.into_iter().enumerate() {
+            self.events.insert(
+                insert_at + offset,
+                RecordedEvent {
+                    ms_from_start: base_ms + 1 + offset as u128,
+                    kind,
+                    pos: None,
+                    click_meta: click_meta.clone(),
+                    on_error: None,
+                    monitor: None,
+                },
+            );
+        }
+
+        self.selected_index = Some(insert_at);
+        insert_at
+    }
+
+    /// The policy picked by `choice`, reading retries from the given inputs and
+    /// the label from the editor. `None` for `RunDefault`.
+    pub(super) fn parse_on_error(
+        &self,
+        choice: OnErrorChoice,
+        retry_times_text: &str,
+        retry_backoff_ms_text: &str,
+    ) -> Result<Option<OnError>, String> {
+        Ok(Some(match choice {
+            OnErrorChoice::RunDefault => return Ok(None),
+            OnErrorChoice::Abort => OnError::Abort,
+            OnErrorChoice::Skip => OnError::Skip,
+            OnErrorChoice::Retry => {
+                let times = retry_times_text
+                    .trim()
+                    .parse::<u32>()
+                    .ok()
+                    .filter(|times| *times > 0)
+                    .ok_or("Retry count must be a whole number above 0.")?;
+                let backoff_ms = retry_backoff_ms_text
+                    .trim()
+                    .parse::<u64>()
+                    .map_err(|_| "Retry backoff must be a whole number of milliseconds.")?;
+                OnError::Retry { times, backoff_ms }
+            }
+            OnErrorChoice::GotoLabel => {
+                let name = self.editor_label_text.trim();
+                if name.is_empty() {
+                    return Err("Enter the label to jump to on error.".to_string());
+                }
+                OnError::GotoLabel(name.to_string())
+            }
+        }))
+    }
+
     pub(super) fn parse_editor_xy(&self) -> Option<(i32, i32)> {
         let x = self.editor_x_text.trim().parse::<i32>().ok()?;
         let y = self.editor_y_text.trim().parse::<i32>().ok()?;
@@ -110,9 +546,30 @@ impl App {
             use_find_image: self.editor_use_find_image,
             target_precision: self.editor_target_precision_percent as f32 / 100.0,
             target_timeout_ms: self.editor_target_timeout_ms as u64,
+            match_strategy: self.editor_match_strategy,
+            target_offset: self.parse_editor_target_offset(),
+            scale_sweep: self.parse_editor_scale_sweep(),
+            match_filter: self.editor_match_filter,
+            search_monitor: self.editor_search_monitor,
         }
     }
 
+    fn parse_editor_scale_sweep(&self) -> Option<ScaleSweep> {
+        parse_scale_sweep(
+            self.editor_scale_sweep_enabled,
+            &self.editor_scale_min_text,
+            &self.editor_scale_max_text,
+            &self.editor_scale_step_text,
+        )
+    }
+
+    pub(crate) fn parse_editor_target_offset(&self) -> (i32, i32) {
+        (
+            self.editor_target_offset_x_text.trim().parse().unwrap_or(0),
+            self.editor_target_offset_y_text.trim().parse().unwrap_or(0),
+        )
+    }
+
     pub(super) fn push_recorded_button_release(
         &self,
         state: &mut RecorderState,
@@ -122,13 +579,7 @@ impl App {
         held_ms: u64,
         button: MouseButton,
     ) {
-        let click_time = if state.started_by_click {
-            state.synthetic_time_ms = state.synthetic_time_ms.saturating_add(self.recorder_wait_ms as u128);
-            state.synthetic_time_ms
-        } else {
-            state.started_by_click = true;
-            state.synthetic_time_ms
-        };
+        let click_time = state.elapsed_ms();
 
         let release_pos = up_pos.or(down_pos);
 
@@ -176,10 +627,12 @@ impl App {
             let up_meta = self.recorded_click_meta(button, ClickEdgeMode::Up);
 
             pushed.push(RecordedEvent {
-                ms_from_start: click_time,
+                ms_from_start: click_time.saturating_sub(held_ms as u128),
                 kind: make_down_kind(down_patch),
                 pos: down_pos,
                 click_meta: Some(down_meta),
+                on_error: None,
+                monitor: None,
             });
 
             pushed.push(RecordedEvent {
@@ -187,6 +640,8 @@ impl App {
                 kind: make_up_kind(up_patch),
                 pos: up_pos,
                 click_meta: Some(up_meta),
+                on_error: None,
+                monitor: None,
             });
 
             state.last_click_pos = release_pos;
@@ -215,6 +670,8 @@ impl App {
                     kind,
                     pos: release_pos,
                     click_meta: Some(click_meta),
+                    on_error: None,
+                    monitor: None,
                 });
                 state.last_click_pos = release_pos;
                 return;
@@ -227,7 +684,7 @@ impl App {
             button,
             pos: release_pos,
             up_at: now,
-            synthetic_time_ms: click_time,
+            ms_from_start: click_time,
         });
         state.last_click_pos = release_pos;
     }
@@ -241,11 +698,7 @@ impl App {
     ) {
         self.flush_pending_click_for_button(state, pushed, button);
 
-        if state.started_by_click {
-            state.synthetic_time_ms = state.synthetic_time_ms.saturating_add(1);
-        } else {
-            state.started_by_click = true;
-        }
+        let ms_from_start = state.elapsed_ms();
 
         let patch = down_pos
             .and_then(|(x, y)| capture_patch_png_base64(x, y, self.find_image_patch_size).ok());
@@ -258,10 +711,12 @@ impl App {
 
         let down_meta = self.recorded_click_meta(button, ClickEdgeMode::Down);
         pushed.push(RecordedEvent {
-            ms_from_start: state.synthetic_time_ms,
+            ms_from_start,
             kind,
             pos: down_pos,
             click_meta: Some(down_meta),
+            on_error: None,
+            monitor: None,
         });
     }
 
@@ -272,11 +727,7 @@ impl App {
         up_pos: Option<(i32, i32)>,
         button: MouseButton,
     ) {
-        if state.started_by_click {
-            state.synthetic_time_ms = state.synthetic_time_ms.saturating_add(1);
-        } else {
-            state.started_by_click = true;
-        }
+        let ms_from_start = state.elapsed_ms();
 
         let patch = up_pos
             .and_then(|(x, y)| capture_patch_png_base64(x, y, self.find_image_patch_size).ok());
@@ -289,15 +740,77 @@ impl App {
 
         let up_meta = self.recorded_click_meta(button, ClickEdgeMode::Up);
         pushed.push(RecordedEvent {
-            ms_from_start: state.synthetic_time_ms,
+            ms_from_start,
             kind,
             pos: up_pos,
             click_meta: Some(up_meta),
+            on_error: None,
+            monitor: None,
         });
 
         state.last_click_pos = up_pos;
     }
 
+    pub(super) fn push_recorded_key(
+        &self,
+        state: &mut RecorderState,
+        pushed: &mut Vec<RecordedEvent>,
+        key: &str,
+        modifiers: KeyModifiers,
+        is_down: bool,
+    ) {
+        // Pending single clicks belong before this key in the timeline.
+        self.flush_all_pending_clicks(state, pushed);
+
+        let ms_from_start = state.elapsed_ms();
+
+        let key = key.to_string();
+        let kind = if is_down {
+            RecordedEventKind::KeyDown { key, modifiers }
+        } else {
+            RecordedEventKind::KeyUp { key, modifiers }
+        };
+
+        pushed.push(RecordedEvent {
+            ms_from_start,
+            kind,
+            pos: None,
+            click_meta: Some(self.plain_row_meta()),
+            on_error: None,
+            monitor: None,
+        });
+    }
+
+    pub(super) fn push_recorded_scroll(
+        &self,
+        state: &mut RecorderState,
+        pushed: &mut Vec<RecordedEvent>,
+        pos: Option<(i32, i32)>,
+        dx: i32,
+        dy: i32,
+    ) {
+        self.flush_all_pending_clicks(state, pushed);
+
+        let ms_from_start = state.elapsed_ms();
+
+        pushed.push(RecordedEvent {
+            ms_from_start,
+            kind: RecordedEventKind::Scroll { dx, dy },
+            pos,
+            click_meta: Some(self.plain_row_meta()),
+            on_error: None,
+            monitor: None,
+        });
+    }
+
+    /// Metadata for rows that are not clicks, where only the pre-row wait applies.
+    pub(super) fn plain_row_meta(&self) -> ClickListMeta {
+        ClickListMeta {
+            wait_ms: self.recorder_wait_ms as u16,
+            ..ClickListMeta::default()
+        }
+    }
+
     fn recorded_click_meta(&self, button: MouseButton, mode: ClickEdgeMode) -> ClickListMeta {
         let mut meta = ClickListMeta {
             left_mode: ClickEdgeMode::Auto,
@@ -309,6 +822,11 @@ impl App {
             use_find_image: self.editor_use_find_image,
             target_precision: (self.editor_target_precision_percent as f32 / 100.0).clamp(0.5, 1.0),
             target_timeout_ms: (self.editor_target_timeout_ms as u64).clamp(200, 10000),
+            match_strategy: self.editor_match_strategy,
+            target_offset: (0, 0),
+            scale_sweep: self.parse_editor_scale_sweep(),
+            match_filter: self.editor_match_filter,
+            search_monitor: self.editor_search_monitor,
         };
 
         match button {
@@ -334,10 +852,12 @@ impl App {
         let click_meta = self.recorded_click_meta(pending.button, ClickEdgeMode::Auto);
 
         pushed.push(RecordedEvent {
-            ms_from_start: pending.synthetic_time_ms,
+            ms_from_start: pending.ms_from_start,
             kind,
             pos: pending.pos,
             click_meta: Some(click_meta),
+            on_error: None,
+            monitor: None,
         });
     }
 
@@ -476,3 +996,264 @@ impl App {
         out
     }
 }
+
+/// Builds the sweep from the min/max/step fields; a field that does not parse
+/// keeps its default.
+pub(super) fn parse_scale_sweep(enabled: bool, min: &str, max: &str, step: &str) -> Option<ScaleSweep> {
+    if !enabled {
+        return None;
+    }
+    let defaults = ScaleSweep::default();
+    let field = |txt: &str, default: f32| txt.trim().parse::<f32>().ok().filter(|v| *v > 0.0).unwrap_or(default);
+    Some(ScaleSweep {
+        min: field(min, defaults.min),
+        max: field(max, defaults.max),
+        step: field(step, defaults.step),
+    })
+}
+
+/// Paints the draft's mask brush at the hovered point of its patch.
+pub(super) fn paint_draft_mask(draft: &mut FindTargetDraft) {
+    const BRUSH_RADIUS: f32 = 0.08;
+
+    let (Some(point), Some(b64)) = (draft.mask_hover, draft.patch_png_base64.as_deref()) else {
+        return;
+    };
+    if let Some(painted) = paint_patch_mask(b64, point, BRUSH_RADIUS, draft.mask_brush) {
+        draft.patch_png_base64 = Some(painted);
+    }
+}
+
+/// Sets the alpha of the patch pixels within `radius` (a fraction of the patch
+/// width) of `center` (a fraction of the patch size): 0 for `MaskBrush::Ignore`,
+/// opaque for `MaskBrush::Keep`. `None` when the patch doesn't decode.
+pub(super) fn paint_patch_mask(png_base64: &str, center: iced::Point, radius: f32, brush: MaskBrush) -> Option<String> {
+    use base64::engine::general_purpose;
+    use base64::Engine;
+
+    let bytes = general_purpose::STANDARD.decode(png_base64).ok()?;
+    let mut rgba = ::image::load_from_memory(&bytes).ok()?.to_rgba8();
+    let (w, h) = rgba.dimensions();
+    let (cx, cy) = (center.x * w as f32, center.y * h as f32);
+    let r = (radius * w as f32).max(1.0);
+    let alpha = match brush {
+        MaskBrush::Ignore => 0,
+        MaskBrush::Keep => 255,
+    };
+    for (x, y, px) in rgba.enumerate_pixels_mut() {
+        let (dx, dy) = (x as f32 + 0.5 - cx, y as f32 + 0.5 - cy);
+        if dx * dx + dy * dy <= r * r {
+            px[3] = alpha;
+        }
+    }
+
+    let mut png = Vec::new();
+    ::image::DynamicImage::ImageRgba8(rgba)
+        .write_to(&mut std::io::Cursor::new(&mut png), ::image::ImageFormat::Png)
+        .ok()?;
+    Some(general_purpose::STANDARD.encode(png))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::test_support::{if_image, label, row, text, wait_for_image};
+
+    fn key(key: &str, modifiers: KeyModifiers, is_down: bool) -> RecordedEvent {
+        let key = key.to_string();
+        RecordedEvent {
+            ms_from_start: 0,
+            kind: if is_down {
+                RecordedEventKind::KeyDown { key, modifiers }
+            } else {
+                RecordedEventKind::KeyUp { key, modifiers }
+            },
+            pos: None,
+            click_meta: None,
+            on_error: None,
+            monitor: None,
+        }
+    }
+
+    fn scroll(pos: (i32, i32), dx: i32, dy: i32) -> RecordedEvent {
+        RecordedEvent {
+            ms_from_start: 0,
+            kind: RecordedEventKind::Scroll { dx, dy },
+            pos: Some(pos),
+            click_meta: None,
+            on_error: None,
+            monitor: None,
+        }
+    }
+
+    fn press(name: &str, modifiers: KeyModifiers) -> [RecordedEvent; 2] {
+        [key(name, modifiers, true), key(name, modifiers, false)]
+    }
+
+    fn app_with(events: Vec<RecordedEvent>) -> App {
+        App {
+            events,
+            ..App::default()
+        }
+    }
+
+    /// Texts and block rows of the materialized range, with their source rows.
+    fn range_rows(app: &App, first: usize, last: usize) -> Vec<(String, usize)> {
+        let (events, row_map) = app.materialize_playback_range(first, last);
+        events
+            .iter()
+            .map(|ev| match &ev.kind {
+                RecordedEventKind::TypeText { text } => text.clone(),
+                RecordedEventKind::RepeatStart { .. } => "RepeatStart".to_string(),
+                RecordedEventKind::RepeatEnd => "RepeatEnd".to_string(),
+                kind => format!("{kind:?}"),
+            })
+            .zip(row_map)
+            .collect()
+    }
+
+    #[test]
+    fn plain_key_presses_fold_into_typed_text() {
+        let ctrl = KeyModifiers {
+            ctrl: true,
+            ..KeyModifiers::default()
+        };
+        let plain = KeyModifiers::default();
+        let mut app = App::default();
+        let mut recorded: Vec<RecordedEvent> = [press("h", plain), press("i", plain), press("c", ctrl)]
+            .into_iter()
+            .flatten()
+            .collect();
+        // A held key stays a KeyDown row.
+        recorded.push(key("shift", plain, true));
+
+        assert!(app.append_recorded_events_compacting_moves(recorded));
+
+        let kinds: Vec<_> = app.events.into_iter().map(|ev| ev.kind).collect();
+        assert_eq!(
+            kinds,
+            [
+                RecordedEventKind::TypeText { text: "hi".to_string() },
+                key("c", ctrl, true).kind,
+                key("c", ctrl, false).kind,
+                key("shift", plain, true).kind,
+            ]
+        );
+    }
+
+    #[test]
+    fn wheel_ticks_at_one_spot_merge() {
+        let mut app = App::default();
+        app.append_recorded_events_compacting_moves(vec![
+            scroll((5, 5), 0, 1),
+            scroll((5, 5), 0, 2),
+            scroll((9, 9), 1, 0),
+            scroll((9, 9), -1, 0),
+            scroll((9, 9), 0, -1),
+        ]);
+
+        let rows: Vec<_> = app.events.into_iter().map(|ev| (ev.kind, ev.pos)).collect();
+        // Ticks that cancel out leave no row behind.
+        assert_eq!(
+            rows,
+            [
+                (RecordedEventKind::Scroll { dx: 0, dy: 3 }, Some((5, 5))),
+                (RecordedEventKind::Scroll { dx: 0, dy: -1 }, Some((9, 9))),
+            ]
+        );
+    }
+
+    #[test]
+    fn window_bound_recordings_store_client_positions() {
+        let mut app = App {
+            recording_window_origin: Some((300, 200)),
+            ..App::default()
+        };
+        app.append_recorded_events_compacting_moves(vec![scroll((310, 250), 0, 1)]);
+
+        assert_eq!(app.events[0].pos, Some((10, 50)));
+    }
+
+    #[test]
+    fn recorded_rows_note_their_monitor() {
+        let primary = ScreenLayout {
+            origin: (0, 0),
+            size: (100, 100),
+        };
+        let left = ScreenLayout {
+            origin: (-100, 0),
+            size: (100, 100),
+        };
+        let mut app = App {
+            recording_monitors: vec![primary, left],
+            ..App::default()
+        };
+        let mut moved = scroll((0, 0), 0, 1);
+        moved.kind = RecordedEventKind::Move { x: -50, y: 10 };
+        moved.pos = None;
+        app.append_recorded_events_compacting_moves(vec![moved, scroll((50, 50), 0, 1), scroll((500, 50), 0, 1)]);
+
+        let monitors: Vec<_> = app.events.iter().map(|ev| ev.monitor).collect();
+        assert_eq!(monitors, [Some(2), Some(1), None]);
+    }
+
+    #[test]
+    fn ranges_starting_inside_a_repeat_drop_its_end() {
+        let app = app_with(vec![
+            row(RecordedEventKind::RepeatStart { count: 2 }),
+            text("a"),
+            text("b"),
+            row(RecordedEventKind::RepeatEnd),
+            text("c"),
+        ]);
+
+        assert_eq!(range_rows(&app, 2, 4), [("b".to_string(), 2), ("c".to_string(), 4)]);
+        // A range ending inside the block gets its end added on the last row.
+        assert_eq!(
+            range_rows(&app, 0, 1),
+            [("RepeatStart".to_string(), 0), ("a".to_string(), 1), ("RepeatEnd".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn ranges_starting_inside_a_branch_skip_the_other_one() {
+        let app = app_with(vec![
+            if_image(String::new()),
+            text("then"),
+            row(RecordedEventKind::Else),
+            text("else"),
+            row(RecordedEventKind::EndIf),
+            text("after"),
+        ]);
+
+        assert_eq!(range_rows(&app, 1, 5), [("then".to_string(), 1), ("after".to_string(), 5)]);
+        assert_eq!(range_rows(&app, 3, 5), [("else".to_string(), 3), ("after".to_string(), 5)]);
+    }
+
+    #[test]
+    fn jumps_to_labels_outside_the_range_fail_instead() {
+        let mut failing = text("failing");
+        failing.on_error = Some(OnError::GotoLabel("end".to_string()));
+        let app = app_with(vec![
+            text("a"),
+            wait_for_image(String::new(), false, 0, OnTimeout::GotoLabel("end".to_string())),
+            failing,
+            label("end"),
+        ]);
+
+        let (events, _) = app.materialize_playback_range(1, 2);
+        assert!(matches!(
+            &events[0].kind,
+            RecordedEventKind::WaitForImage { on_timeout: OnTimeout::Fail, .. }
+        ));
+        assert_eq!(events[1].on_error, Some(OnError::Abort));
+
+        // With the label in the range the jumps are kept.
+        let (events, _) = app.materialize_playback_range(1, 3);
+        assert!(matches!(
+            &events[0].kind,
+            RecordedEventKind::WaitForImage { on_timeout: OnTimeout::GotoLabel(name), .. } if name == "end"
+        ));
+        assert_eq!(events[1].on_error, Some(OnError::GotoLabel("end".to_string())));
+    }
+}
@@ -36,6 +36,8 @@ impl App {
                     kind: RecordedEventKind::Wait { ms: draft.wait_ms },
                     pos: None,
                     click_meta: None,
+                    on_error: None,
+                    monitor: None,
                 });
                 self.status = "Added wait row.".to_string();
                 Ok(Task::none())
@@ -68,9 +70,24 @@ impl App {
                         timeout_ms: draft.timeout_ms,
                         search_anchor: draft.anchor,
                         search_region_size: draft.limit_region.then_some(draft.region_size),
+                        match_strategy: draft.match_strategy,
+                        target_offset: (
+                            draft.offset_x_text.trim().parse().unwrap_or(0),
+                            draft.offset_y_text.trim().parse().unwrap_or(0),
+                        ),
+                        scale_sweep: helpers::parse_scale_sweep(
+                            draft.scale_sweep_enabled,
+                            &draft.scale_min_text,
+                            &draft.scale_max_text,
+                            &draft.scale_step_text,
+                        ),
+                        match_filter: draft.match_filter,
+                        search_monitor: draft.search_monitor,
                     },
                     pos,
                     click_meta: None,
+                    on_error: None,
+                    monitor: pos.and_then(|pos| monitor_at(&monitors(), pos)),
                 });
 
                 self.status = "Added find-target row (move only).".to_string();
@@ -124,34 +141,143 @@ impl App {
                 }
                 Ok(Task::none())
             }
+            Message::FindTargetOffsetXChanged(txt) => {
+                if let Some(draft) = &mut self.find_target_modal {
+                    draft.offset_x_text = txt;
+                }
+                Ok(Task::none())
+            }
+            Message::FindTargetOffsetYChanged(txt) => {
+                if let Some(draft) = &mut self.find_target_modal {
+                    draft.offset_y_text = txt;
+                }
+                Ok(Task::none())
+            }
+            Message::FindTargetScaleSweepToggled(v) => {
+                if let Some(draft) = &mut self.find_target_modal {
+                    draft.scale_sweep_enabled = v;
+                }
+                Ok(Task::none())
+            }
+            Message::FindTargetScaleMinChanged(txt) => {
+                if let Some(draft) = &mut self.find_target_modal {
+                    draft.scale_min_text = txt;
+                }
+                Ok(Task::none())
+            }
+            Message::FindTargetScaleMaxChanged(txt) => {
+                if let Some(draft) = &mut self.find_target_modal {
+                    draft.scale_max_text = txt;
+                }
+                Ok(Task::none())
+            }
+            Message::FindTargetScaleStepChanged(txt) => {
+                if let Some(draft) = &mut self.find_target_modal {
+                    draft.scale_step_text = txt;
+                }
+                Ok(Task::none())
+            }
+            Message::FindTargetMatchFilterSelected(filter) => {
+                if let Some(draft) = &mut self.find_target_modal {
+                    draft.match_filter = filter;
+                }
+                Ok(Task::none())
+            }
+            Message::FindTargetSearchMonitorSelected(monitor) => {
+                if let Some(draft) = &mut self.find_target_modal {
+                    draft.search_monitor = monitor;
+                }
+                Ok(Task::none())
+            }
+            Message::FindTargetMaskHovered(point) => {
+                if let Some(draft) = &mut self.find_target_modal {
+                    draft.mask_hover = Some(point);
+                    if draft.mask_painting {
+                        helpers::paint_draft_mask(draft);
+                    }
+                }
+                Ok(Task::none())
+            }
+            Message::FindTargetMaskPressed => {
+                if let Some(draft) = &mut self.find_target_modal {
+                    draft.mask_painting = true;
+                    helpers::paint_draft_mask(draft);
+                    if draft.mask_brush == MaskBrush::Ignore && draft.match_filter != MatchFilter::Masked {
+                        draft.match_filter = MatchFilter::Masked;
+                        draft.status = "Matching switched to Masked so painted pixels are ignored.".to_string();
+                    }
+                }
+                Ok(Task::none())
+            }
+            Message::FindTargetMaskReleased => {
+                if let Some(draft) = &mut self.find_target_modal {
+                    draft.mask_painting = false;
+                }
+                Ok(Task::none())
+            }
+            Message::FindTargetMaskBrushSelected(brush) => {
+                if let Some(draft) = &mut self.find_target_modal {
+                    draft.mask_brush = brush;
+                }
+                Ok(Task::none())
+            }
+            Message::FindTargetClearMask => {
+                if let Some(draft) = &mut self.find_target_modal {
+                    if let Some(b64) = draft.patch_png_base64.as_deref() {
+                        // A brush covering the whole patch restores every pixel.
+                        draft.patch_png_base64 =
+                            helpers::paint_patch_mask(b64, iced::Point::new(0.5, 0.5), 2.0, MaskBrush::Keep)
+                                .or(draft.patch_png_base64.take());
+                    }
+                }
+                Ok(Task::none())
+            }
+            Message::FindTargetMatchStrategySelected(strategy) => {
+                if let Some(draft) = &mut self.find_target_modal {
+                    draft.match_strategy = strategy;
+                }
+                Ok(Task::none())
+            }
+            Message::FindTargetMatchNthChanged(txt) => {
+                if let Some(draft) = &mut self.find_target_modal {
+                    if let Ok(n) = txt.trim().parse::<u32>() {
+                        if matches!(draft.match_strategy, MatchStrategy::Nth(_)) {
+                            draft.match_strategy = MatchStrategy::Nth(n.max(1));
+                        }
+                    }
+                    draft.match_nth_text = txt;
+                }
+                Ok(Task::none())
+            }
             Message::FindTargetStartCapture => {
                 if let Some(draft) = &mut self.find_target_modal {
                     draft.patch_png_base64 = None;
                     draft.captured_pos = None;
+                    draft.match_test = None;
 
-                    #[cfg(windows)]
+                    #[cfg(any(windows, target_os = "linux"))]
                     {
                         draft.capture_waiting = true;
-                        draft.capture_left_was_down = is_vk_down_windows(VK_LBUTTON);
+                        draft.capture_left_was_down = is_vk_down(VK_LBUTTON);
                         draft.status = "Click on the screen to capture.".to_string();
                     }
 
-                    #[cfg(not(windows))]
+                    #[cfg(not(any(windows, target_os = "linux")))]
                     {
-                        draft.status = "Capture is currently Windows-only.".to_string();
+                        draft.status = "Capture is not supported on this platform.".to_string();
                     }
                 }
                 Ok(Task::none())
             }
             Message::FindTargetCaptureTick => {
-                #[cfg(windows)]
+                #[cfg(any(windows, target_os = "linux"))]
                 {
                     if let Some(draft) = &mut self.find_target_modal {
                         if !draft.capture_waiting {
                             return Ok(Task::none());
                         }
 
-                        let left_now = is_vk_down_windows(VK_LBUTTON);
+                        let left_now = is_vk_down(VK_LBUTTON);
                         if draft.capture_left_was_down && !left_now {
                             if let Some((x, y)) = get_mouse_pos() {
                                 match capture_patch_png_base64(x, y, draft.patch_size) {
@@ -197,6 +323,7 @@ impl App {
                             draft.patch_png_base64 = Some(general_purpose::STANDARD.encode(bytes));
                             draft.captured_pos = self.current_pos;
                             draft.capture_waiting = false;
+                            draft.match_test = None;
                             draft.status = "Loaded.".to_string();
                         }
                         Err(err) => {
@@ -206,6 +333,81 @@ impl App {
                 }
                 Ok(Task::none())
             }
+            Message::OpenFindTargetModal => {
+                if self.mode == Mode::Idle {
+                    self.find_target_modal = Some(FindTargetDraft {
+                        captured_pos: self.current_pos,
+                        ..FindTargetDraft::default()
+                    });
+                }
+                Ok(Task::none())
+            }
+            Message::FindTargetTestMatch => {
+                let Some(draft) = &mut self.find_target_modal else {
+                    return Ok(Task::none());
+                };
+                let Some(patch_png_base64) = draft.patch_png_base64.clone() else {
+                    draft.status = "No image selected.".to_string();
+                    return Ok(Task::none());
+                };
+
+                let anchor_pos = match draft.anchor {
+                    SearchAnchor::CurrentMouse => get_mouse_pos(),
+                    SearchAnchor::RecordedClick | SearchAnchor::LastFound => draft.captured_pos.or(self.current_pos),
+                };
+                let region_size = draft.limit_region.then_some(draft.region_size);
+                let connected = monitors();
+                let recorded = draft.captured_pos.or(self.current_pos).and_then(|pos| monitor_at(&connected, pos));
+                let area = match draft.search_monitor.area(recorded, &connected) {
+                    Ok(area) => area,
+                    Err(err) => {
+                        draft.status = err;
+                        return Ok(Task::none());
+                    }
+                };
+                let patch_size = draft.patch_size;
+                let filter = draft.match_filter;
+                draft.match_testing = true;
+                draft.match_test = None;
+                draft.status = "Testing match...".to_string();
+
+                Ok(Task::perform(
+                    async move {
+                        let best = test_template_match(&patch_png_base64, region_size, anchor_pos, area, filter)?;
+                        Ok(best.map(|(x, y, score)| MatchTest {
+                            pos: (x, y),
+                            score,
+                            candidate_png_base64: capture_patch_png_base64(x, y, patch_size).ok(),
+                        }))
+                    },
+                    Message::FindTargetTestMatchFinished,
+                ))
+            }
+            Message::FindTargetTestMatchFinished(result) => {
+                if let Some(draft) = &mut self.find_target_modal {
+                    draft.match_testing = false;
+                    draft.status = match &result {
+                        Ok(Some(test)) if test.score >= draft.precision => format!(
+                            "Best match {:.1}% at ({},{}): found at precision {:.2}.",
+                            test.score * 100.0,
+                            test.pos.0,
+                            test.pos.1,
+                            draft.precision
+                        ),
+                        Ok(Some(test)) => format!(
+                            "Best match {:.1}% at ({},{}): below precision {:.2}, playback would time out.",
+                            test.score * 100.0,
+                            test.pos.0,
+                            test.pos.1,
+                            draft.precision
+                        ),
+                        Ok(None) => format!("Nothing on screen scores {:.0}% or more.", CANDIDATE_FLOOR * 100.0),
+                        Err(err) => format!("Test failed: {err}"),
+                    };
+                    draft.match_test = result.ok().flatten();
+                }
+                Ok(Task::none())
+            }
             _ => Err(message),
         }
     }
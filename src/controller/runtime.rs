@@ -16,6 +16,43 @@ impl App {
                 };
                 Ok(Task::none())
             }
+            Message::PlaybackSpeedChanged(percent) => {
+                self.playback_speed_percent = percent.clamp(25, 1000);
+                Ok(Task::none())
+            }
+            Message::SetFaithfulTiming(enabled) => {
+                self.playback_faithful_timing = enabled;
+                self.status = if enabled {
+                    "Faithful timing: ON (recorded row gaps)".to_string()
+                } else {
+                    "Faithful timing: OFF (per-row waits)".to_string()
+                };
+                Ok(Task::none())
+            }
+            Message::PlaybackOnErrorChanged(choice) => {
+                self.playback_on_error = choice;
+                Ok(Task::none())
+            }
+            Message::PlaybackRetryTimesChanged(value) => {
+                self.playback_retry_times_text = value;
+                Ok(Task::none())
+            }
+            Message::PlaybackRetryBackoffMsChanged(value) => {
+                self.playback_retry_backoff_ms_text = value;
+                Ok(Task::none())
+            }
+            Message::PlaybackRemapChanged(mode) => {
+                self.playback_remap = mode;
+                Ok(Task::none())
+            }
+            Message::PlaybackRemapOffsetXChanged(value) => {
+                self.playback_remap_offset_x_text = value;
+                Ok(Task::none())
+            }
+            Message::PlaybackRemapOffsetYChanged(value) => {
+                self.playback_remap_offset_y_text = value;
+                Ok(Task::none())
+            }
             Message::WindowResized(w, h) => {
                 if w.is_finite() {
                     self.window_width_px = w.max(640.0);
@@ -30,11 +67,53 @@ impl App {
                     return Ok(Task::none());
                 }
 
+                let bound_window = if self.recorder_bind_window {
+                    let title = self.editor_window_title_text.trim();
+                    let class = self.editor_window_class_text.trim();
+                    match find_window(title, class) {
+                        Ok(Some(window)) => Some((title.to_string(), class.to_string(), window)),
+                        Ok(None) => {
+                            self.status = format!(
+                                "Cannot record: no open window matches {}.",
+                                window_pattern_label(title, class)
+                            );
+                            return Ok(Task::none());
+                        }
+                        Err(err) => {
+                            self.status = format!("Cannot record: {err}");
+                            return Ok(Task::none());
+                        }
+                    }
+                } else {
+                    None
+                };
+
                 self.recorder_wait_ms = self.editor_wait_ms as u64;
 
                 self.mode = Mode::Recording;
                 self.events.clear();
-                self.status = "Recording...".to_string();
+                self.recording_screen = screen_layout();
+                self.recording_real_timestamps = true;
+                self.recording_monitors = monitors();
+                self.recording_window_origin = None;
+                let mut status = "Recording...".to_string();
+                if let Some((title, class, window)) = bound_window {
+                    activate_window(window.id);
+                    self.recording_window_origin = Some(window.origin);
+                    self.events.push(RecordedEvent {
+                        ms_from_start: 0,
+                        kind: RecordedEventKind::BindWindow { title, class },
+                        pos: None,
+                        click_meta: None,
+                        on_error: None,
+                        monitor: None,
+                    });
+                    status = format!("Recording relative to \"{}\"...", window.title);
+                }
+                self.status = match start_scroll_capture().and_then(|()| start_key_capture()) {
+                    Ok(()) => status,
+                    Err(err) => format!("{status} (wheel and key capture unavailable: {err})"),
+                };
 
                 if let Ok(mut state) = self.recorder_state.lock() {
                     state.enabled = true;
@@ -47,12 +126,13 @@ impl App {
                     state.left_down_at = None;
                     state.right_down_at = None;
                     state.middle_down_at = None;
-                    state.started_by_click = false;
+                    state.started_at = None;
                     state.last_click_pos = None;
-                    state.synthetic_time_ms = 0;
                     state.left_pending_click = None;
                     state.right_pending_click = None;
                     state.middle_pending_click = None;
+                    state.keys_down.clear();
+                    state.modifiers_down.clear();
 
                     if let Some((x, y)) = get_mouse_pos() {
                         state.last_x = x;
@@ -68,6 +148,7 @@ impl App {
                         token.store(true, Ordering::Relaxed);
                     }
                     self.playback_cancel = None;
+                    self.playback_pause = None;
                     self.playback_progress = None;
                     self.playback_active_index = None;
                     self.playback_last_scrolled_index = None;
@@ -80,48 +161,113 @@ impl App {
                         self.flush_all_pending_clicks(&mut state, &mut pushed);
                         state.enabled = false;
                     }
+                    stop_scroll_capture();
+                    stop_key_capture();
 
                     self.append_recorded_events_compacting_moves(pushed);
+                    if self.recording_window_origin.take().is_some() {
+                        self.events.push(RecordedEvent {
+                            ms_from_start: self.events.last().map(|e| e.ms_from_start).unwrap_or(0),
+                            kind: RecordedEventKind::EndWindow,
+                            pos: None,
+                            click_meta: None,
+                            on_error: None,
+                            monitor: None,
+                        });
+                    }
                     self.mode = Mode::Idle;
                     self.status = format!("Stopped. {} events recorded.", self.events.len());
                 }
 
                 Ok(Task::none())
             }
-            Message::StartPlayback => {
-                if self.mode == Mode::Recording || self.events.is_empty() {
+            Message::StartPlayback | Message::StepPlayback if self.mode == Mode::Idle => {
+                if self.events.is_empty() {
                     return Ok(Task::none());
                 }
 
-                self.mode = Mode::Playing;
-                self.status = "Playing (materialized MOVES)...".to_string();
-                self.playback_active_index = None;
-                self.playback_last_scrolled_index = None;
-
-                let cancel = Arc::new(AtomicBool::new(false));
-                self.playback_cancel = Some(cancel.clone());
-
-                let progress = Arc::new(AtomicUsize::new(usize::MAX));
-                self.playback_progress = Some(progress.clone());
+                let (events, row_map) = self.playback_events_with_row_map();
+                Ok(self.start_playback(events, row_map, matches!(message, Message::StepPlayback)))
+            }
+            Message::StartPlayback => Ok(Task::none()),
+            Message::PlayFromSelected | Message::PlaySelection => {
+                if self.mode != Mode::Idle {
+                    return Ok(Task::none());
+                }
+                let Some((first, last)) = self.selected_range() else {
+                    self.status = "Select a row first.".to_string();
+                    return Ok(Task::none());
+                };
 
-                let (events, row_map) = self.materialize_moves_grouped_events_with_row_map();
-                self.playback_progress_row_map = row_map;
-                Ok(Task::perform(
-                    async move { playback(events, cancel, progress).map_err(|e| e.to_string()) },
-                    Message::PlaybackFinished,
-                ))
+                let last = if matches!(message, Message::PlaySelection) {
+                    last
+                } else {
+                    usize::MAX
+                };
+                let (events, row_map) = self.materialize_playback_range(first, last);
+                if events.is_empty() {
+                    self.status = "Nothing to play in the selection.".to_string();
+                    return Ok(Task::none());
+                }
+                Ok(self.start_playback(events, row_map, false))
+            }
+            Message::TogglePausePlayback => {
+                let Some(pause) = self.playback_pause.as_ref().filter(|_| self.mode == Mode::Playing) else {
+                    return Ok(Task::none());
+                };
+                if pause.is_paused() {
+                    pause.resume();
+                    self.status = "Playing (materialized MOVES)...".to_string();
+                } else {
+                    pause.pause();
+                    self.status = "Paused. Step runs one row, resume continues.".to_string();
+                }
+                Ok(Task::none())
             }
-            Message::PlaybackFinished(result) => {
+            Message::StepPlayback => {
+                if let Some(pause) = self.playback_pause.as_ref().filter(|_| self.mode == Mode::Playing) {
+                    pause.step();
+                    self.status = "Stepping one row...".to_string();
+                }
+                Ok(Task::none())
+            }
+            Message::PlaybackFinished(mut report) => {
                 self.mode = Mode::Idle;
                 self.playback_cancel = None;
+                self.playback_pause = None;
                 self.playback_progress = None;
                 self.playback_active_index = None;
                 self.playback_last_scrolled_index = None;
-                self.playback_progress_row_map.clear();
-                match result {
-                    Ok(()) => self.status = "Playback finished.".to_string(),
-                    Err(err) => self.status = format!("Playback failed: {err}"),
+                let row_map = std::mem::take(&mut self.playback_progress_row_map);
+                for row in &mut report.rows {
+                    row.row = row_map.get(row.row).copied().unwrap_or(row.row);
                 }
+                self.status = match (&report.error, report.failed_row()) {
+                    (Some(err), Some(row)) => format!("Playback failed at row {row}: {err}"),
+                    (Some(err), None) => format!("Playback failed: {err}"),
+                    (None, _) => format!("Playback finished. {}", report.describe()).trim_end().to_string(),
+                };
+                self.last_report = Some(report);
+                Ok(Task::none())
+            }
+            Message::ExportReport(junit) => {
+                let Some(report) = &self.last_report else {
+                    return Ok(Task::none());
+                };
+                let suffix = if junit { "junit.xml" } else { "report.json" };
+                let path = report_path_for(&self.file_path, suffix);
+                let suite = std::path::Path::new(&self.file_path)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+                    .unwrap_or_else(|| "playback".to_string());
+                let report = report.clone();
+                Ok(Task::perform(
+                    async move { save_report(&report, &path, &suite).map(FileOpResult::Saved) },
+                    Message::FileOpFinished,
+                ))
+            }
+            Message::CloseReport => {
+                self.last_report = None;
                 Ok(Task::none())
             }
             Message::Clear => {
@@ -129,6 +275,8 @@ impl App {
                     return Ok(Task::none());
                 }
                 self.events.clear();
+                self.recording_screen = None;
+                self.recording_real_timestamps = true;
                 self.status = "Cleared all events.".to_string();
                 Ok(Task::none())
             }
@@ -141,6 +289,15 @@ impl App {
                 };
                 Ok(Task::none())
             }
+            Message::SetRecordWindowBound(enabled) => {
+                self.recorder_bind_window = enabled;
+                self.status = if enabled {
+                    "Window-bound recording: ON (editor window patterns)".to_string()
+                } else {
+                    "Window-bound recording: OFF".to_string()
+                };
+                Ok(Task::none())
+            }
             Message::Tick => {
                 if self.mode != Mode::Recording {
                     return Ok(Task::none());
@@ -160,11 +317,11 @@ impl App {
                         current_pos = (x, y);
                     }
 
-                    #[cfg(windows)]
+                    #[cfg(any(windows, target_os = "linux"))]
                     {
-                        let left_now = is_vk_down_windows(VK_LBUTTON);
-                        let right_now = is_vk_down_windows(VK_RBUTTON);
-                        let middle_now = is_vk_down_windows(VK_MBUTTON);
+                        let left_now = is_vk_down(VK_LBUTTON);
+                        let right_now = is_vk_down(VK_RBUTTON);
+                        let middle_now = is_vk_down(VK_MBUTTON);
 
                         if left_now && !state.left_down {
                             state.left_down = true;
@@ -290,21 +447,47 @@ impl App {
                         }
                     }
 
+                    // Modifiers only shape the rows of the keys pressed while
+                    // they are held; held keys repeat their press.
+                    for (key, is_down) in take_key_transitions() {
+                        if is_modifier_key(key) {
+                            state.modifiers_down.retain(|held| *held != key);
+                            if is_down {
+                                state.modifiers_down.push(key);
+                            }
+                        } else if is_down {
+                            if !state.keys_down.iter().any(|(held, _)| *held == key) {
+                                let modifiers = KeyModifiers::from_pressed(&state.modifiers_down);
+                                state.keys_down.push((key, modifiers));
+                                self.push_recorded_key(&mut state, &mut pushed, key, modifiers, true);
+                            }
+                        } else if let Some(index) = state.keys_down.iter().position(|(held, _)| *held == key) {
+                            let (_, down_modifiers) = state.keys_down.remove(index);
+                            self.push_recorded_key(&mut state, &mut pushed, key, down_modifiers, false);
+                        }
+                    }
+
+                    let (scroll_dx, scroll_dy) = take_scroll_notches();
+                    if scroll_dx != 0 || scroll_dy != 0 {
+                        self.push_recorded_scroll(&mut state, &mut pushed, Some(current_pos), scroll_dx, scroll_dy);
+                    }
+
                     if self.recorder_mouse_path_enabled {
                         let dx = (current_pos.0 - previous_pos.0).abs();
                         let dy = (current_pos.1 - previous_pos.1).abs();
                         let min_delta = self.recorder_mouse_path_min_delta_px as i32;
 
                         if dx >= min_delta || dy >= min_delta {
-                            state.synthetic_time_ms = state.synthetic_time_ms.saturating_add(1);
                             pushed.push(RecordedEvent {
-                                ms_from_start: state.synthetic_time_ms,
+                                ms_from_start: state.elapsed_ms(),
                                 kind: RecordedEventKind::Move {
                                     x: current_pos.0,
                                     y: current_pos.1,
                                 },
                                 pos: Some(current_pos),
                                 click_meta: None,
+                                on_error: None,
+                                monitor: None,
                             });
                         }
                     }
@@ -350,6 +533,9 @@ impl App {
                         if new_active != self.playback_active_index {
                             self.playback_active_index = new_active;
                             if let Some(i) = new_active {
+                                if self.playback_pause.as_ref().is_some_and(|p| p.is_paused()) {
+                                    self.status = format!("Paused before row {i}.");
+                                }
                                 let should_snap = self.playback_last_scrolled_index != Some(i);
 
                                 if should_snap {
@@ -403,11 +589,11 @@ impl App {
                     }
                 }
 
-                #[cfg(windows)]
+                #[cfg(any(windows, target_os = "linux"))]
                 {
-                    self.current_left_down = is_vk_down_windows(VK_LBUTTON);
-                    self.current_right_down = is_vk_down_windows(VK_RBUTTON);
-                    self.current_middle_down = is_vk_down_windows(VK_MBUTTON);
+                    self.current_left_down = is_vk_down(VK_LBUTTON);
+                    self.current_right_down = is_vk_down(VK_RBUTTON);
+                    self.current_middle_down = is_vk_down(VK_MBUTTON);
 
                     if self.editor_capture_armed {
                         if let Some((x, y, button_name)) = take_get_capture_hook_result() {
@@ -420,7 +606,7 @@ impl App {
                         }
                     }
 
-                    let esc_now = is_vk_down_windows(VK_ESCAPE);
+                    let esc_now = is_vk_down(VK_ESCAPE);
                     if esc_now && !self.esc_was_down {
                         if self.editor_capture_armed {
                             disarm_get_capture_hook();
@@ -474,11 +660,17 @@ impl App {
                     return Ok(Task::none());
                 }
                 let events = self.materialize_moves_grouped_events();
+                let header = RecordingHeader {
+                    // Rows built by hand use positions on this screen.
+                    screen: self.recording_screen.or_else(screen_layout),
+                    real_timestamps: self.recording_real_timestamps,
+                    ..RecordingHeader::default()
+                };
 
                 self.status = format!("Saving to {path} (materializing MOVES from MOVE samples)...");
                 Ok(Task::perform(
                     async move {
-                        save_events_to_file(&path, &events).map(FileOpResult::Saved)
+                        save_events_to_file(&path, &events, header).map(FileOpResult::Saved)
                     },
                     Message::FileOpFinished,
                 ))
@@ -496,7 +688,9 @@ impl App {
                 }
                 self.status = format!("Loading from {path}...");
                 Ok(Task::perform(
-                    async move { load_events_from_file(&path).map(FileOpResult::Loaded) },
+                    async move {
+                        load_events_from_file(&path).map(|(events, header)| FileOpResult::Loaded(events, header))
+                    },
                     Message::FileOpFinished,
                 ))
             }
@@ -505,10 +699,19 @@ impl App {
                     Ok(FileOpResult::Saved(msg)) => {
                         self.status = msg;
                     }
-                    Ok(FileOpResult::Loaded(events)) => {
+                    Ok(FileOpResult::Loaded(events, header)) => {
                         let count = events.len();
                         self.events = events;
-                        self.status = format!("Loaded {count} events.");
+                        let screen = header.recording_screen(screen_layout(), &monitors());
+                        self.recording_screen = screen;
+                        self.recording_real_timestamps = header.real_timestamps;
+                        self.status = match (screen, screen_layout()) {
+                            (Some(recorded), Some(current)) if recorded != current && self.playback_remap == RemapMode::Off => format!(
+                                "Loaded {count} events, recorded on a {recorded} screen; this one is {current}. \
+                                 Pick a screen remap before playing."
+                            ),
+                            _ => format!("Loaded {count} events."),
+                        };
                     }
                     Err(err) => self.status = err,
                 }
@@ -18,8 +18,11 @@ pub(crate) fn format_event_with_prev(
     };
 
     let source_from_meta = |meta: Option<&ClickListMeta>, pos: Option<(i32, i32)>| {
-        if meta.map(|m| m.use_find_image).unwrap_or(false) {
-            "TARGET".to_string()
+        if let Some(m) = meta.filter(|m| m.use_find_image) {
+            match m.target_offset {
+                (0, 0) => "TARGET".to_string(),
+                (dx, dy) => format!("TARGET{dx:+},{dy:+}"),
+            }
         } else if let Some((x, y)) = pos {
             format!("({x},{y})")
         } else {
@@ -48,7 +51,7 @@ pub(crate) fn format_event_with_prev(
         format!("{button}:{}|D:{}ms|{source}", mode_tag(mode), duration)
     };
 
-    match &ev.kind {
+    let (action, value, next_pos) = match &ev.kind {
         RecordedEventKind::Move { x, y } => {
             let _ = prev_pos.unwrap_or((*x, *y));
             (
@@ -65,6 +68,20 @@ pub(crate) fn format_event_with_prev(
                 last,
             )
         }
+        RecordedEventKind::Scroll { dx, dy } => {
+            let mut parts = Vec::new();
+            if *dy != 0 {
+                parts.push(format!("{} {}", if *dy > 0 { "DOWN" } else { "UP" }, dy.abs()));
+            }
+            if *dx != 0 {
+                parts.push(format!("{} {}", if *dx > 0 { "RIGHT" } else { "LEFT" }, dx.abs()));
+            }
+            (
+                format!("SCROLL|{}", parts.join(" ")),
+                wait_value(ev.click_meta.as_ref()),
+                ev.pos.or(prev_pos),
+            )
+        }
         RecordedEventKind::Wait { ms } => (
             "WAIT".to_string(),
             format!("wait {} ms", ms),
@@ -120,5 +137,85 @@ pub(crate) fn format_event_with_prev(
             wait_value(ev.click_meta.as_ref()),
             ev.pos.or(prev_pos),
         ),
+        RecordedEventKind::KeyDown { key, modifiers } => (
+            format!("KEY:DOWN|{}", modifiers.chord_label(key)),
+            wait_value(ev.click_meta.as_ref()),
+            prev_pos,
+        ),
+        RecordedEventKind::KeyUp { key, modifiers } => (
+            format!("KEY:UP|{}", modifiers.chord_label(key)),
+            wait_value(ev.click_meta.as_ref()),
+            prev_pos,
+        ),
+        RecordedEventKind::TypeText { text } => (
+            format!("TYPE|{text:?}"),
+            format!("{} chars | {}", text.chars().count(), wait_value(ev.click_meta.as_ref())),
+            prev_pos,
+        ),
+        RecordedEventKind::RepeatStart { count } => (
+            "REPEAT".to_string(),
+            format!("{count} times"),
+            prev_pos,
+        ),
+        RecordedEventKind::RepeatEnd => ("END REPEAT".to_string(), String::new(), prev_pos),
+        RecordedEventKind::IfImage {
+            precision,
+            timeout_ms,
+            ..
+        } => (
+            "IF|IMAGE".to_string(),
+            format!("{:.0}% | {} ms | {}", precision * 100.0, timeout_ms, wait_value(ev.click_meta.as_ref())),
+            ev.pos.or(prev_pos),
+        ),
+        RecordedEventKind::Else => ("ELSE".to_string(), String::new(), prev_pos),
+        RecordedEventKind::EndIf => ("END IF".to_string(), String::new(), prev_pos),
+        RecordedEventKind::WaitForImage {
+            precision,
+            timeout_ms,
+            poll_ms,
+            on_timeout,
+            ..
+        }
+        | RecordedEventKind::WaitUntilImageGone {
+            precision,
+            timeout_ms,
+            poll_ms,
+            on_timeout,
+            ..
+        } => (
+            if matches!(ev.kind, RecordedEventKind::WaitUntilImageGone { .. }) {
+                "WAIT|IMAGE GONE".to_string()
+            } else {
+                "WAIT|IMAGE".to_string()
+            },
+            format!(
+                "{:.0}% | {} ms every {} ms | {}",
+                precision * 100.0,
+                timeout_ms,
+                poll_ms,
+                on_timeout.label()
+            ),
+            ev.pos.or(prev_pos),
+        ),
+        RecordedEventKind::Label { name } => (format!("LABEL|{name}"), String::new(), prev_pos),
+        RecordedEventKind::BindWindow { title, class } => (
+            "WINDOW".to_string(),
+            window_pattern_label(title, class),
+            None,
+        ),
+        RecordedEventKind::EndWindow => ("END WINDOW".to_string(), String::new(), None),
+    };
+
+    // Rows on the primary monitor stay unmarked.
+    let value = match ev.monitor {
+        Some(monitor) if monitor > 1 && value.is_empty() => format!("monitor {monitor}"),
+        Some(monitor) if monitor > 1 => format!("{value} | monitor {monitor}"),
+        _ => value,
+    };
+
+    match &ev.on_error {
+        Some(on_error) if value.is_empty() => (action, format!("on error {}", on_error.label()), next_pos),
+        Some(on_error) => (action, format!("{value} | on error {}", on_error.label()), next_pos),
+        None => (action, value, next_pos),
     }
 }
\ No newline at end of file
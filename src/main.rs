@@ -16,26 +16,40 @@ use std::time::{Duration, Instant};
 mod model;
 mod platform;
 mod storage;
+mod report;
 mod view;
 mod controller;
 mod playback;
 mod formatting;
 mod message;
 mod state;
+mod cli;
 
-use model::{ClickEdgeMode, ClickListMeta, ClickTarget, MouseButton, RecordedEvent, RecordedEventKind, SearchAnchor};
+use model::{
+    block_jumps, is_modifier_key, label_rows, monitor_at, typed_char, window_pattern_label, window_scopes, ClickEdgeMode, ClickListMeta, ClickTarget, KeyModifiers, MouseButton, RecordedEvent,
+    MatchStrategy, MonitorTarget, OnError, OnTimeout, MatchFilter, RecordedEventKind, RemapMode, ScaleSweep, ScreenLayout, ScreenRemap, SearchAnchor,
+};
 use message::{FileOpResult, Message};
-use state::{FindTargetDraft, Mode, RecorderState, WaitDraft};
+use state::{
+    EditHistory, EditSnapshot, FindTargetDraft, MaskBrush, MatchTest, Mode, OnErrorChoice, OnTimeoutChoice, RecorderState, WaitDraft,
+};
 use formatting::format_event_with_prev;
-use playback::playback;
+use playback::{playback, test_template_match, PlaybackOptions, PlaybackPause, CANDIDATE_FLOOR};
 use platform::{
-    arm_get_capture_hook, capture_patch_png_base64, disarm_get_capture_hook, ensure_get_capture_hook_thread,
-    get_mouse_pos, is_vk_down_windows, jump_mouse_to, take_get_capture_hook_result, VK_ESCAPE, VK_LBUTTON, VK_MBUTTON,
-    VK_RBUTTON,
+    arm_get_capture_hook, capture_patch_png_base64, capture_screen, disarm_get_capture_hook, ensure_get_capture_hook_thread,
+    activate_window, find_window, get_mouse_pos, is_vk_down, jump_mouse_to, list_windows, monitors, screen_layout,
+    start_key_capture, start_scroll_capture, stop_key_capture, stop_scroll_capture, take_get_capture_hook_result,
+    take_key_transitions, take_scroll_notches, WindowInfo,
+    WindowMatcher, VK_ESCAPE, VK_LBUTTON, VK_MBUTTON, VK_RBUTTON,
 };
-use storage::{load_events_from_file, save_events_to_file};
+use storage::{load_events_from_file, save_events_to_file, RecordingHeader};
+use report::{failure_capture_dir, report_path_for, save_report, PlaybackReport, RowOutcome, RowReport};
 
 fn main() -> iced::Result {
+    if let Some(code) = cli::run_from_args() {
+        std::process::exit(code);
+    }
+
     iced::application(App::boot, App::update, App::view)
         .title(App::title)
         .scale_factor(App::ui_scale_factor)
@@ -50,9 +64,26 @@ fn main() -> iced::Result {
 
 struct App {
     ui_scale_percent: u16,
+    // Run-level playback speed, 25..=1000 (0.25x-10x)
+    playback_speed_percent: u16,
+    playback_faithful_timing: bool,
+    playback_on_error: OnErrorChoice,
+    playback_retry_times_text: String,
+    playback_retry_backoff_ms_text: String,
+    playback_remap: RemapMode,
+    playback_remap_offset_x_text: String,
+    playback_remap_offset_y_text: String,
 
     mode: Mode,
     events: Vec<RecordedEvent>,
+    /// Whether every row's `ms_from_start` is real; editor rows make it false.
+    recording_real_timestamps: bool,
+    /// Screen the rows were recorded on, saved in the file header.
+    recording_screen: Option<ScreenLayout>,
+    /// Client origin of the window the running recording is bound to.
+    recording_window_origin: Option<(i32, i32)>,
+    /// Monitors when recording started, for numbering recorded rows.
+    recording_monitors: Vec<ScreenLayout>,
     status: String,
 
     file_path: String,
@@ -65,6 +96,8 @@ struct App {
     recorder_wait_ms: u64,
     recorder_mouse_path_enabled: bool,
     recorder_mouse_path_min_delta_px: u16,
+    // Bind new recordings to the window matching the editor's window patterns
+    recorder_bind_window: bool,
 
     find_image_patch_size: u32,
     find_image_region_size: u32,
@@ -81,6 +114,17 @@ struct App {
     editor_click_max_hold_ms: u16,
     editor_target_precision_percent: u16,
     editor_target_timeout_ms: u16,
+    editor_match_strategy: MatchStrategy,
+    editor_match_nth_text: String,
+    editor_target_offset_x_text: String,
+    editor_target_offset_y_text: String,
+    editor_scale_sweep_enabled: bool,
+    editor_scale_min_text: String,
+    editor_scale_max_text: String,
+    editor_scale_step_text: String,
+    editor_match_filter: MatchFilter,
+    editor_search_monitor: MonitorTarget,
+    editor_preview_hover: Option<iced::Point>,
     editor_click_target: ClickTarget,
     editor_left_mode: ClickEdgeMode,
     editor_right_mode: ClickEdgeMode,
@@ -91,8 +135,26 @@ struct App {
     editor_last_capture_button: Option<&'static str>,
     editor_last_preview_at: Option<Instant>,
     editor_last_preview_pos: Option<(i32, i32)>,
+    editor_key_text: String,
+    editor_key_modifiers: KeyModifiers,
+    editor_type_text: String,
+    editor_repeat_count_text: String,
+    editor_wait_image_gone: bool,
+    editor_wait_poll_ms_text: String,
+    editor_on_timeout: OnTimeoutChoice,
+    // Name for new `Label` rows and the target of `OnTimeout::GotoLabel`
+    editor_label_text: String,
+    editor_on_error: OnErrorChoice,
+    editor_retry_times_text: String,
+    editor_retry_backoff_ms_text: String,
+    // Title and class regexes for window blocks
+    editor_window_title_text: String,
+    editor_window_class_text: String,
 
     selected_index: Option<usize>,
+    // Other end of a shift-click range; `selected_index` is the active end
+    selection_anchor: Option<usize>,
+    keyboard_modifiers: iced::keyboard::Modifiers,
     selected_wait_ms_text: String,
     selected_precision_text: String,
     selected_timeout_ms_text: String,
@@ -105,24 +167,42 @@ struct App {
     events_scroll_id: iced::widget::Id,
 
     playback_cancel: Option<Arc<AtomicBool>>,
+    playback_pause: Option<Arc<PlaybackPause>>,
     playback_progress: Option<Arc<AtomicUsize>>,
     playback_active_index: Option<usize>,
     playback_last_scrolled_index: Option<usize>,
     playback_progress_row_map: Vec<usize>,
+    last_report: Option<PlaybackReport>,
 
     window_width_px: f32,
     window_height_px: f32,
 
     // Shared recorder state for the background poller
     recorder_state: Arc<Mutex<RecorderState>>,
+
+    edit_history: EditHistory,
+    // Nested update() calls belong to the outer edit's undo entry
+    update_depth: u32,
 }
 
 impl Default for App {
     fn default() -> Self {
         Self {
             ui_scale_percent: 50,
+            playback_speed_percent: 100,
+            playback_faithful_timing: false,
+            playback_on_error: OnErrorChoice::Abort,
+            playback_retry_times_text: "3".to_string(),
+            playback_retry_backoff_ms_text: "500".to_string(),
+            playback_remap: RemapMode::Off,
+            playback_remap_offset_x_text: "0".to_string(),
+            playback_remap_offset_y_text: "0".to_string(),
             mode: Mode::Idle,
             events: Vec::new(),
+            recording_real_timestamps: true,
+            recording_screen: None,
+            recording_window_origin: None,
+            recording_monitors: Vec::new(),
             status: "Ready".to_string(),
             file_path: "recording.yaml".to_string(),
             current_pos: None,
@@ -133,6 +213,7 @@ impl Default for App {
             recorder_wait_ms: 20,
             recorder_mouse_path_enabled: false,
             recorder_mouse_path_min_delta_px: 0,
+            recorder_bind_window: false,
 
             find_image_patch_size: 64,
             find_image_region_size: 600,
@@ -149,6 +230,17 @@ impl Default for App {
             editor_click_max_hold_ms: 50,
             editor_target_precision_percent: 90,
             editor_target_timeout_ms: 2000,
+            editor_match_strategy: MatchStrategy::BestScore,
+            editor_match_nth_text: "1".to_string(),
+            editor_target_offset_x_text: "0".to_string(),
+            editor_target_offset_y_text: "0".to_string(),
+            editor_scale_sweep_enabled: false,
+            editor_scale_min_text: "0.5".to_string(),
+            editor_scale_max_text: "2.0".to_string(),
+            editor_scale_step_text: "0.25".to_string(),
+            editor_match_filter: MatchFilter::Grayscale,
+            editor_search_monitor: MonitorTarget::All,
+            editor_preview_hover: None,
             editor_click_target: ClickTarget::Left,
             editor_left_mode: ClickEdgeMode::Auto,
             editor_right_mode: ClickEdgeMode::Auto,
@@ -159,8 +251,23 @@ impl Default for App {
             editor_last_capture_button: None,
             editor_last_preview_at: None,
             editor_last_preview_pos: None,
+            editor_key_text: "return".to_string(),
+            editor_key_modifiers: KeyModifiers::default(),
+            editor_type_text: String::new(),
+            editor_repeat_count_text: "2".to_string(),
+            editor_wait_image_gone: false,
+            editor_wait_poll_ms_text: "50".to_string(),
+            editor_on_timeout: OnTimeoutChoice::Fail,
+            editor_label_text: String::new(),
+            editor_on_error: OnErrorChoice::RunDefault,
+            editor_retry_times_text: "3".to_string(),
+            editor_retry_backoff_ms_text: "500".to_string(),
+            editor_window_title_text: String::new(),
+            editor_window_class_text: String::new(),
 
             selected_index: None,
+            selection_anchor: None,
+            keyboard_modifiers: iced::keyboard::Modifiers::default(),
             selected_wait_ms_text: "1000".to_string(),
             selected_precision_text: "0.92".to_string(),
             selected_timeout_ms_text: "2000".to_string(),
@@ -171,13 +278,17 @@ impl Default for App {
             preview_cache: RefCell::new(HashMap::new()),
             events_scroll_id: iced::widget::Id::new("events-list"),
             playback_cancel: None,
+            playback_pause: None,
             playback_progress: None,
             playback_active_index: None,
             playback_last_scrolled_index: None,
             playback_progress_row_map: Vec::new(),
+            last_report: None,
             window_width_px: 1140.0,
             window_height_px: 1052.0,
             recorder_state: Arc::new(Mutex::new(RecorderState::default())),
+            edit_history: EditHistory::default(),
+            update_depth: 0,
         }
     }
 }
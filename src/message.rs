@@ -1,24 +1,45 @@
-use crate::model::{ClickEdgeMode, ClickTarget, RecordedEvent, SearchAnchor};
+use crate::model::{
+    ClickEdgeMode, ClickTarget, KeyModifiers, MatchFilter, MatchStrategy, MonitorTarget, RecordedEvent, RemapMode, SearchAnchor,
+};
+use crate::report::PlaybackReport;
+use crate::state::{MaskBrush, MatchTest, OnErrorChoice, OnTimeoutChoice};
+use crate::storage::RecordingHeader;
 
 #[derive(Debug, Clone)]
 pub(crate) enum FileOpResult {
     Saved(String),
-    Loaded(Vec<RecordedEvent>),
+    Loaded(Vec<RecordedEvent>, RecordingHeader),
 }
 
 #[derive(Debug, Clone)]
 pub(crate) enum Message {
     UiScaleChanged(u16),
+    PlaybackSpeedChanged(u16),
+    SetFaithfulTiming(bool),
+    PlaybackOnErrorChanged(OnErrorChoice),
+    PlaybackRetryTimesChanged(String),
+    PlaybackRetryBackoffMsChanged(String),
+    PlaybackRemapChanged(RemapMode),
+    PlaybackRemapOffsetXChanged(String),
+    PlaybackRemapOffsetYChanged(String),
 
     StartRecording,
     StopRecording,
     StartPlayback,
+    TogglePausePlayback,
+    StepPlayback,
+    PlayFromSelected,
+    PlaySelection,
     Clear,
     SetMousePathEnabled(bool),
+    SetRecordWindowBound(bool),
     MousePathMinDeltaPxChanged(u16),
     WindowResized(f32, f32),
     Tick,
-    PlaybackFinished(Result<(), String>),
+    PlaybackFinished(PlaybackReport),
+    /// `true` exports JUnit XML, `false` JSON, next to the recording file.
+    ExportReport(bool),
+    CloseReport,
     PosTick,
 
     FilePathChanged(String),
@@ -38,6 +59,22 @@ pub(crate) enum Message {
     FindTargetLimitRegionToggled(bool),
     FindTargetRegionSizeChanged(String),
     FindTargetAnchorSelected(SearchAnchor),
+    FindTargetMatchStrategySelected(MatchStrategy),
+    FindTargetMatchNthChanged(String),
+    FindTargetOffsetXChanged(String),
+    FindTargetOffsetYChanged(String),
+    FindTargetScaleSweepToggled(bool),
+    FindTargetScaleMinChanged(String),
+    FindTargetScaleMaxChanged(String),
+    FindTargetScaleStepChanged(String),
+    FindTargetMatchFilterSelected(MatchFilter),
+    FindTargetSearchMonitorSelected(MonitorTarget),
+    /// Cursor position over the mask painter, as a fraction of the patch size.
+    FindTargetMaskHovered(iced::Point),
+    FindTargetMaskPressed,
+    FindTargetMaskReleased,
+    FindTargetMaskBrushSelected(MaskBrush),
+    FindTargetClearMask,
 
     FindTargetStartCapture,
     FindTargetCaptureTick,
@@ -45,6 +82,10 @@ pub(crate) enum Message {
     FindTargetPathChanged(String),
     FindTargetLoadFromPath,
 
+    OpenFindTargetModal,
+    FindTargetTestMatch,
+    FindTargetTestMatchFinished(Result<Option<MatchTest>, String>),
+
     EditorLeftModeSelected(ClickEdgeMode),
     EditorRightModeSelected(ClickEdgeMode),
     EditorMiddleModeSelected(ClickEdgeMode),
@@ -57,10 +98,49 @@ pub(crate) enum Message {
     EditorTargetPrecisionChanged(u16),
     EditorTargetTimeoutMsChanged(u16),
     EditorUseFindImageToggled(bool),
+    EditorMatchStrategySelected(MatchStrategy),
+    EditorMatchNthChanged(String),
+    EditorTargetOffsetXChanged(String),
+    EditorTargetOffsetYChanged(String),
+    EditorScaleSweepToggled(bool),
+    EditorScaleMinChanged(String),
+    EditorScaleMaxChanged(String),
+    EditorScaleStepChanged(String),
+    EditorMatchFilterSelected(MatchFilter),
+    EditorSearchMonitorSelected(MonitorTarget),
+    /// Cursor position over the template preview, as a fraction of its size.
+    EditorPreviewHovered(iced::Point),
+    /// Sets the target offset to the last hovered point of the preview.
+    EditorPickTargetOffset,
     EditorStartGetXY,
     EditorJumpToXY,
     EditorInsertOrApply,
     EditorInsertBelowSelected,
+    EditorKeyChanged(String),
+    EditorKeyModifiersChanged(KeyModifiers),
+    EditorTypeTextChanged(String),
+    EditorInsertOrApplyKey,
+    EditorInsertOrApplyText,
+    EditorRepeatCountChanged(String),
+    EditorInsertOrApplyRepeat,
+    EditorInsertOrApplyIfImage,
+    EditorWaitImageGoneToggled(bool),
+    EditorWaitPollMsChanged(String),
+    EditorOnTimeoutChanged(OnTimeoutChoice),
+    EditorLabelChanged(String),
+    EditorInsertOrApplyWaitImage,
+    EditorInsertOrApplyLabel,
+    EditorOnErrorChanged(OnErrorChoice),
+    EditorRetryTimesChanged(String),
+    EditorRetryBackoffMsChanged(String),
+    EditorApplyOnError,
+    EditorWindowTitleChanged(String),
+    EditorWindowClassChanged(String),
+    EditorInsertOrApplyWindow,
+
+    Undo,
+    Redo,
+    ModifiersChanged(iced::keyboard::Modifiers),
 
     RowJump(usize),
     RowClone(usize),
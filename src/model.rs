@@ -34,7 +34,7 @@ impl ClickTarget {
     }
 }
 
-#[derive(Debug, Clone, Copy, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum MouseButton {
     Left,
     Right,
@@ -64,11 +64,317 @@ impl SearchAnchor {
     }
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+/// Which hit a template search uses when the template is on screen more than once.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum MatchStrategy {
+    #[default]
+    BestScore,
+    NearestAnchor,
+    /// 1-based, in reading order: top row first, left to right within a row.
+    Nth(u32),
+    Leftmost,
+    Topmost,
+    Random,
+}
+
+impl std::fmt::Display for MatchStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl MatchStrategy {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::BestScore => "Best score",
+            Self::NearestAnchor => "Nearest anchor",
+            Self::Nth(_) => "Nth match",
+            Self::Leftmost => "Leftmost",
+            Self::Topmost => "Topmost",
+            Self::Random => "Random",
+        }
+    }
+
+    /// Every strategy, with `nth` filled into `Nth`, for pick lists.
+    pub fn all(nth: u32) -> [Self; 6] {
+        [
+            Self::BestScore,
+            Self::NearestAnchor,
+            Self::Nth(nth),
+            Self::Leftmost,
+            Self::Topmost,
+            Self::Random,
+        ]
+    }
+}
+
+/// How the template and the screen are compared.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum MatchFilter {
+    /// Correlation of the grayscale images, the backend's own search.
+    #[default]
+    Grayscale,
+    /// Correlation of edge maps, so fills and color changes don't matter.
+    Edges,
+    /// Grayscale, skipping pixels the patch's alpha channel makes transparent.
+    Masked,
+}
+
+impl std::fmt::Display for MatchFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl MatchFilter {
+    pub const ALL: [Self; 3] = [Self::Grayscale, Self::Edges, Self::Masked];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Grayscale => "Grayscale",
+            Self::Edges => "Edges",
+            Self::Masked => "Masked",
+        }
+    }
+}
+
+/// Which monitors a template search looks at. Monitors are numbered from 1,
+/// primary first, like `RecordedEvent::monitor`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum MonitorTarget {
+    /// The whole desktop.
+    #[default]
+    All,
+    /// The monitor the row was recorded on; the whole desktop when unknown.
+    Recorded,
+    Monitor(u32),
+}
+
+impl std::fmt::Display for MonitorTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::All => f.write_str("All monitors"),
+            Self::Recorded => f.write_str("Recorded monitor"),
+            Self::Monitor(number) => write!(f, "Monitor {number}"),
+        }
+    }
+}
+
+impl MonitorTarget {
+    /// Pick-list choices for `count` connected monitors.
+    pub fn options(count: usize) -> Vec<Self> {
+        let monitors = (1..=count.max(1) as u32).map(Self::Monitor);
+        [Self::All, Self::Recorded].into_iter().chain(monitors).collect()
+    }
+
+    /// The desktop area to search, or `None` for the whole desktop.
+    pub fn area(self, recorded: Option<u32>, monitors: &[ScreenLayout]) -> Result<Option<ScreenLayout>, String> {
+        let number = match (self, recorded) {
+            (Self::Monitor(number), _) | (Self::Recorded, Some(number)) => number,
+            (Self::All, _) | (Self::Recorded, None) => return Ok(None),
+        };
+        number
+            .checked_sub(1)
+            .and_then(|index| monitors.get(index as usize))
+            .map(|&monitor| Some(monitor))
+            .ok_or_else(|| format!("Monitor {number} is not connected ({} found)", monitors.len()))
+    }
+}
+
+/// Template scales to try besides the recorded one, for patches captured at
+/// another display scaling.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScaleSweep {
+    pub min: f32,
+    pub max: f32,
+    pub step: f32,
+}
+
+impl Default for ScaleSweep {
+    fn default() -> Self {
+        Self {
+            min: 0.5,
+            max: 2.0,
+            step: 0.25,
+        }
+    }
+}
+
+impl ScaleSweep {
+    /// Every scale in the sweep, most likely first: `preferred` (the scale that
+    /// won earlier in the run), then 1.0, then the rest by distance from 1.0.
+    pub fn scales(&self, preferred: Option<f32>) -> Vec<f32> {
+        let min = self.min.clamp(0.1, 1.0);
+        let max = self.max.clamp(1.0, 4.0);
+        let step = self.step.max(0.01);
+
+        let mut scales = vec![1.0];
+        let steps = ((max - min) / step).round() as u32;
+        for i in 0..=steps.min(100) {
+            let scale = ((min + i as f32 * step) * 100.0).round() / 100.0;
+            if scale <= max + 0.001 && !scales.iter().any(|s: &f32| (s - scale).abs() < 0.001) {
+                scales.push(scale);
+            }
+        }
+        // ln makes 0.5x and 2x equally far from 1x.
+        scales.sort_by(|a, b| a.ln().abs().total_cmp(&b.ln().abs()));
+        if let Some(preferred) = preferred.filter(|p| scales.iter().any(|s| (s - p).abs() < 0.001)) {
+            scales.retain(|s| (s - preferred).abs() >= 0.001);
+            scales.insert(0, preferred);
+        }
+        scales
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyModifiers {
+    pub ctrl: bool,
+    pub shift: bool,
+    pub alt: bool,
+    pub meta: bool,
+}
+
+impl KeyModifiers {
+    pub fn from_pressed(keys: &[&str]) -> Self {
+        Self {
+            ctrl: keys.contains(&"ctrl"),
+            shift: keys.contains(&"shift"),
+            alt: keys.contains(&"alt"),
+            meta: keys.contains(&"win"),
+        }
+    }
+
+    /// rustautogui key names for the held modifiers, in press order.
+    pub fn key_names(self) -> Vec<&'static str> {
+        [
+            (self.ctrl, "ctrl"),
+            (self.shift, "shift"),
+            (self.alt, "alt"),
+            (self.meta, "win"),
+        ]
+        .into_iter()
+        .filter_map(|(held, name)| held.then_some(name))
+        .collect()
+    }
+
+    pub fn chord_label(self, key: &str) -> String {
+        let mut parts: Vec<String> = self
+            .key_names()
+            .into_iter()
+            .map(|name| name.to_uppercase())
+            .collect();
+        parts.push(key.to_uppercase());
+        parts.join("+")
+    }
+}
+
+/// What a wait row does when its condition is not met in time.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum OnTimeout {
+    /// Stop playback with an error.
+    #[default]
+    Fail,
+    /// Carry on with the next row.
+    Continue,
+    /// Carry on after the `Label` row with this name.
+    GotoLabel(String),
+}
+
+impl OnTimeout {
+    pub fn label(&self) -> String {
+        match self {
+            Self::Fail => "fail".to_string(),
+            Self::Continue => "continue".to_string(),
+            Self::GotoLabel(name) => format!("goto {name}"),
+        }
+    }
+}
+
+/// What playback does when a row fails, e.g. its template is never found.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub enum OnError {
+    /// Stop playback with the error.
+    #[default]
+    Abort,
+    /// Leave the row out and carry on with the next one.
+    Skip,
+    /// Run the row again up to `times` times, waiting `backoff_ms` before the
+    /// first retry and twice as long before each further one, then abort.
+    Retry { times: u32, backoff_ms: u64 },
+    /// Carry on after the `Label` row with this name.
+    GotoLabel(String),
+}
+
+impl OnError {
+    pub fn label(&self) -> String {
+        match self {
+            Self::Abort => "abort".to_string(),
+            Self::Skip => "skip".to_string(),
+            Self::Retry { times, backoff_ms } => format!("retry {times}x from {backoff_ms} ms"),
+            Self::GotoLabel(name) => format!("goto {name}"),
+        }
+    }
+}
+
+pub fn default_poll_ms() -> u64 {
+    50
+}
+
+/// For `skip_serializing_if`, so rows that keep a field's default save as they
+/// did before the field existed.
+pub fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
+
+pub fn is_modifier_key(key: &str) -> bool {
+    matches!(key, "ctrl" | "shift" | "alt" | "win")
+}
+
+/// The character a plain key press produces, used to fold recorded presses into
+/// `TypeText` rows. Only layout-independent keys qualify.
+pub fn typed_char(key: &str, modifiers: KeyModifiers) -> Option<char> {
+    if modifiers.ctrl || modifiers.alt || modifiers.meta {
+        return None;
+    }
+
+    if key == "space" {
+        return Some(' ');
+    }
+
+    let mut chars = key.chars();
+    let (Some(c), None) = (chars.next(), chars.next()) else {
+        return None;
+    };
+
+    if c.is_ascii_lowercase() {
+        Some(if modifiers.shift { c.to_ascii_uppercase() } else { c })
+    } else if c.is_ascii_digit() || c.is_ascii_punctuation() {
+        if modifiers.shift {
+            shifted_char(c)
+        } else {
+            Some(c)
+        }
+    } else {
+        None
+    }
+}
+
+/// What a digit or punctuation key types with shift held, on a US layout.
+fn shifted_char(c: char) -> Option<char> {
+    const PLAIN: &str = "1234567890-=[]\\;',./`";
+    const SHIFTED: &str = "!@#$%^&*()_+{}|:\"<>?~";
+    PLAIN.find(c).and_then(|index| SHIFTED.chars().nth(index))
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(deny_unknown_fields)]
 pub enum RecordedEventKind {
     Move { x: i32, y: i32 },
     Moves { points: Vec<(i32, i32)> },
+    /// Wheel notches; positive `dx` scrolls right, positive `dy` scrolls down.
+    Scroll { dx: i32, dy: i32 },
     Wait { ms: u64 },
     FindTarget {
         patch_png_base64: String,
@@ -77,6 +383,18 @@ pub enum RecordedEventKind {
         timeout_ms: u64,
         search_anchor: SearchAnchor,
         search_region_size: Option<u32>,
+        #[serde(default, skip_serializing_if = "is_default")]
+        match_strategy: MatchStrategy,
+        /// Added to the match center before moving, like `ClickListMeta::target_offset`.
+        #[serde(default, skip_serializing_if = "is_default")]
+        target_offset: (i32, i32),
+        /// Like `ClickListMeta::scale_sweep`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        scale_sweep: Option<ScaleSweep>,
+        #[serde(default, skip_serializing_if = "is_default")]
+        match_filter: MatchFilter,
+        #[serde(default, skip_serializing_if = "is_default")]
+        search_monitor: MonitorTarget,
     },
     LeftDown {
         patch_png_base64: Option<String>,
@@ -105,18 +423,142 @@ pub enum RecordedEventKind {
     MiddleClick {
         patch_png_base64: Option<String>,
     },
+    KeyDown {
+        key: String,
+        #[serde(default)]
+        modifiers: KeyModifiers,
+    },
+    KeyUp {
+        key: String,
+        #[serde(default)]
+        modifiers: KeyModifiers,
+    },
+    TypeText {
+        text: String,
+    },
+    /// Runs the rows up to the matching `RepeatEnd` `count` times; 0 skips them.
+    RepeatStart {
+        count: u32,
+    },
+    RepeatEnd,
+    /// Runs the rows up to `Else` (or `EndIf`) when the template is found within
+    /// `timeout_ms`, otherwise the rows between `Else` and `EndIf`. A miss never
+    /// fails the run.
+    ///
+    /// Image conditions and waits have no match settings of their own: they look
+    /// for the best grayscale match at 1x anywhere on the desktop, like a
+    /// `FindTarget` with the default strategy, filter, offset, sweep and monitor.
+    IfImage {
+        patch_png_base64: String,
+        patch_size: u32,
+        precision: f32,
+        timeout_ms: u64,
+        search_anchor: SearchAnchor,
+        search_region_size: Option<u32>,
+    },
+    Else,
+    EndIf,
+    /// Blocks until the template is on screen, checking every `poll_ms`. Searches
+    /// like `IfImage`.
+    WaitForImage {
+        patch_png_base64: String,
+        patch_size: u32,
+        precision: f32,
+        timeout_ms: u64,
+        #[serde(default = "default_poll_ms")]
+        poll_ms: u64,
+        search_anchor: SearchAnchor,
+        search_region_size: Option<u32>,
+        #[serde(default)]
+        on_timeout: OnTimeout,
+    },
+    /// Blocks until the template is no longer on screen, checking every `poll_ms`.
+    WaitUntilImageGone {
+        patch_png_base64: String,
+        patch_size: u32,
+        precision: f32,
+        timeout_ms: u64,
+        #[serde(default = "default_poll_ms")]
+        poll_ms: u64,
+        search_anchor: SearchAnchor,
+        search_region_size: Option<u32>,
+        #[serde(default)]
+        on_timeout: OnTimeout,
+    },
+    /// Jump target for `OnTimeout::GotoLabel`; does nothing when reached.
+    Label {
+        name: String,
+    },
+    /// Binds the rows up to the matching `EndWindow` to the first top-level
+    /// window whose title and class match these regexes (empty matches any).
+    /// Their positions are relative to the window's client area. Playback
+    /// brings the window to the front and fails when none matches.
+    BindWindow {
+        title: String,
+        class: String,
+    },
+    EndWindow,
+}
+
+impl RecordedEventKind {
+    /// The label this row may jump to, if any.
+    pub fn goto_label(&self) -> Option<&str> {
+        match self {
+            Self::WaitForImage { on_timeout, .. } | Self::WaitUntilImageGone { on_timeout, .. } => {
+                match on_timeout {
+                    OnTimeout::GotoLabel(name) => Some(name),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+/// `title ~ "..."`, `class ~ "..."` or both, for row text and errors.
+pub fn window_pattern_label(title: &str, class: &str) -> String {
+    match (title.is_empty(), class.is_empty()) {
+        (true, true) => "any window".to_string(),
+        (false, true) => format!("title ~ {title:?}"),
+        (true, false) => format!("class ~ {class:?}"),
+        (false, false) => format!("title ~ {title:?}, class ~ {class:?}"),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct RecordedEvent {
     pub ms_from_start: u128,
     pub kind: RecordedEventKind,
     pub pos: Option<(i32, i32)>,
     pub click_meta: Option<ClickListMeta>,
+    /// Overrides the run-level failure policy for this row.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_error: Option<OnError>,
+    /// Monitor `pos` was recorded on, numbered like `MonitorTarget::Monitor`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub monitor: Option<u32>,
+}
+
+impl RecordedEvent {
+    /// Applies `f` to the row position, a `Move` target and every `Moves` point.
+    pub fn map_positions(&mut self, mut f: impl FnMut((i32, i32)) -> (i32, i32)) {
+        if let Some(pos) = self.pos {
+            self.pos = Some(f(pos));
+        }
+        match &mut self.kind {
+            RecordedEventKind::Move { x, y } => (*x, *y) = f((*x, *y)),
+            RecordedEventKind::Moves { points } => {
+                for point in points {
+                    *point = f(*point);
+                }
+            }
+            _ => {}
+        }
+    }
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct ClickListMeta {
     pub left_mode: ClickEdgeMode,
@@ -128,6 +570,19 @@ pub struct ClickListMeta {
     pub use_find_image: bool,
     pub target_precision: f32,
     pub target_timeout_ms: u64,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub match_strategy: MatchStrategy,
+    /// Where to click relative to the found template's center, e.g. a button
+    /// to the right of a matched label.
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub target_offset: (i32, i32),
+    /// Also tries the template at these scales; `None` matches at 1x only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale_sweep: Option<ScaleSweep>,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub match_filter: MatchFilter,
+    #[serde(default, skip_serializing_if = "is_default")]
+    pub search_monitor: MonitorTarget,
 }
 
 impl Default for ClickListMeta {
@@ -142,6 +597,555 @@ impl Default for ClickListMeta {
             use_find_image: false,
             target_precision: 0.90,
             target_timeout_ms: 2000,
+            match_strategy: MatchStrategy::BestScore,
+            target_offset: (0, 0),
+            scale_sweep: None,
+            match_filter: MatchFilter::Grayscale,
+            search_monitor: MonitorTarget::All,
+        }
+    }
+}
+
+/// The screen a recording's positions refer to, in desktop coordinates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScreenLayout {
+    pub origin: (i32, i32),
+    pub size: (i32, i32),
+}
+
+impl ScreenLayout {
+    pub fn contains(&self, (x, y): (i32, i32)) -> bool {
+        let (left, top) = self.origin;
+        (left..left + self.size.0).contains(&x) && (top..top + self.size.1).contains(&y)
+    }
+}
+
+/// Number of the monitor containing `pos`, counting from 1 in `monitors` order.
+pub fn monitor_at(monitors: &[ScreenLayout], pos: (i32, i32)) -> Option<u32> {
+    monitors
+        .iter()
+        .position(|monitor| monitor.contains(pos))
+        .map(|index| index as u32 + 1)
+}
+
+impl std::fmt::Display for ScreenLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}x{}", self.size.0, self.size.1)?;
+        if self.origin != (0, 0) {
+            write!(f, " at ({},{})", self.origin.0, self.origin.1)?;
+        }
+        Ok(())
+    }
+}
+
+/// How recorded positions are moved onto a screen that differs from the
+/// recording's.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RemapMode {
+    /// Play positions as recorded.
+    #[default]
+    Off,
+    /// Stretch positions with the screen size.
+    Scale,
+    /// Keep each position's distance from one corner of the screen.
+    AnchorTopLeft,
+    AnchorTopRight,
+    AnchorBottomLeft,
+    AnchorBottomRight,
+    /// Shift every position by a fixed amount.
+    Offset,
+}
+
+impl std::fmt::Display for RemapMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl RemapMode {
+    pub const ALL: [Self; 7] = [
+        Self::Off,
+        Self::Scale,
+        Self::AnchorTopLeft,
+        Self::AnchorTopRight,
+        Self::AnchorBottomLeft,
+        Self::AnchorBottomRight,
+        Self::Offset,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Off => "As recorded",
+            Self::Scale => "Scale",
+            Self::AnchorTopLeft => "Top-left",
+            Self::AnchorTopRight => "Top-right",
+            Self::AnchorBottomLeft => "Bottom-left",
+            Self::AnchorBottomRight => "Bottom-right",
+            Self::Offset => "Offset",
+        }
+    }
+}
+
+/// Maps positions recorded on `from` onto `to`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScreenRemap {
+    pub from: ScreenLayout,
+    pub to: ScreenLayout,
+    pub mode: RemapMode,
+    /// Shift used by `RemapMode::Offset`.
+    pub offset: (i32, i32),
+}
+
+impl ScreenRemap {
+    pub fn apply(&self, (x, y): (i32, i32)) -> (i32, i32) {
+        let (from, to) = (self.from, self.to);
+        let (fx, fy) = (from.origin.0, from.origin.1);
+        let (tx, ty) = (to.origin.0, to.origin.1);
+        let (fw, fh) = (from.size.0.max(1), from.size.1.max(1));
+        let (tw, th) = (to.size.0.max(1), to.size.1.max(1));
+        // Distances from the left/top and from the right/bottom edge
+        let near = |p: i32, f: i32, t: i32| t + (p - f);
+        let far = |p: i32, f: i32, fs: i32, t: i32, ts: i32| (t + ts) - (f + fs - p);
+
+        match self.mode {
+            RemapMode::Off => (x, y),
+            RemapMode::Scale => (
+                tx + ((x - fx) as i64 * tw as i64 / fw as i64) as i32,
+                ty + ((y - fy) as i64 * th as i64 / fh as i64) as i32,
+            ),
+            RemapMode::AnchorTopLeft => (near(x, fx, tx), near(y, fy, ty)),
+            RemapMode::AnchorTopRight => (far(x, fx, fw, tx, tw), near(y, fy, ty)),
+            RemapMode::AnchorBottomLeft => (near(x, fx, tx), far(y, fy, fh, ty, th)),
+            RemapMode::AnchorBottomRight => (far(x, fx, fw, tx, tw), far(y, fy, fh, ty, th)),
+            RemapMode::Offset => (x + self.offset.0, y + self.offset.1),
+        }
+    }
+}
+
+/// Where each block marker continues when its rows are skipped: `RepeatStart`
+/// to its `RepeatEnd`, `IfImage` to its `Else` (or `EndIf`), `Else` to its
+/// `EndIf` and `BindWindow` to its `EndWindow`. `None` for all other rows.
+/// Fails on the first unbalanced marker.
+pub fn block_jumps(events: &[RecordedEvent]) -> Result<Vec<Option<usize>>, String> {
+    let mut jumps = vec![None; events.len()];
+    // Open blocks: start row, and the `Else` row once one is seen
+    let mut open: Vec<(usize, Option<usize>)> = Vec::new();
+
+    for (index, ev) in events.iter().enumerate() {
+        let top = open.last().map(|&(start, _)| &events[start].kind);
+        match ev.kind {
+            RecordedEventKind::RepeatStart { .. }
+            | RecordedEventKind::IfImage { .. }
+            | RecordedEventKind::BindWindow { .. } => {
+                open.push((index, None));
+            }
+            RecordedEventKind::RepeatEnd => {
+                if !matches!(top, Some(RecordedEventKind::RepeatStart { .. })) {
+                    return Err(format!("row {index}: RepeatEnd has no matching RepeatStart"));
+                }
+                let (start, _) = open.pop().unwrap_or_default();
+                jumps[start] = Some(index);
+            }
+            RecordedEventKind::Else => match open.last_mut() {
+                Some((start, else_row @ None))
+                    if matches!(events[*start].kind, RecordedEventKind::IfImage { .. }) =>
+                {
+                    jumps[*start] = Some(index);
+                    *else_row = Some(index);
+                }
+                _ => return Err(format!("row {index}: Else has no matching IfImage")),
+            },
+            RecordedEventKind::EndIf => {
+                if !matches!(top, Some(RecordedEventKind::IfImage { .. })) {
+                    return Err(format!("row {index}: EndIf has no matching IfImage"));
+                }
+                let (start, else_row) = open.pop().unwrap_or_default();
+                jumps[else_row.unwrap_or(start)] = Some(index);
+            }
+            RecordedEventKind::EndWindow => {
+                if !matches!(top, Some(RecordedEventKind::BindWindow { .. })) {
+                    return Err(format!("row {index}: EndWindow has no matching BindWindow"));
+                }
+                let (start, _) = open.pop().unwrap_or_default();
+                jumps[start] = Some(index);
+            }
+            _ => {}
+        }
+    }
+
+    match open.pop() {
+        Some((start, _)) => Err(format!(
+            "row {start}: {} is never closed",
+            match events[start].kind {
+                RecordedEventKind::IfImage { .. } => "IfImage",
+                RecordedEventKind::BindWindow { .. } => "BindWindow",
+                _ => "RepeatStart",
+            }
+        )),
+        None => Ok(jumps),
+    }
+}
+
+/// The `BindWindow` row whose window each row's positions are relative to, i.e.
+/// the innermost window block around it. Expects balanced blocks.
+pub fn window_scopes(events: &[RecordedEvent]) -> Vec<Option<usize>> {
+    let mut scopes = Vec::with_capacity(events.len());
+    let mut open: Vec<usize> = Vec::new();
+    for (index, ev) in events.iter().enumerate() {
+        scopes.push(open.last().copied());
+        match ev.kind {
+            RecordedEventKind::BindWindow { .. } => open.push(index),
+            RecordedEventKind::EndWindow => {
+                open.pop();
+            }
+            _ => {}
         }
     }
+    scopes
+}
+
+/// Row of every `Label`, by name. Fails on empty or duplicate names and on
+/// jumps to a label that does not exist.
+pub fn label_rows(events: &[RecordedEvent]) -> Result<std::collections::HashMap<String, usize>, String> {
+    let mut rows = std::collections::HashMap::new();
+    for (index, ev) in events.iter().enumerate() {
+        if let RecordedEventKind::Label { name } = &ev.kind {
+            if name.trim().is_empty() {
+                return Err(format!("row {index}: Label has no name"));
+            }
+            if let Some(first) = rows.insert(name.clone(), index) {
+                return Err(format!("row {index}: label \"{name}\" is already used on row {first}"));
+            }
+        }
+    }
+
+    for (index, ev) in events.iter().enumerate() {
+        let on_error = match &ev.on_error {
+            Some(OnError::GotoLabel(name)) => Some(name.as_str()),
+            _ => None,
+        };
+        for name in ev.kind.goto_label().into_iter().chain(on_error) {
+            if !rows.contains_key(name) {
+                return Err(format!("row {index}: no label named \"{name}\""));
+            }
+        }
+    }
+    Ok(rows)
+}
+
+/// Row fixtures shared by the test modules.
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::*;
+
+    pub(crate) fn row(kind: RecordedEventKind) -> RecordedEvent {
+        RecordedEvent {
+            ms_from_start: 0,
+            kind,
+            pos: None,
+            click_meta: None,
+            on_error: None,
+            monitor: None,
+        }
+    }
+
+    pub(crate) fn text(text: &str) -> RecordedEvent {
+        row(RecordedEventKind::TypeText { text: text.to_string() })
+    }
+
+    pub(crate) fn label(name: &str) -> RecordedEvent {
+        row(RecordedEventKind::Label { name: name.to_string() })
+    }
+
+    /// An 8x8 `IfImage` row searching for `patch_png_base64` at 0.9 precision.
+    pub(crate) fn if_image(patch_png_base64: String) -> RecordedEvent {
+        row(RecordedEventKind::IfImage {
+            patch_png_base64,
+            patch_size: 8,
+            precision: 0.9,
+            timeout_ms: 0,
+            search_anchor: SearchAnchor::RecordedClick,
+            search_region_size: None,
+        })
+    }
+
+    /// A `WaitForImage` row, or `WaitUntilImageGone` when `gone`, polling every
+    /// 100 ms like `if_image` searches.
+    pub(crate) fn wait_for_image(patch_png_base64: String, gone: bool, timeout_ms: u64, on_timeout: OnTimeout) -> RecordedEvent {
+        let (patch_size, precision, poll_ms) = (8, 0.9, 100);
+        let (search_anchor, search_region_size) = (SearchAnchor::RecordedClick, None);
+        row(if gone {
+            RecordedEventKind::WaitUntilImageGone {
+                patch_png_base64,
+                patch_size,
+                precision,
+                timeout_ms,
+                poll_ms,
+                search_anchor,
+                search_region_size,
+                on_timeout,
+            }
+        } else {
+            RecordedEventKind::WaitForImage {
+                patch_png_base64,
+                patch_size,
+                precision,
+                timeout_ms,
+                poll_ms,
+                search_anchor,
+                search_region_size,
+                on_timeout,
+            }
+        })
+    }
+
+    pub(crate) fn bind_window(title: &str) -> RecordedEvent {
+        row(RecordedEventKind::BindWindow {
+            title: title.to_string(),
+            class: String::new(),
+        })
+    }
+
+    pub(crate) fn layout(origin: (i32, i32), size: (i32, i32)) -> ScreenLayout {
+        ScreenLayout { origin, size }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::{bind_window, label, layout, row, text, wait_for_image};
+    use super::*;
+
+    #[test]
+    fn plain_printable_keys_are_typed() {
+        let shift = KeyModifiers {
+            shift: true,
+            ..KeyModifiers::default()
+        };
+        let ctrl = KeyModifiers {
+            ctrl: true,
+            ..KeyModifiers::default()
+        };
+        assert_eq!(typed_char("a", KeyModifiers::default()), Some('a'));
+        assert_eq!(typed_char("a", shift), Some('A'));
+        assert_eq!(typed_char("7", KeyModifiers::default()), Some('7'));
+        assert_eq!(typed_char("space", shift), Some(' '));
+        assert_eq!(typed_char("7", shift), Some('&'));
+        assert_eq!(typed_char("0", shift), Some(')'));
+        assert_eq!(typed_char("/", shift), Some('?'));
+        assert_eq!(typed_char("'", shift), Some('"'));
+        assert_eq!(typed_char("\\", shift), Some('|'));
+        assert_eq!(typed_char("a", ctrl), None);
+        assert_eq!(typed_char("return", KeyModifiers::default()), None);
+        assert_eq!(typed_char("pageup", KeyModifiers::default()), None);
+    }
+
+    #[test]
+    fn file_names_type_back_as_recorded() {
+        let plain = KeyModifiers::default();
+        let typed: Option<String> = "report.txt".chars().map(|c| typed_char(&c.to_string(), plain)).collect();
+        assert_eq!(typed.as_deref(), Some("report.txt"));
+    }
+
+    #[test]
+    fn modifiers_are_named_in_press_order() {
+        let modifiers = KeyModifiers::from_pressed(&["win", "shift", "ctrl", "a"]);
+        assert_eq!(modifiers.key_names(), ["ctrl", "shift", "win"]);
+        assert_eq!(modifiers.chord_label("a"), "CTRL+SHIFT+WIN+A");
+    }
+
+    #[test]
+    fn sweep_scales_start_with_the_likeliest() {
+        let sweep = ScaleSweep::default();
+        assert_eq!(sweep.scales(None), [1.0, 1.25, 0.75, 1.5, 1.75, 0.5, 2.0]);
+        assert_eq!(sweep.scales(Some(1.5)), [1.5, 1.0, 1.25, 0.75, 1.75, 0.5, 2.0]);
+        // A scale the sweep does not cover is ignored.
+        assert_eq!(sweep.scales(Some(3.0)), sweep.scales(None));
+
+        // Sweeps stay within 0.1x to 4x, stepping from the minimum.
+        let clamped = ScaleSweep {
+            min: 0.0,
+            max: 9.0,
+            step: 1.0,
+        };
+        assert_eq!(clamped.scales(None), [1.0, 1.1, 2.1, 3.1, 0.1]);
+    }
+
+    fn remap(mode: RemapMode) -> ScreenRemap {
+        ScreenRemap {
+            from: layout((0, 0), (1000, 800)),
+            to: layout((-500, 100), (500, 400)),
+            mode,
+            offset: (7, -3),
+        }
+    }
+
+    /// A primary 1920x1080 monitor with a 1280x1024 one to its left.
+    fn two_monitors() -> [ScreenLayout; 2] {
+        [layout((0, 0), (1920, 1080)), layout((-1280, 0), (1280, 1024))]
+    }
+
+    #[test]
+    fn positions_map_to_monitor_numbers() {
+        let monitors = two_monitors();
+        assert_eq!(monitor_at(&monitors, (0, 0)), Some(1));
+        assert_eq!(monitor_at(&monitors, (-1, 500)), Some(2));
+        // Below the shorter left monitor
+        assert_eq!(monitor_at(&monitors, (-1, 1050)), None);
+        assert_eq!(monitor_at(&monitors, (1920, 0)), None);
+    }
+
+    #[test]
+    fn monitor_targets_pick_search_areas() {
+        let monitors = two_monitors();
+        assert_eq!(MonitorTarget::All.area(Some(2), &monitors), Ok(None));
+        assert_eq!(MonitorTarget::Recorded.area(None, &monitors), Ok(None));
+        assert_eq!(MonitorTarget::Recorded.area(Some(2), &monitors), Ok(Some(monitors[1])));
+        assert_eq!(MonitorTarget::Monitor(1).area(Some(2), &monitors), Ok(Some(monitors[0])));
+        assert_eq!(
+            MonitorTarget::Monitor(3).area(None, &monitors),
+            Err("Monitor 3 is not connected (2 found)".to_string())
+        );
+        assert!(MonitorTarget::Monitor(0).area(None, &monitors).is_err());
+        assert_eq!(
+            MonitorTarget::options(2),
+            [MonitorTarget::All, MonitorTarget::Recorded, MonitorTarget::Monitor(1), MonitorTarget::Monitor(2)]
+        );
+    }
+
+    #[test]
+    fn remaps_move_positions_onto_the_new_screen() {
+        let pos = (900, 100);
+        assert_eq!(remap(RemapMode::Off).apply(pos), (900, 100));
+        assert_eq!(remap(RemapMode::Scale).apply(pos), (-50, 150));
+        assert_eq!(remap(RemapMode::AnchorTopLeft).apply(pos), (400, 200));
+        // 100 px from the right edge, 100 px from the top
+        assert_eq!(remap(RemapMode::AnchorTopRight).apply(pos), (-100, 200));
+        // 700 px above the bottom edge
+        assert_eq!(remap(RemapMode::AnchorBottomLeft).apply(pos), (400, -200));
+        assert_eq!(remap(RemapMode::AnchorBottomRight).apply(pos), (-100, -200));
+        assert_eq!(remap(RemapMode::Offset).apply(pos), (907, 97));
+    }
+
+    #[test]
+    fn repeat_starts_jump_to_their_end() {
+        let events = [
+            row(RecordedEventKind::RepeatStart { count: 2 }),
+            text("a"),
+            row(RecordedEventKind::RepeatStart { count: 3 }),
+            text("b"),
+            row(RecordedEventKind::RepeatEnd),
+            row(RecordedEventKind::RepeatEnd),
+        ];
+        assert_eq!(
+            block_jumps(&events),
+            Ok(vec![Some(5), None, Some(4), None, None, None])
+        );
+    }
+
+    #[test]
+    fn if_blocks_jump_past_the_branch_not_taken() {
+        let condition = || {
+            row(RecordedEventKind::IfImage {
+                patch_png_base64: String::new(),
+                patch_size: 8,
+                precision: 0.9,
+                timeout_ms: 0,
+                search_anchor: SearchAnchor::RecordedClick,
+                search_region_size: None,
+            })
+        };
+        let events = [
+            condition(),
+            text("a"),
+            row(RecordedEventKind::Else),
+            condition(),
+            row(RecordedEventKind::EndIf),
+            row(RecordedEventKind::EndIf),
+        ];
+        // Outer IfImage to its Else, Else to its EndIf, inner IfImage to its EndIf
+        assert_eq!(
+            block_jumps(&events),
+            Ok(vec![Some(2), None, Some(5), Some(4), None, None])
+        );
+
+        let stray_else = [condition(), row(RecordedEventKind::Else), row(RecordedEventKind::Else)];
+        assert_eq!(block_jumps(&stray_else), Err("row 2: Else has no matching IfImage".to_string()));
+    }
+
+    #[test]
+    fn labels_map_to_their_rows() {
+        let events = [
+            label("start"),
+            wait_for_image(String::new(), false, 0, OnTimeout::GotoLabel("end".to_string())),
+            label("end"),
+        ];
+        let rows = label_rows(&events).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows["start"], 0);
+        assert_eq!(rows["end"], 2);
+    }
+
+    #[test]
+    fn bad_labels_are_rejected() {
+        assert_eq!(
+            label_rows(&[label("a"), label("a")]),
+            Err("row 1: label \"a\" is already used on row 0".to_string())
+        );
+        assert_eq!(label_rows(&[label(" ")]), Err("row 0: Label has no name".to_string()));
+        assert_eq!(
+            label_rows(&[wait_for_image(String::new(), false, 0, OnTimeout::GotoLabel("nowhere".to_string()))]),
+            Err("row 0: no label named \"nowhere\"".to_string())
+        );
+
+        let mut failing = text("a");
+        failing.on_error = Some(OnError::GotoLabel("nowhere".to_string()));
+        assert_eq!(label_rows(&[failing]), Err("row 0: no label named \"nowhere\"".to_string()));
+    }
+
+    #[test]
+    fn window_blocks_scope_their_rows() {
+        let events = [
+            text("outside"),
+            bind_window("Editor"),
+            text("a"),
+            bind_window("Editor"),
+            text("b"),
+            row(RecordedEventKind::EndWindow),
+            text("c"),
+            row(RecordedEventKind::EndWindow),
+        ];
+        assert_eq!(
+            block_jumps(&events),
+            Ok(vec![None, Some(7), None, Some(5), None, None, None, None])
+        );
+        // Markers belong to the block around them, not their own.
+        assert_eq!(
+            window_scopes(&events),
+            [None, None, Some(1), Some(1), Some(3), Some(3), Some(1), Some(1)]
+        );
+
+        let crossed = [
+            bind_window("Editor"),
+            row(RecordedEventKind::RepeatStart { count: 1 }),
+            row(RecordedEventKind::EndWindow),
+        ];
+        assert_eq!(
+            block_jumps(&crossed),
+            Err("row 2: EndWindow has no matching BindWindow".to_string())
+        );
+    }
+
+    #[test]
+    fn unbalanced_repeats_are_rejected() {
+        let unclosed = [row(RecordedEventKind::RepeatStart { count: 2 }), text("a")];
+        assert_eq!(block_jumps(&unclosed), Err("row 0: RepeatStart is never closed".to_string()));
+
+        let unopened = [text("a"), row(RecordedEventKind::RepeatEnd)];
+        assert_eq!(
+            block_jumps(&unopened),
+            Err("row 1: RepeatEnd has no matching RepeatStart".to_string())
+        );
+    }
 }
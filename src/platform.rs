@@ -1,7 +1,12 @@
+use crate::model::ScreenLayout;
+use regex::Regex;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex, OnceLock};
 use std::time::Duration;
 
+#[cfg(target_os = "linux")]
+use x11_dl::xlib;
+
 #[cfg(windows)]
 fn win_cursor_pos() -> Option<(i32, i32)> {
     use windows::Win32::Foundation::POINT;
@@ -22,16 +27,266 @@ fn win_key_is_down(vk: i32) -> bool {
     unsafe { (GetAsyncKeyState(vk) as i16) < 0 }
 }
 
+/// Bounding box of every monitor; its origin is negative when a monitor sits
+/// left of or above the primary one.
 #[cfg(windows)]
-fn win_screen_size() -> Option<(i32, i32)> {
-    use windows::Win32::UI::WindowsAndMessaging::{GetSystemMetrics, SM_CXSCREEN, SM_CYSCREEN};
+fn win_virtual_screen() -> Option<ScreenLayout> {
+    use windows::Win32::UI::WindowsAndMessaging::{
+        GetSystemMetrics, SM_CXVIRTUALSCREEN, SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN, SM_YVIRTUALSCREEN,
+    };
 
-    let screen_w = unsafe { GetSystemMetrics(SM_CXSCREEN) };
-    let screen_h = unsafe { GetSystemMetrics(SM_CYSCREEN) };
+    let (left, top) = unsafe { (GetSystemMetrics(SM_XVIRTUALSCREEN), GetSystemMetrics(SM_YVIRTUALSCREEN)) };
+    let screen_w = unsafe { GetSystemMetrics(SM_CXVIRTUALSCREEN) };
+    let screen_h = unsafe { GetSystemMetrics(SM_CYVIRTUALSCREEN) };
     if screen_w <= 0 || screen_h <= 0 {
         return None;
     }
-    Some((screen_w, screen_h))
+    Some(ScreenLayout {
+        origin: (left, top),
+        size: (screen_w, screen_h),
+    })
+}
+
+/// Monitor rectangles with a primary flag, in enumeration order.
+#[cfg(windows)]
+fn win_monitors() -> Vec<(ScreenLayout, bool)> {
+    use windows::core::BOOL;
+    use windows::Win32::Foundation::{LPARAM, RECT};
+    use windows::Win32::Graphics::Gdi::{EnumDisplayMonitors, GetMonitorInfoW, HDC, HMONITOR, MONITORINFO};
+    use windows::Win32::UI::WindowsAndMessaging::MONITORINFOF_PRIMARY;
+
+    unsafe extern "system" fn collect(monitor: HMONITOR, _hdc: HDC, _rect: *mut RECT, lparam: LPARAM) -> BOOL {
+        let monitors = &mut *(lparam.0 as *mut Vec<(ScreenLayout, bool)>);
+        let mut info = MONITORINFO {
+            cbSize: std::mem::size_of::<MONITORINFO>() as u32,
+            ..Default::default()
+        };
+        if GetMonitorInfoW(monitor, &mut info).as_bool() {
+            let rect = info.rcMonitor;
+            monitors.push((
+                ScreenLayout {
+                    origin: (rect.left, rect.top),
+                    size: (rect.right - rect.left, rect.bottom - rect.top),
+                },
+                info.dwFlags & MONITORINFOF_PRIMARY != 0,
+            ));
+        }
+        true.into()
+    }
+
+    let mut monitors: Vec<(ScreenLayout, bool)> = Vec::new();
+    unsafe {
+        let _ = EnumDisplayMonitors(
+            None,
+            None,
+            Some(collect),
+            LPARAM(&mut monitors as *mut Vec<(ScreenLayout, bool)> as isize),
+        );
+    }
+    monitors
+}
+
+#[cfg(target_os = "linux")]
+struct X11Conn {
+    xlib: xlib::Xlib,
+    display: *mut xlib::Display,
+    root: xlib::Window,
+    /// Missing when libXrandr isn't installed; monitors then fall back to the root.
+    xrandr: Option<x11_dl::xrandr::Xrandr>,
+}
+
+// The display is only touched while holding the connection mutex.
+#[cfg(target_os = "linux")]
+unsafe impl Send for X11Conn {}
+
+#[cfg(target_os = "linux")]
+static X11_CONN: OnceLock<Option<Mutex<X11Conn>>> = OnceLock::new();
+
+#[cfg(target_os = "linux")]
+fn x11_open() -> Option<X11Conn> {
+    let xlib = xlib::Xlib::open().ok()?;
+    unsafe {
+        let display = (xlib.XOpenDisplay)(std::ptr::null());
+        if display.is_null() {
+            return None;
+        }
+        let root = (xlib.XDefaultRootWindow)(display);
+        Some(X11Conn {
+            xlib,
+            display,
+            root,
+            xrandr: x11_dl::xrandr::Xrandr::open().ok(),
+        })
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn x11_with<R>(f: impl FnOnce(&X11Conn) -> Option<R>) -> Option<R> {
+    let conn = X11_CONN.get_or_init(|| x11_open().map(Mutex::new)).as_ref()?;
+    let conn = conn.lock().ok()?;
+    f(&conn)
+}
+
+#[cfg(target_os = "linux")]
+fn x11_query_pointer() -> Option<(i32, i32, u32)> {
+    x11_with(|conn| unsafe {
+        let mut root_ret: xlib::Window = 0;
+        let mut child_ret: xlib::Window = 0;
+        let (mut root_x, mut root_y, mut win_x, mut win_y) = (0, 0, 0, 0);
+        let mut mask = 0u32;
+        let ok = (conn.xlib.XQueryPointer)(
+            conn.display,
+            conn.root,
+            &mut root_ret,
+            &mut child_ret,
+            &mut root_x,
+            &mut root_y,
+            &mut win_x,
+            &mut win_y,
+            &mut mask,
+        );
+        (ok != 0).then_some((root_x, root_y, mask))
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn x11_cursor_pos() -> Option<(i32, i32)> {
+    x11_query_pointer().map(|(x, y, _)| (x, y))
+}
+
+#[cfg(target_os = "linux")]
+fn x11_key_is_down(vk: i32) -> bool {
+    let button_mask = match vk {
+        VK_LBUTTON => Some(xlib::Button1Mask),
+        VK_MBUTTON => Some(xlib::Button2Mask),
+        VK_RBUTTON => Some(xlib::Button3Mask),
+        _ => None,
+    };
+    if let Some(button_mask) = button_mask {
+        return x11_query_pointer()
+            .map(|(_, _, mask)| mask & button_mask != 0)
+            .unwrap_or(false);
+    }
+
+    let keysyms = x11_keysyms_for_vk(vk);
+    if keysyms.is_empty() {
+        return false;
+    }
+
+    x11_with(|conn| {
+        let keys = x11_query_keymap(conn);
+        Some(keysyms.iter().any(|&keysym| x11_keysym_is_down(conn, &keys, keysym)))
+    })
+    .unwrap_or(false)
+}
+
+#[cfg(target_os = "linux")]
+fn x11_keysyms_for_vk(vk: i32) -> Vec<u32> {
+    use x11_dl::keysym::*;
+
+    match vk {
+        // Letters map to their lowercase keysyms, digits and space are identical.
+        0x41..=0x5A => vec![vk as u32 + 0x20],
+        0x30..=0x39 | 0x20 => vec![vk as u32],
+        VK_ESCAPE => vec![XK_Escape],
+        0x08 => vec![XK_BackSpace],
+        0x09 => vec![XK_Tab],
+        0x0D => vec![XK_Return, XK_KP_Enter],
+        0x10 => vec![XK_Shift_L, XK_Shift_R],
+        0x11 => vec![XK_Control_L, XK_Control_R],
+        0x12 => vec![XK_Alt_L, XK_Alt_R],
+        0x21 => vec![XK_Page_Up],
+        0x22 => vec![XK_Page_Down],
+        0x23 => vec![XK_End],
+        0x24 => vec![XK_Home],
+        0x25 => vec![XK_Left],
+        0x26 => vec![XK_Up],
+        0x27 => vec![XK_Right],
+        0x28 => vec![XK_Down],
+        0x2D => vec![XK_Insert],
+        0x2E => vec![XK_Delete],
+        0x5B => vec![XK_Super_L, XK_Super_R],
+        0x70..=0x7B => vec![XK_F1 + (vk - 0x70) as u32],
+        0xBA => vec![XK_semicolon],
+        0xBB => vec![XK_equal],
+        0xBC => vec![XK_comma],
+        0xBD => vec![XK_minus],
+        0xBE => vec![XK_period],
+        0xBF => vec![XK_slash],
+        0xC0 => vec![XK_grave],
+        0xDB => vec![XK_bracketleft],
+        0xDC => vec![XK_backslash],
+        0xDD => vec![XK_bracketright],
+        0xDE => vec![XK_apostrophe],
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn x11_query_keymap(conn: &X11Conn) -> [std::os::raw::c_char; 32] {
+    let mut keys = [0 as std::os::raw::c_char; 32];
+    unsafe { (conn.xlib.XQueryKeymap)(conn.display, keys.as_mut_ptr()) };
+    keys
+}
+
+#[cfg(target_os = "linux")]
+fn x11_keysym_is_down(conn: &X11Conn, keys: &[std::os::raw::c_char; 32], keysym: u32) -> bool {
+    let keycode = unsafe { (conn.xlib.XKeysymToKeycode)(conn.display, keysym as std::os::raw::c_ulong) };
+    if keycode == 0 {
+        return false;
+    }
+    let byte = keys[(keycode / 8) as usize] as u8;
+    byte & (1 << (keycode % 8)) != 0
+}
+
+/// The `RECORDABLE_KEYS` virtual-key code typed by `keycode`, if any.
+#[cfg(target_os = "linux")]
+fn x11_vk_for_keycode(conn: &X11Conn, keycode: i32) -> Option<i32> {
+    RECORDABLE_KEYS.iter().map(|(_, vk)| *vk).find(|&vk| {
+        x11_keysyms_for_vk(vk).into_iter().any(|keysym| {
+            let code = unsafe { (conn.xlib.XKeysymToKeycode)(conn.display, keysym as std::os::raw::c_ulong) };
+            code != 0 && i32::from(code) == keycode
+        })
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn x11_screen_size() -> Option<(i32, i32)> {
+    x11_with(|conn| unsafe {
+        let screen = (conn.xlib.XDefaultScreen)(conn.display);
+        let screen_w = (conn.xlib.XDisplayWidth)(conn.display, screen);
+        let screen_h = (conn.xlib.XDisplayHeight)(conn.display, screen);
+        if screen_w <= 0 || screen_h <= 0 {
+            return None;
+        }
+        Some((screen_w, screen_h))
+    })
+}
+
+/// XRandR monitors with a primary flag; empty without the extension.
+#[cfg(target_os = "linux")]
+fn x11_monitors() -> Vec<(ScreenLayout, bool)> {
+    x11_with(|conn| unsafe {
+        let xrandr = conn.xrandr.as_ref()?;
+        let mut count = 0;
+        let infos = (xrandr.XRRGetMonitors)(conn.display, conn.root, xlib::True, &mut count);
+        if infos.is_null() {
+            return None;
+        }
+        let monitors = std::slice::from_raw_parts(infos, count.max(0) as usize)
+            .iter()
+            .map(|info| {
+                let layout = ScreenLayout {
+                    origin: (info.x, info.y),
+                    size: (info.width, info.height),
+                };
+                (layout, info.primary != 0)
+            })
+            .collect();
+        (xrandr.XRRFreeMonitors)(infos);
+        Some(monitors)
+    })
+    .unwrap_or_default()
 }
 
 pub fn get_mouse_pos() -> Option<(i32, i32)> {
@@ -40,12 +295,327 @@ pub fn get_mouse_pos() -> Option<(i32, i32)> {
         win_cursor_pos()
     }
 
-    #[cfg(not(windows))]
+    #[cfg(target_os = "linux")]
+    {
+        x11_cursor_pos()
+    }
+
+    #[cfg(not(any(windows, target_os = "linux")))]
+    {
+        None
+    }
+}
+
+/// The desktop positions are recorded and played on: the bounding box of all
+/// monitors on Windows, the X11 root window on Linux.
+pub fn screen_layout() -> Option<ScreenLayout> {
+    #[cfg(windows)]
+    {
+        win_virtual_screen()
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        x11_screen_size().map(|size| ScreenLayout { origin: (0, 0), size })
+    }
+
+    #[cfg(not(any(windows, target_os = "linux")))]
     {
         None
     }
 }
 
+/// Connected monitors in desktop coordinates, the primary one first and the
+/// rest left to right. Rows and `MonitorTarget::Monitor` number them from 1 in
+/// this order. Falls back to the whole desktop when they can't be listed.
+pub fn monitors() -> Vec<ScreenLayout> {
+    #[cfg(windows)]
+    let mut monitors = win_monitors();
+
+    #[cfg(target_os = "linux")]
+    let mut monitors = x11_monitors();
+
+    #[cfg(not(any(windows, target_os = "linux")))]
+    let mut monitors: Vec<(ScreenLayout, bool)> = Vec::new();
+
+    if monitors.is_empty() {
+        return screen_layout().into_iter().collect();
+    }
+    monitors.sort_by_key(|&(layout, primary)| (!primary, layout.origin.0, layout.origin.1));
+    monitors.into_iter().map(|(layout, _)| layout).collect()
+}
+
+/// A top-level window, with its client area in desktop coordinates.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WindowInfo {
+    /// HWND on Windows, X11 window id on Linux.
+    pub id: u64,
+    pub title: String,
+    /// Window class on Windows, `WM_CLASS` class name on X11.
+    pub class: String,
+    pub origin: (i32, i32),
+    pub size: (i32, i32),
+}
+
+/// Picks windows by title and class regex; an empty pattern matches any.
+pub struct WindowMatcher {
+    title: Regex,
+    class: Regex,
+}
+
+impl WindowMatcher {
+    pub fn new(title: &str, class: &str) -> Result<Self, String> {
+        let compile = |pattern: &str, what: &str| {
+            Regex::new(pattern).map_err(|e| format!("Invalid window {what} pattern {pattern:?}: {e}"))
+        };
+        Ok(Self {
+            title: compile(title, "title")?,
+            class: compile(class, "class")?,
+        })
+    }
+
+    pub fn matches(&self, window: &WindowInfo) -> bool {
+        self.title.is_match(&window.title) && self.class.is_match(&window.class)
+    }
+}
+
+/// Visible top-level windows, topmost first.
+pub fn list_windows() -> Vec<WindowInfo> {
+    #[cfg(windows)]
+    {
+        win_list_windows()
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        x11_list_windows()
+    }
+
+    #[cfg(not(any(windows, target_os = "linux")))]
+    {
+        Vec::new()
+    }
+}
+
+/// The topmost window matching both regexes.
+pub fn find_window(title: &str, class: &str) -> Result<Option<WindowInfo>, String> {
+    let matcher = WindowMatcher::new(title, class)?;
+    Ok(list_windows().into_iter().find(|window| matcher.matches(window)))
+}
+
+/// Restores and raises the window and asks for input focus. The window
+/// manager may refuse focus, so this is best effort.
+pub fn activate_window(id: u64) {
+    #[cfg(windows)]
+    win_activate_window(id);
+
+    #[cfg(target_os = "linux")]
+    x11_activate_window(id);
+
+    #[cfg(not(any(windows, target_os = "linux")))]
+    let _ = id;
+}
+
+#[cfg(windows)]
+fn win_list_windows() -> Vec<WindowInfo> {
+    use windows::core::BOOL;
+    use windows::Win32::Foundation::{HWND, LPARAM, POINT, RECT};
+    use windows::Win32::Graphics::Gdi::ClientToScreen;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        EnumWindows, GetClassNameW, GetClientRect, GetWindowTextW, IsWindowVisible,
+    };
+
+    unsafe extern "system" fn collect(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let handles = &mut *(lparam.0 as *mut Vec<HWND>);
+        if IsWindowVisible(hwnd).as_bool() {
+            handles.push(hwnd);
+        }
+        true.into()
+    }
+
+    let mut handles: Vec<HWND> = Vec::new();
+    // EnumWindows walks top-level windows in Z order, topmost first.
+    unsafe {
+        let _ = EnumWindows(Some(collect), LPARAM(&mut handles as *mut Vec<HWND> as isize));
+    }
+
+    handles
+        .into_iter()
+        .filter_map(|hwnd| unsafe {
+            let mut title = [0u16; 512];
+            let title_len = GetWindowTextW(hwnd, &mut title).max(0) as usize;
+            let mut class = [0u16; 256];
+            let class_len = GetClassNameW(hwnd, &mut class).max(0) as usize;
+
+            let mut rect = RECT::default();
+            GetClientRect(hwnd, &mut rect).ok()?;
+            let mut origin = POINT { x: 0, y: 0 };
+            if !ClientToScreen(hwnd, &mut origin).as_bool() {
+                return None;
+            }
+
+            Some(WindowInfo {
+                id: hwnd.0 as usize as u64,
+                title: String::from_utf16_lossy(&title[..title_len]),
+                class: String::from_utf16_lossy(&class[..class_len]),
+                origin: (origin.x, origin.y),
+                size: (rect.right - rect.left, rect.bottom - rect.top),
+            })
+        })
+        .collect()
+}
+
+#[cfg(windows)]
+fn win_activate_window(id: u64) {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        BringWindowToTop, IsIconic, SetForegroundWindow, ShowWindow, SW_RESTORE,
+    };
+
+    let hwnd = HWND(id as usize as *mut std::ffi::c_void);
+    unsafe {
+        if IsIconic(hwnd).as_bool() {
+            let _ = ShowWindow(hwnd, SW_RESTORE);
+        }
+        // Windows only lets the foreground process take focus; raising still works.
+        if !SetForegroundWindow(hwnd).as_bool() {
+            let _ = BringWindowToTop(hwnd);
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn x11_atom(conn: &X11Conn, name: &std::ffi::CStr) -> xlib::Atom {
+    unsafe { (conn.xlib.XInternAtom)(conn.display, name.as_ptr(), xlib::False) }
+}
+
+/// Raw items of a window property, or `None` when it is missing or not of `kind`.
+#[cfg(target_os = "linux")]
+fn x11_property<T: Copy>(conn: &X11Conn, window: xlib::Window, property: xlib::Atom, kind: xlib::Atom) -> Option<Vec<T>> {
+    unsafe {
+        let mut actual_kind: xlib::Atom = 0;
+        let mut format = 0;
+        let (mut items, mut bytes_after) = (0, 0);
+        let mut data: *mut std::os::raw::c_uchar = std::ptr::null_mut();
+        let status = (conn.xlib.XGetWindowProperty)(
+            conn.display,
+            window,
+            property,
+            0,
+            i32::MAX as std::os::raw::c_long,
+            xlib::False,
+            kind,
+            &mut actual_kind,
+            &mut format,
+            &mut items,
+            &mut bytes_after,
+            &mut data,
+        );
+        if status != xlib::Success as i32 || data.is_null() {
+            return None;
+        }
+        let values = (actual_kind == kind)
+            .then(|| std::slice::from_raw_parts(data as *const T, items as usize).to_vec());
+        (conn.xlib.XFree)(data as *mut _);
+        values
+    }
+}
+
+/// Client windows from `_NET_CLIENT_LIST_STACKING` (bottom to top, so reversed)
+/// or, when the window manager lacks it, `_NET_CLIENT_LIST`.
+#[cfg(target_os = "linux")]
+fn x11_list_windows() -> Vec<WindowInfo> {
+    x11_with(|conn| {
+        let stacking = x11_atom(conn, c"_NET_CLIENT_LIST_STACKING");
+        let clients: Vec<xlib::Window> = match x11_property(conn, conn.root, stacking, xlib::XA_WINDOW) {
+            Some(stack) => stack.into_iter().rev().collect(),
+            None => x11_property(conn, conn.root, x11_atom(conn, c"_NET_CLIENT_LIST"), xlib::XA_WINDOW)?,
+        };
+
+        let net_wm_name = x11_atom(conn, c"_NET_WM_NAME");
+        let utf8_string = x11_atom(conn, c"UTF8_STRING");
+        Some(
+            clients
+                .into_iter()
+                .filter_map(|window| unsafe {
+                    let mut attributes: xlib::XWindowAttributes = std::mem::zeroed();
+                    if (conn.xlib.XGetWindowAttributes)(conn.display, window, &mut attributes) == 0 {
+                        return None;
+                    }
+                    let (mut x, mut y) = (0, 0);
+                    let mut child: xlib::Window = 0;
+                    (conn.xlib.XTranslateCoordinates)(conn.display, window, conn.root, 0, 0, &mut x, &mut y, &mut child);
+
+                    let title = x11_property::<u8>(conn, window, net_wm_name, utf8_string)
+                        .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+                        .or_else(|| x11_property::<u8>(conn, window, xlib::XA_WM_NAME, xlib::XA_STRING)
+                            .map(|bytes| bytes.iter().map(|&b| b as char).collect()))
+                        .unwrap_or_default();
+
+                    let mut hint = xlib::XClassHint {
+                        res_name: std::ptr::null_mut(),
+                        res_class: std::ptr::null_mut(),
+                    };
+                    let mut class = String::new();
+                    if (conn.xlib.XGetClassHint)(conn.display, window, &mut hint) != 0 {
+                        if !hint.res_class.is_null() {
+                            class = std::ffi::CStr::from_ptr(hint.res_class).to_string_lossy().into_owned();
+                            (conn.xlib.XFree)(hint.res_class as *mut _);
+                        }
+                        if !hint.res_name.is_null() {
+                            (conn.xlib.XFree)(hint.res_name as *mut _);
+                        }
+                    }
+
+                    Some(WindowInfo {
+                        id: window,
+                        title,
+                        class,
+                        origin: (x, y),
+                        size: (attributes.width, attributes.height),
+                    })
+                })
+                .collect(),
+        )
+    })
+    .unwrap_or_default()
+}
+
+/// Asks the window manager to activate the window (`_NET_ACTIVE_WINDOW`),
+/// which also maps and raises it.
+#[cfg(target_os = "linux")]
+fn x11_activate_window(id: u64) {
+    x11_with(|conn| unsafe {
+        let window = id as xlib::Window;
+        let mut data = xlib::ClientMessageData::new();
+        // Source indication 2: a pager or similar tool acting for the user.
+        data.set_long(0, 2);
+        data.set_long(1, xlib::CurrentTime as std::os::raw::c_long);
+        let mut event = xlib::XEvent {
+            client_message: xlib::XClientMessageEvent {
+                type_: xlib::ClientMessage,
+                serial: 0,
+                send_event: xlib::True,
+                display: conn.display,
+                window,
+                message_type: x11_atom(conn, c"_NET_ACTIVE_WINDOW"),
+                format: 32,
+                data,
+            },
+        };
+        (conn.xlib.XSendEvent)(
+            conn.display,
+            conn.root,
+            xlib::False,
+            xlib::SubstructureRedirectMask | xlib::SubstructureNotifyMask,
+            &mut event,
+        );
+        (conn.xlib.XMapRaised)(conn.display, window);
+        (conn.xlib.XFlush)(conn.display);
+        Some(())
+    });
+}
+
 #[cfg(windows)]
 pub fn jump_mouse_to(x: i32, y: i32) -> Result<(), String> {
     use windows::Win32::UI::WindowsAndMessaging::SetCursorPos;
@@ -59,69 +629,262 @@ pub fn jump_mouse_to(x: i32, y: i32) -> Result<(), String> {
     }
 }
 
-#[cfg(not(windows))]
+#[cfg(target_os = "linux")]
+pub fn jump_mouse_to(x: i32, y: i32) -> Result<(), String> {
+    x11_with(|conn| unsafe {
+        (conn.xlib.XWarpPointer)(conn.display, 0, conn.root, 0, 0, 0, 0, x, y);
+        (conn.xlib.XFlush)(conn.display);
+        Some(())
+    })
+    .ok_or_else(|| "XWarpPointer failed: no X11 display".to_string())
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
 pub fn jump_mouse_to(_x: i32, _y: i32) -> Result<(), String> {
-    Err("Jump is currently Windows-only".to_string())
+    Err("Jump is not supported on this platform".to_string())
 }
 
-#[cfg(windows)]
+// Windows virtual-key codes; the X11 backend maps them onto pointer masks and keysyms.
 pub const VK_LBUTTON: i32 = 0x01;
-#[cfg(windows)]
 pub const VK_RBUTTON: i32 = 0x02;
-#[cfg(windows)]
 pub const VK_MBUTTON: i32 = 0x04;
-#[cfg(windows)]
 pub const VK_ESCAPE: i32 = 0x1B;
 
-#[cfg(windows)]
+/// Keys the recorder captures, as (rustautogui key name, virtual-key code). ESC is
+/// left out because it stops recording. Punctuation keys are named after the
+/// character they type on a US layout.
+pub const RECORDABLE_KEYS: &[(&str, i32)] = &[
+    ("backspace", 0x08),
+    ("tab", 0x09),
+    ("return", 0x0D),
+    ("shift", 0x10),
+    ("ctrl", 0x11),
+    ("alt", 0x12),
+    ("space", 0x20),
+    ("pageup", 0x21),
+    ("pagedown", 0x22),
+    ("end", 0x23),
+    ("home", 0x24),
+    ("left", 0x25),
+    ("up", 0x26),
+    ("right", 0x27),
+    ("down", 0x28),
+    ("insert", 0x2D),
+    ("delete", 0x2E),
+    ("0", 0x30),
+    ("1", 0x31),
+    ("2", 0x32),
+    ("3", 0x33),
+    ("4", 0x34),
+    ("5", 0x35),
+    ("6", 0x36),
+    ("7", 0x37),
+    ("8", 0x38),
+    ("9", 0x39),
+    ("a", 0x41),
+    ("b", 0x42),
+    ("c", 0x43),
+    ("d", 0x44),
+    ("e", 0x45),
+    ("f", 0x46),
+    ("g", 0x47),
+    ("h", 0x48),
+    ("i", 0x49),
+    ("j", 0x4A),
+    ("k", 0x4B),
+    ("l", 0x4C),
+    ("m", 0x4D),
+    ("n", 0x4E),
+    ("o", 0x4F),
+    ("p", 0x50),
+    ("q", 0x51),
+    ("r", 0x52),
+    ("s", 0x53),
+    ("t", 0x54),
+    ("u", 0x55),
+    ("v", 0x56),
+    ("w", 0x57),
+    ("x", 0x58),
+    ("y", 0x59),
+    ("z", 0x5A),
+    ("win", 0x5B),
+    ("f1", 0x70),
+    ("f2", 0x71),
+    ("f3", 0x72),
+    ("f4", 0x73),
+    ("f5", 0x74),
+    ("f6", 0x75),
+    ("f7", 0x76),
+    ("f8", 0x77),
+    ("f9", 0x78),
+    ("f10", 0x79),
+    ("f11", 0x7A),
+    ("f12", 0x7B),
+    (";", 0xBA),
+    ("=", 0xBB),
+    (",", 0xBC),
+    ("-", 0xBD),
+    (".", 0xBE),
+    ("/", 0xBF),
+    ("`", 0xC0),
+    ("[", 0xDB),
+    ("\\", 0xDC),
+    ("]", 0xDD),
+    ("'", 0xDE),
+];
+
 #[derive(Default)]
 struct GetCaptureHookState {
     armed: bool,
     captured: Option<(i32, i32, &'static str)>,
+    #[cfg(windows)]
     swallow_up_message: Option<u32>,
 }
 
-#[cfg(windows)]
 static GET_CAPTURE_HOOK_STATE: OnceLock<Arc<Mutex<GetCaptureHookState>>> = OnceLock::new();
-#[cfg(windows)]
 static GET_CAPTURE_HOOK_STARTED: AtomicBool = AtomicBool::new(false);
 
-#[cfg(windows)]
 fn get_capture_hook_state() -> &'static Arc<Mutex<GetCaptureHookState>> {
     GET_CAPTURE_HOOK_STATE.get_or_init(|| Arc::new(Mutex::new(GetCaptureHookState::default())))
 }
 
-#[cfg(windows)]
 pub fn arm_get_capture_hook() {
     if let Ok(mut state) = get_capture_hook_state().lock() {
         state.armed = true;
         state.captured = None;
-        state.swallow_up_message = None;
+        #[cfg(windows)]
+        {
+            state.swallow_up_message = None;
+        }
     }
 }
 
-#[cfg(windows)]
 pub fn disarm_get_capture_hook() {
     if let Ok(mut state) = get_capture_hook_state().lock() {
         state.armed = false;
         state.captured = None;
-        state.swallow_up_message = None;
+        #[cfg(windows)]
+        {
+            state.swallow_up_message = None;
+        }
     }
 }
 
-#[cfg(windows)]
 pub fn take_get_capture_hook_result() -> Option<(i32, i32, &'static str)> {
     let mut state = get_capture_hook_state().lock().ok()?;
     state.captured.take()
 }
 
+// Wheel movement seen by the hook thread, in 1/120 notch units (WHEEL_DELTA).
+// Positive x scrolls right, positive y scrolls down.
+const WHEEL_DELTA: i32 = 120;
+
+#[derive(Default)]
+struct ScrollCaptureState {
+    enabled: bool,
+    units: (i32, i32),
+}
+
+static SCROLL_CAPTURE_STATE: Mutex<ScrollCaptureState> = Mutex::new(ScrollCaptureState {
+    enabled: false,
+    units: (0, 0),
+});
+
+fn add_scroll_units(dx: i32, dy: i32) {
+    if let Ok(mut state) = SCROLL_CAPTURE_STATE.lock() {
+        if state.enabled {
+            state.units.0 = state.units.0.saturating_add(dx);
+            state.units.1 = state.units.1.saturating_add(dy);
+        }
+    }
+}
+
+/// Starts accumulating wheel input from the capture hook thread.
+pub fn start_scroll_capture() -> Result<(), String> {
+    ensure_get_capture_hook_thread()?;
+    if let Ok(mut state) = SCROLL_CAPTURE_STATE.lock() {
+        *state = ScrollCaptureState {
+            enabled: true,
+            ..Default::default()
+        };
+    }
+    Ok(())
+}
+
+pub fn stop_scroll_capture() {
+    if let Ok(mut state) = SCROLL_CAPTURE_STATE.lock() {
+        *state = ScrollCaptureState::default();
+    }
+}
+
+/// Whole wheel notches since the last call; partial notches carry over.
+pub fn take_scroll_notches() -> (i32, i32) {
+    let Ok(mut state) = SCROLL_CAPTURE_STATE.lock() else {
+        return (0, 0);
+    };
+    let notches = (state.units.0 / WHEEL_DELTA, state.units.1 / WHEEL_DELTA);
+    state.units.0 -= notches.0 * WHEEL_DELTA;
+    state.units.1 -= notches.1 * WHEEL_DELTA;
+    notches
+}
+
+// Key presses and releases seen by the hook thread, oldest first, named as in
+// `RECORDABLE_KEYS`. Held keys repeat their press.
+#[derive(Default)]
+struct KeyCaptureState {
+    enabled: bool,
+    transitions: Vec<(&'static str, bool)>,
+}
+
+static KEY_CAPTURE_STATE: Mutex<KeyCaptureState> = Mutex::new(KeyCaptureState {
+    enabled: false,
+    transitions: Vec::new(),
+});
+
+fn add_key_transition(vk: i32, is_down: bool) {
+    let Some(&(name, _)) = RECORDABLE_KEYS.iter().find(|(_, key_vk)| *key_vk == vk) else {
+        return;
+    };
+    if let Ok(mut state) = KEY_CAPTURE_STATE.lock() {
+        if state.enabled {
+            state.transitions.push((name, is_down));
+        }
+    }
+}
+
+/// Starts queueing key presses and releases from the capture hook thread.
+pub fn start_key_capture() -> Result<(), String> {
+    ensure_get_capture_hook_thread()?;
+    if let Ok(mut state) = KEY_CAPTURE_STATE.lock() {
+        *state = KeyCaptureState {
+            enabled: true,
+            ..Default::default()
+        };
+    }
+    Ok(())
+}
+
+pub fn stop_key_capture() {
+    if let Ok(mut state) = KEY_CAPTURE_STATE.lock() {
+        *state = KeyCaptureState::default();
+    }
+}
+
+/// Key presses (`true`) and releases (`false`) since the last call, in order.
+pub fn take_key_transitions() -> Vec<(&'static str, bool)> {
+    KEY_CAPTURE_STATE
+        .lock()
+        .map(|mut state| std::mem::take(&mut state.transitions))
+        .unwrap_or_default()
+}
+
 #[cfg(windows)]
 pub fn ensure_get_capture_hook_thread() -> Result<(), String> {
     use std::sync::mpsc;
     use std::thread;
     use windows::Win32::UI::WindowsAndMessaging::{
         DispatchMessageW, GetMessageW, SetWindowsHookExW, TranslateMessage, UnhookWindowsHookEx,
-        WH_MOUSE_LL, MSG,
+        WH_KEYBOARD_LL, WH_MOUSE_LL, MSG,
     };
 
     if GET_CAPTURE_HOOK_STARTED.load(Ordering::Relaxed) {
@@ -138,6 +901,14 @@ pub fn ensure_get_capture_hook_thread() -> Result<(), String> {
                 return;
             }
         };
+        let key_hook = match SetWindowsHookExW(WH_KEYBOARD_LL, Some(capture_keyboard_hook_proc), None, 0) {
+            Ok(key_hook) if !key_hook.is_invalid() => key_hook,
+            _ => {
+                let _ = UnhookWindowsHookEx(hook);
+                let _ = tx.send(Err("SetWindowsHookExW(WH_KEYBOARD_LL) failed".to_string()));
+                return;
+            }
+        };
 
         let _ = tx.send(Ok(()));
 
@@ -147,6 +918,7 @@ pub fn ensure_get_capture_hook_thread() -> Result<(), String> {
             let _ = DispatchMessageW(&msg);
         }
 
+        let _ = UnhookWindowsHookEx(key_hook);
         let _ = UnhookWindowsHookEx(hook);
     });
 
@@ -160,6 +932,128 @@ pub fn ensure_get_capture_hook_thread() -> Result<(), String> {
     }
 }
 
+/// Listens for XInput2 raw button and key events on a dedicated display
+/// connection. Unlike the Windows low-level hook, X11 cannot swallow the click,
+/// so the captured press still reaches the window under the cursor. Buttons 4-7
+/// are the wheel and feed the scroll capture; keys feed the key capture.
+#[cfg(target_os = "linux")]
+pub fn ensure_get_capture_hook_thread() -> Result<(), String> {
+    use std::sync::mpsc;
+    use std::thread;
+    use x11_dl::xinput2;
+
+    if GET_CAPTURE_HOOK_STARTED.load(Ordering::Relaxed) {
+        return Ok(());
+    }
+
+    let (tx, rx) = mpsc::channel::<Result<(), String>>();
+
+    thread::spawn(move || unsafe {
+        let Some(conn) = x11_open() else {
+            let _ = tx.send(Err("XOpenDisplay failed".to_string()));
+            return;
+        };
+        let Ok(xi) = xinput2::XInput2::open() else {
+            let _ = tx.send(Err("libXi is not available".to_string()));
+            return;
+        };
+
+        let mut xi_opcode = 0;
+        let (mut first_event, mut first_error) = (0, 0);
+        let has_xi = (conn.xlib.XQueryExtension)(
+            conn.display,
+            c"XInputExtension".as_ptr(),
+            &mut xi_opcode,
+            &mut first_event,
+            &mut first_error,
+        );
+        let (mut major, mut minor) = (2, 0);
+        if has_xi == 0 || (xi.XIQueryVersion)(conn.display, &mut major, &mut minor) != 0 {
+            let _ = tx.send(Err("XInput2 is not available".to_string()));
+            return;
+        }
+
+        let mut mask_bits = [0u8; ((xinput2::XI_LASTEVENT + 7) / 8) as usize];
+        xinput2::XISetMask(&mut mask_bits, xinput2::XI_RawButtonPress);
+        xinput2::XISetMask(&mut mask_bits, xinput2::XI_RawKeyPress);
+        xinput2::XISetMask(&mut mask_bits, xinput2::XI_RawKeyRelease);
+        let mut mask = xinput2::XIEventMask {
+            deviceid: xinput2::XIAllMasterDevices,
+            mask_len: mask_bits.len() as i32,
+            mask: mask_bits.as_mut_ptr(),
+        };
+        (xi.XISelectEvents)(conn.display, conn.root, &mut mask, 1);
+        (conn.xlib.XFlush)(conn.display);
+
+        let _ = tx.send(Ok(()));
+
+        let mut event: xlib::XEvent = std::mem::zeroed();
+        loop {
+            (conn.xlib.XNextEvent)(conn.display, &mut event);
+            if event.get_type() != xlib::GenericEvent {
+                continue;
+            }
+
+            let mut cookie = event.generic_event_cookie;
+            if cookie.extension != xi_opcode || (conn.xlib.XGetEventData)(conn.display, &mut cookie) == 0 {
+                continue;
+            }
+
+            if cookie.evtype == xinput2::XI_RawKeyPress || cookie.evtype == xinput2::XI_RawKeyRelease {
+                let raw = &*(cookie.data as *const xinput2::XIRawEvent);
+                if let Some(vk) = x11_vk_for_keycode(&conn, raw.detail) {
+                    add_key_transition(vk, cookie.evtype == xinput2::XI_RawKeyPress);
+                }
+            }
+
+            if cookie.evtype == xinput2::XI_RawButtonPress {
+                let raw = &*(cookie.data as *const xinput2::XIRawEvent);
+                match raw.detail {
+                    4 => add_scroll_units(0, -WHEEL_DELTA),
+                    5 => add_scroll_units(0, WHEEL_DELTA),
+                    6 => add_scroll_units(-WHEEL_DELTA, 0),
+                    7 => add_scroll_units(WHEEL_DELTA, 0),
+                    _ => {}
+                }
+
+                let button_name = match raw.detail {
+                    1 => Some("Left"),
+                    2 => Some("Middle"),
+                    3 => Some("Right"),
+                    _ => None,
+                };
+
+                if let Some(button_name) = button_name {
+                    if let Ok(mut state) = get_capture_hook_state().lock() {
+                        if state.armed {
+                            if let Some((x, y)) = x11_cursor_pos() {
+                                state.captured = Some((x, y, button_name));
+                                state.armed = false;
+                            }
+                        }
+                    }
+                }
+            }
+
+            (conn.xlib.XFreeEventData)(conn.display, &mut cookie);
+        }
+    });
+
+    match rx.recv_timeout(Duration::from_millis(800)) {
+        Ok(Ok(())) => {
+            GET_CAPTURE_HOOK_STARTED.store(true, Ordering::Relaxed);
+            Ok(())
+        }
+        Ok(Err(err)) => Err(err),
+        Err(_) => Err("Timed out initializing mouse capture hook".to_string()),
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+pub fn ensure_get_capture_hook_thread() -> Result<(), String> {
+    Err("GET capture is not supported on this platform".to_string())
+}
+
 #[cfg(windows)]
 unsafe extern "system" fn get_capture_mouse_hook_proc(
     code: i32,
@@ -170,6 +1064,7 @@ unsafe extern "system" fn get_capture_mouse_hook_proc(
     use windows::Win32::UI::WindowsAndMessaging::{
         CallNextHookEx, HC_ACTION, MSLLHOOKSTRUCT,
         WM_LBUTTONDOWN, WM_LBUTTONUP,
+        WM_MOUSEHWHEEL, WM_MOUSEWHEEL,
         WM_RBUTTONDOWN, WM_RBUTTONUP,
         WM_MBUTTONDOWN, WM_MBUTTONUP,
     };
@@ -177,6 +1072,17 @@ unsafe extern "system" fn get_capture_mouse_hook_proc(
     if code == HC_ACTION as i32 {
         let message = wparam.0 as u32;
 
+        if message == WM_MOUSEWHEEL || message == WM_MOUSEHWHEEL {
+            let data = &*(lparam.0 as *const MSLLHOOKSTRUCT);
+            let delta = (data.mouseData >> 16) as u16 as i16 as i32;
+            // Forward wheel rotation scrolls up, so flip it to our "positive is down".
+            if message == WM_MOUSEWHEEL {
+                add_scroll_units(0, -delta);
+            } else {
+                add_scroll_units(delta, 0);
+            }
+        }
+
         if let Ok(mut state) = get_capture_hook_state().lock() {
             if let Some(up_message) = state.swallow_up_message {
                 if message == up_message {
@@ -208,28 +1114,120 @@ unsafe extern "system" fn get_capture_mouse_hook_proc(
 }
 
 #[cfg(windows)]
-pub fn is_vk_down_windows(vk: i32) -> bool {
-    win_key_is_down(vk)
+unsafe extern "system" fn capture_keyboard_hook_proc(
+    code: i32,
+    wparam: windows::Win32::Foundation::WPARAM,
+    lparam: windows::Win32::Foundation::LPARAM,
+) -> windows::Win32::Foundation::LRESULT {
+    use windows::Win32::UI::WindowsAndMessaging::{
+        CallNextHookEx, HC_ACTION, KBDLLHOOKSTRUCT, WM_KEYDOWN, WM_KEYUP, WM_SYSKEYDOWN, WM_SYSKEYUP,
+    };
+
+    if code == HC_ACTION as i32 {
+        let is_down = match wparam.0 as u32 {
+            WM_KEYDOWN | WM_SYSKEYDOWN => Some(true),
+            WM_KEYUP | WM_SYSKEYUP => Some(false),
+            _ => None,
+        };
+        if let Some(is_down) = is_down {
+            let data = &*(lparam.0 as *const KBDLLHOOKSTRUCT);
+            // The hook tells left and right modifiers apart; recordings don't.
+            let vk = match data.vkCode {
+                0xA0 | 0xA1 => 0x10,
+                0xA2 | 0xA3 => 0x11,
+                0xA4 | 0xA5 => 0x12,
+                0x5C => 0x5B,
+                vk => vk as i32,
+            };
+            add_key_transition(vk, is_down);
+        }
+    }
+
+    CallNextHookEx(None, code, wparam, lparam)
 }
 
-#[cfg(windows)]
-pub fn capture_patch_png_base64(center_x: i32, center_y: i32, patch_size: u32) -> Result<String, String> {
+pub fn is_vk_down(vk: i32) -> bool {
+    #[cfg(windows)]
+    {
+        win_key_is_down(vk)
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        x11_key_is_down(vk)
+    }
+
+    #[cfg(not(any(windows, target_os = "linux")))]
+    {
+        let _ = vk;
+        false
+    }
+}
+
+fn patch_origin(screen: ScreenLayout, center_x: i32, center_y: i32, size: i32) -> (i32, i32) {
+    let half = size / 2;
+    let (min_x, min_y) = screen.origin;
+    let left = (center_x - half).clamp(min_x, min_x + (screen.size.0 - size).max(0));
+    let top = (center_y - half).clamp(min_y, min_y + (screen.size.1 - size).max(0));
+    (left, top)
+}
+
+fn encode_rgba_png_base64(width: u32, height: u32, rgba: Vec<u8>) -> Result<String, String> {
     use base64::engine::general_purpose;
     use base64::Engine;
     use ::image::{DynamicImage, ImageFormat, RgbaImage};
     use std::io::Cursor;
 
-    use windows::Win32::Graphics::Gdi::*;
-    let Some((screen_w, screen_h)) = win_screen_size() else {
-        return Err("GetSystemMetrics returned invalid screen size".to_string());
-    };
+    let rgba: RgbaImage = RgbaImage::from_raw(width, height, rgba).ok_or("Image buffer failed")?;
 
+    let mut png = Vec::new();
+    DynamicImage::ImageRgba8(rgba)
+        .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+
+    Ok(general_purpose::STANDARD.encode(png))
+}
+
+pub fn capture_patch_png_base64(center_x: i32, center_y: i32, patch_size: u32) -> Result<String, String> {
+    let screen = screen_layout().ok_or("Could not read the screen size for patch capture")?;
     let size = patch_size.max(16) as i32;
-    let half = size / 2;
-    let mut left = center_x - half;
-    let mut top = center_y - half;
-    left = left.clamp(0, screen_w - size);
-    top = top.clamp(0, screen_h - size);
+    let (left, top) = patch_origin(screen, center_x, center_y, size);
+    let rgba = capture_rgba(left, top, size, size)?;
+    encode_rgba_png_base64(size as u32, size as u32, rgba)
+}
+
+/// Grabs `area` of the desktop, which may reach onto any monitor.
+pub fn capture_screen(area: ScreenLayout) -> Result<::image::RgbaImage, String> {
+    let (width, height) = area.size;
+    if width <= 0 || height <= 0 {
+        return Err(format!("Cannot capture an empty area ({area})"));
+    }
+    let rgba = capture_rgba(area.origin.0, area.origin.1, width, height)?;
+    ::image::RgbaImage::from_raw(width as u32, height as u32, rgba).ok_or_else(|| "Image buffer failed".to_string())
+}
+
+/// RGBA pixels of a desktop rectangle, row by row.
+fn capture_rgba(left: i32, top: i32, width: i32, height: i32) -> Result<Vec<u8>, String> {
+    #[cfg(windows)]
+    {
+        win_capture_rgba(left, top, width, height)
+    }
+
+    #[cfg(target_os = "linux")]
+    {
+        x11_capture_rgba(left, top, width, height)
+    }
+
+    #[cfg(not(any(windows, target_os = "linux")))]
+    {
+        let _ = (left, top, width, height);
+        Err("Screen capture is not supported on this platform".to_string())
+    }
+}
+
+#[cfg(windows)]
+fn win_capture_rgba(left: i32, top: i32, width: i32, height: i32) -> Result<Vec<u8>, String> {
+    use windows::Win32::Graphics::Gdi::*;
 
     unsafe {
         let hdc_screen = GetDC(None);
@@ -242,7 +1240,7 @@ pub fn capture_patch_png_base64(center_x: i32, center_y: i32, patch_size: u32) -
             return Err("CreateCompatibleDC failed".to_string());
         }
 
-        let hbmp = CreateCompatibleBitmap(hdc_screen, size, size);
+        let hbmp = CreateCompatibleBitmap(hdc_screen, width, height);
         if hbmp.is_invalid() {
             let _ = DeleteDC(hdc_mem);
             let _ = ReleaseDC(None, hdc_screen);
@@ -261,8 +1259,8 @@ pub fn capture_patch_png_base64(center_x: i32, center_y: i32, patch_size: u32) -
             hdc_mem,
             0,
             0,
-            size,
-            size,
+            width,
+            height,
             Some(hdc_screen),
             left,
             top,
@@ -280,8 +1278,8 @@ pub fn capture_patch_png_base64(center_x: i32, center_y: i32, patch_size: u32) -
         let mut bmi = BITMAPINFO {
             bmiHeader: BITMAPINFOHEADER {
                 biSize: std::mem::size_of::<BITMAPINFOHEADER>() as u32,
-                biWidth: size,
-                biHeight: -size,
+                biWidth: width,
+                biHeight: -height,
                 biPlanes: 1,
                 biBitCount: 32,
                 biCompression: BI_RGB.0,
@@ -299,13 +1297,13 @@ pub fn capture_patch_png_base64(center_x: i32, center_y: i32, patch_size: u32) -
             }; 1],
         };
 
-        let mut bgra = vec![0u8; (size * size * 4) as usize];
+        let mut bgra = vec![0u8; (width * height * 4) as usize];
 
         let lines = GetDIBits(
             hdc_mem,
             hbmp,
             0,
-            size as u32,
+            height as u32,
             Some(bgra.as_mut_ptr() as *mut _),
             &mut bmi,
             DIB_RGB_COLORS,
@@ -329,24 +1327,76 @@ pub fn capture_patch_png_base64(center_x: i32, center_y: i32, patch_size: u32) -
             px[3] = a;
         }
 
-        let rgba: RgbaImage =
-            RgbaImage::from_raw(size as u32, size as u32, bgra).ok_or("Image buffer failed")?;
-
-        let mut png = Vec::new();
-        DynamicImage::ImageRgba8(rgba)
-            .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
-            .map_err(|e| e.to_string())?;
-
         let _ = SelectObject(hdc_mem, old);
         let _ = DeleteObject(hbmp.into());
         let _ = DeleteDC(hdc_mem);
         let _ = ReleaseDC(None, hdc_screen);
 
-        Ok(general_purpose::STANDARD.encode(png))
+        Ok(bgra)
     }
 }
 
-#[cfg(not(windows))]
-pub fn capture_patch_png_base64(_center_x: i32, _center_y: i32, _patch_size: u32) -> Result<String, String> {
-    Err("Image-search click recording is currently Windows-only".to_string())
+#[cfg(target_os = "linux")]
+fn x11_capture_rgba(left: i32, top: i32, width: i32, height: i32) -> Result<Vec<u8>, String> {
+    x11_with(|conn| unsafe {
+        let img = (conn.xlib.XGetImage)(
+            conn.display,
+            conn.root,
+            left,
+            top,
+            width as u32,
+            height as u32,
+            !0,
+            xlib::ZPixmap,
+        );
+        if img.is_null() {
+            return None;
+        }
+
+        let channel = |pixel: std::os::raw::c_ulong, mask: std::os::raw::c_ulong| -> u8 {
+            if mask == 0 {
+                return 0;
+            }
+            let shift = mask.trailing_zeros();
+            let max = mask >> shift;
+            (((pixel & mask) >> shift) * 255 / max) as u8
+        };
+
+        let (red_mask, green_mask, blue_mask) = ((*img).red_mask, (*img).green_mask, (*img).blue_mask);
+        let mut rgba = Vec::with_capacity((width * height * 4) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let pixel = (conn.xlib.XGetPixel)(img, x, y);
+                rgba.push(channel(pixel, red_mask));
+                rgba.push(channel(pixel, green_mask));
+                rgba.push(channel(pixel, blue_mask));
+                rgba.push(255);
+            }
+        }
+
+        (conn.xlib.XDestroyImage)(img);
+        Some(rgba)
+    })
+    .ok_or_else(|| "XGetImage failed".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn partial_wheel_notches_carry_over() {
+        SCROLL_CAPTURE_STATE.lock().unwrap().enabled = true;
+        add_scroll_units(0, 60);
+        assert_eq!(take_scroll_notches(), (0, 0));
+
+        add_scroll_units(0, 80);
+        add_scroll_units(-2 * WHEEL_DELTA, 0);
+        assert_eq!(take_scroll_notches(), (-2, 1));
+        assert_eq!(SCROLL_CAPTURE_STATE.lock().unwrap().units, (0, 20));
+
+        stop_scroll_capture();
+        add_scroll_units(0, WHEEL_DELTA);
+        assert_eq!(take_scroll_notches(), (0, 0));
+    }
 }
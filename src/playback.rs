@@ -2,380 +2,997 @@ use super::*;
 
 use base64::engine::general_purpose;
 use base64::Engine;
-use rustautogui::{MatchMode, MouseClick, RustAutoGui};
+use std::path::{Path, PathBuf};
+
+mod backend;
+mod capture;
+mod matching;
+#[cfg(test)]
+mod mock;
+#[cfg(test)]
+mod tests;
+
+pub(crate) use backend::{InputBackend, RustAutoGuiBackend};
+
+/// Pause and single-step requests from the UI. Honored before each row and
+/// inside waits and mouse moves; template searches keep running.
+#[derive(Debug, Default)]
+pub(crate) struct PlaybackPause {
+    paused: AtomicBool,
+    step_pending: AtomicBool,
+    // Owned by the playback thread: the current row was started by a step.
+    stepping: AtomicBool,
+}
+
+impl PlaybackPause {
+    pub(crate) fn pause(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn resume(&self) {
+        self.step_pending.store(false, Ordering::Relaxed);
+        self.paused.store(false, Ordering::Relaxed);
+    }
+
+    /// Runs the next row, then pauses again.
+    pub(crate) fn step(&self) {
+        self.paused.store(true, Ordering::Relaxed);
+        self.step_pending.store(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+}
+
+/// Run-level settings applied on top of the per-row `ClickListMeta` values.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct PlaybackOptions {
+    /// 2.0 plays twice as fast: waits, move durations and click holds are halved.
+    pub speed: f32,
+    /// Pace rows by their recorded `ms_from_start` deltas instead of `wait_ms`.
+    /// Plain moves then jump, since the recorded gaps already cover them.
+    pub faithful_timing: bool,
+    /// Failure policy for rows without their own `on_error`.
+    pub on_error: OnError,
+    /// Folder that gets a timestamped screenshot folder for every failed row;
+    /// `None` turns failure captures off.
+    pub capture_dir: Option<PathBuf>,
+    /// Editor row each played row came from, for naming failure captures like
+    /// the report table; rows past its end keep their own index.
+    pub source_rows: Vec<usize>,
+    /// Moves recorded positions onto the current screen; `None` plays them as
+    /// recorded.
+    pub remap: Option<ScreenRemap>,
+}
+
+impl PlaybackOptions {
+    pub(crate) const MIN_SPEED: f32 = 0.25;
+    pub(crate) const MAX_SPEED: f32 = 10.0;
+
+    fn scale_ms(&self, ms: u64) -> u64 {
+        let speed = self.speed.clamp(Self::MIN_SPEED, Self::MAX_SPEED) as f64;
+        (ms as f64 / speed).round() as u64
+    }
+}
+
+impl Default for PlaybackOptions {
+    fn default() -> Self {
+        Self {
+            speed: 1.0,
+            faithful_timing: false,
+            on_error: OnError::Abort,
+            capture_dir: None,
+            source_rows: Vec::new(),
+            remap: None,
+        }
+    }
+}
 
 pub(crate) fn playback(
     events: Vec<RecordedEvent>,
+    options: PlaybackOptions,
+    cancel: Arc<AtomicBool>,
+    pause: Arc<PlaybackPause>,
+    progress: Arc<AtomicUsize>,
+) -> PlaybackReport {
+    match RustAutoGuiBackend::new() {
+        Ok(mut backend) => playback_with_backend(&mut backend, events, options, cancel, pause, progress),
+        Err(err) => PlaybackReport {
+            error: Some(err.to_string()),
+            ..PlaybackReport::started_now()
+        },
+    }
+}
+
+/// Plays `events` and reports every executed row. A failed or cancelled run
+/// sets `PlaybackReport::error` and keeps the rows done so far.
+pub(crate) fn playback_with_backend<B: InputBackend>(
+    backend: &mut B,
+    events: Vec<RecordedEvent>,
+    options: PlaybackOptions,
     cancel: Arc<AtomicBool>,
+    pause: Arc<PlaybackPause>,
     progress: Arc<AtomicUsize>,
+) -> PlaybackReport {
+    let mut events = events;
+    if let Some(remap) = &options.remap {
+        remap_events(&mut events, remap);
+    }
+
+    let mut report = PlaybackReport::started_now();
+    let started_at = backend.elapsed();
+    if let Err(err) = play_rows(backend, &events, &options, &cancel, &pause, &progress, &mut report) {
+        report.error = Some(err.to_string());
+    }
+    report.duration_ms = backend.elapsed().saturating_sub(started_at).as_millis() as u64;
+    report
+}
+
+/// Applies `remap` to every row position, `Move` target and `Moves` point.
+/// Template searches still report where the image is on the current screen.
+/// Rows bound to a window are left alone; they follow the window instead.
+fn remap_events(events: &mut [RecordedEvent], remap: &ScreenRemap) {
+    let scopes = window_scopes(events);
+    for (ev, scope) in events.iter_mut().zip(scopes) {
+        if scope.is_none() {
+            ev.map_positions(|pos| remap.apply(pos));
+        }
+    }
+}
+
+fn play_rows<B: InputBackend>(
+    backend: &mut B,
+    events: &[RecordedEvent],
+    options: &PlaybackOptions,
+    cancel: &Arc<AtomicBool>,
+    pause: &PlaybackPause,
+    progress: &AtomicUsize,
+    report: &mut PlaybackReport,
 ) -> anyhow::Result<()> {
-    let mut gui = RustAutoGui::new(false)?;
+    let run_started = backend.elapsed();
+    let since_start = |at: Duration| at.saturating_sub(run_started).as_millis() as u64;
+    let mut search = SearchState::default();
+    let mut current_pos: Option<(i32, i32)> = backend.cursor_pos();
+    // Backend time and `ms_from_start` of the previous row, for faithful timing
+    let mut timeline: Option<(Duration, u128)> = None;
 
-    let mut last_smart_found: Option<(i32, i32)> = None;
-    let mut current_pos: Option<(i32, i32)> = get_mouse_pos();
+    let jumps = block_jumps(events).map_err(anyhow::Error::msg)?;
+    let labels = label_rows(events).map_err(anyhow::Error::msg)?;
+    let scopes = window_scopes(events);
+    // Open repeat blocks: start row and iterations left after the current one
+    let mut repeats: Vec<(usize, u32)> = Vec::new();
+    // Client origin found by each `BindWindow` row that ran
+    let mut window_origins: HashMap<usize, (i32, i32)> = HashMap::new();
 
-    let sleep_with_cancel = |cancel: &Arc<AtomicBool>, total_ms: u64| -> anyhow::Result<()> {
-        let mut remaining = Duration::from_millis(total_ms);
-        while remaining > Duration::from_millis(0) {
-            if cancel.load(Ordering::Relaxed) {
-                anyhow::bail!("Cancelled");
-            }
-            let step = remaining.min(Duration::from_millis(10));
-            std::thread::sleep(step);
-            remaining = remaining.saturating_sub(step);
+    let mut index = 0usize;
+    while index < events.len() {
+        if cancel.load(Ordering::Relaxed) {
+            anyhow::bail!("Cancelled");
         }
-        Ok(())
-    };
 
-    let click_once_with_speed = |gui: &mut RustAutoGui, button: MouseButton, speed_ms: u64| {
-        match button {
-            MouseButton::Left => {
-                let _ = gui.click_down(MouseClick::LEFT);
-                if speed_ms > 0 {
-                    std::thread::sleep(Duration::from_millis(speed_ms));
+        progress.store(index, Ordering::Relaxed);
+
+        match events[index].kind {
+            RecordedEventKind::RepeatStart { count: 0 } => {
+                index = jumps[index].unwrap_or(index) + 1;
+                continue;
+            }
+            RecordedEventKind::RepeatStart { count } => {
+                repeats.push((index, count - 1));
+                index += 1;
+                continue;
+            }
+            RecordedEventKind::RepeatEnd => {
+                match repeats.last_mut() {
+                    Some((start, remaining)) if *remaining > 0 => {
+                        *remaining -= 1;
+                        index = *start + 1;
+                    }
+                    _ => {
+                        repeats.pop();
+                        index += 1;
+                    }
                 }
-                let _ = gui.click_up(MouseClick::LEFT);
+                continue;
+            }
+            // Reached at the end of a taken `IfImage` branch.
+            RecordedEventKind::Else => {
+                index = jumps[index].unwrap_or(index) + 1;
+                continue;
+            }
+            RecordedEventKind::EndIf | RecordedEventKind::Label { .. } | RecordedEventKind::EndWindow => {
+                index += 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        wait_for_row_start(backend, cancel, pause)?;
+
+        let row = index;
+        index += 1;
+        let ms_from_start = events[row].ms_from_start;
+        let wait_ms = events[row]
+            .click_meta
+            .as_ref()
+            .map(|m| m.wait_ms as u64)
+            .unwrap_or(0);
+
+        if options.faithful_timing {
+            if let Some((previous_at, previous_ms)) = timeline {
+                let gap_ms = options.scale_ms(ms_from_start.saturating_sub(previous_ms) as u64);
+                let due = previous_at + Duration::from_millis(gap_ms);
+                let remaining_ms = due.saturating_sub(backend.elapsed()).as_millis() as u64;
+                sleep_with_cancel(backend, cancel, pause, remaining_ms)?;
             }
-            MouseButton::Right => {
-                let _ = gui.click_down(MouseClick::RIGHT);
-                if speed_ms > 0 {
-                    std::thread::sleep(Duration::from_millis(speed_ms));
+            timeline = Some((backend.elapsed(), ms_from_start));
+        } else if wait_ms > 0 && !matches!(events[row].kind, RecordedEventKind::Wait { .. }) {
+            sleep_with_cancel(backend, cancel, pause, options.scale_ms(wait_ms))?;
+        }
+
+        let policy = events[row].on_error.as_ref().unwrap_or(&options.on_error);
+        let row_started = backend.elapsed();
+        search.start_row();
+        let mut retries = 0u32;
+        // Error of a row that failed but was let through by its policy
+        let mut skipped: Option<String> = None;
+        let result = loop {
+            let attempt = match &events[row].kind {
+                RecordedEventKind::BindWindow { title, class } => {
+                    locate_window(backend, title, class).map(|origin| {
+                        window_origins.insert(row, origin);
+                        None
+                    })
+                }
+                _ => window_relative_event(backend, events, &scopes, &mut window_origins, row).and_then(|event| {
+                    perform_row(
+                        backend,
+                        cancel,
+                        pause,
+                        &mut current_pos,
+                        &mut search,
+                        options,
+                        event,
+                        row,
+                        &jumps,
+                        &labels,
+                    )
+                }),
+            };
+            let err = match attempt {
+                Ok(jump) => break Ok(jump),
+                Err(err) if cancel.load(Ordering::Relaxed) => break Err(err),
+                Err(err) => err,
+            };
+            match policy {
+                OnError::Retry { times, backoff_ms } if retries < *times => {
+                    let delay_ms = backoff_ms.saturating_mul(1 << retries.min(16));
+                    retries += 1;
+                    if let Err(err) = sleep_with_cancel(backend, cancel, pause, options.scale_ms(delay_ms)) {
+                        break Err(err);
+                    }
                 }
-                let _ = gui.click_up(MouseClick::RIGHT);
+                OnError::Retry { .. } => break Err(anyhow::anyhow!("{err} (gave up after {retries} retries)")),
+                OnError::Abort => break Err(err),
+                OnError::Skip => {
+                    skipped = Some(err.to_string());
+                    break Ok(None);
+                }
+                OnError::GotoLabel(name) => match labels.get(name) {
+                    Some(&target) => {
+                        skipped = Some(err.to_string());
+                        break Ok(Some(target));
+                    }
+                    None => break Err(anyhow::anyhow!("{err} (no label named \"{name}\")")),
+                },
             }
-            MouseButton::Middle => {
-                let _ = gui.click_down(MouseClick::MIDDLE);
-                if speed_ms > 0 {
-                    std::thread::sleep(Duration::from_millis(speed_ms));
+        };
+
+        let end_ms = since_start(backend.elapsed());
+        let action = format_event_with_prev(&events[row], None).0;
+        let mut error = result.as_ref().err().map(|err| err.to_string()).or(skipped.clone());
+        let mut failure_capture = None;
+        if let (Some(dir), Some(message)) = (&options.capture_dir, error.clone()) {
+            if !cancel.load(Ordering::Relaxed) {
+                let source_row = options.source_rows.get(row).copied().unwrap_or(row);
+                match capture::save_failure_capture(backend, dir, source_row, &action, &message, &search) {
+                    Ok(folder) => failure_capture = Some(folder.display().to_string()),
+                    Err(err) => error = Some(format!("{message} (failure capture failed: {err})")),
                 }
-                let _ = gui.click_up(MouseClick::MIDDLE);
             }
         }
-    };
 
-    for (index, ev) in events.into_iter().enumerate() {
-        if cancel.load(Ordering::Relaxed) {
-            anyhow::bail!("Cancelled");
+        report.rows.push(RowReport {
+            row,
+            action,
+            start_ms: since_start(row_started),
+            end_ms,
+            position: current_pos,
+            match_score: search.row_score,
+            match_scale: search.row_scale,
+            search_ms: search.row_search.map(|d| d.as_millis() as u64),
+            retries,
+            outcome: match (&result, &skipped) {
+                (Err(_), _) => RowOutcome::Failed,
+                (Ok(_), Some(_)) => RowOutcome::Skipped,
+                (Ok(_), None) => RowOutcome::Passed,
+            },
+            error,
+            failure_capture,
+        });
+
+        if let Some(target) = result? {
+            enter_repeat_blocks(events, &jumps, &mut repeats, target);
+            index = target;
         }
+    }
 
-        progress.store(index, Ordering::Relaxed);
+    progress.store(usize::MAX, Ordering::Relaxed);
 
-        let RecordedEvent {
-            kind,
-            pos,
-            click_meta,
-            ..
-        } = ev;
+    Ok(())
+}
+
+/// Performs one action row. Returns the row to continue at when it is not the
+/// next one: an `IfImage` miss, or a wait timing out to a label.
+#[allow(clippy::too_many_arguments)]
+fn perform_row<B: InputBackend>(
+    backend: &mut B,
+    cancel: &Arc<AtomicBool>,
+    pause: &PlaybackPause,
+    current_pos: &mut Option<(i32, i32)>,
+    search: &mut SearchState,
+    options: &PlaybackOptions,
+    event: RecordedEvent,
+    row: usize,
+    jumps: &[Option<usize>],
+    labels: &HashMap<String, usize>,
+) -> anyhow::Result<Option<usize>> {
+    let RecordedEvent {
+        kind,
+        pos,
+        click_meta,
+        monitor,
+        ..
+    } = event;
 
-        let wait_ms = click_meta.as_ref().map(|m| m.wait_ms as u64).unwrap_or(0);
-        let click_speed_ms = click_meta
+    let click_speed_ms = options.scale_ms(
+        click_meta
             .as_ref()
             .map(|m| m.click_speed_ms as u64)
             .unwrap_or(20)
-            .min(100);
-        let default_move_speed_ms = 25;
-        let mouse_move_speed_ms = click_meta
+            .min(100),
+    );
+    let default_move_speed_ms = 25;
+    let mouse_move_speed_ms = options.scale_ms(
+        click_meta
             .as_ref()
             .map(|m| m.mouse_move_speed_ms as u64)
             .unwrap_or(default_move_speed_ms)
-            .clamp(5, 500);
+            .clamp(5, 500),
+    );
+    let path_move_ms = if options.faithful_timing {
+        0
+    } else {
+        mouse_move_speed_ms
+    };
+    let gone = matches!(kind, RecordedEventKind::WaitUntilImageGone { .. });
 
-        if wait_ms > 0 && !matches!(&kind, RecordedEventKind::Wait { .. }) {
-            sleep_with_cancel(&cancel, wait_ms)?;
+    match kind {
+        RecordedEventKind::Move { x, y } => {
+            move_mouse_with_speed(
+                backend,
+                cancel,
+                pause,
+                current_pos,
+                (x, y),
+                path_move_ms,
+            )?;
         }
-
-        match kind {
-            RecordedEventKind::Move { x, y } => {
+        RecordedEventKind::Moves { points } => {
+            for (x, y) in points {
                 move_mouse_with_speed(
-                    &mut gui,
-                    &cancel,
-                    &mut current_pos,
+                    backend,
+                    cancel,
+                    pause,
+                    current_pos,
                     (x, y),
-                    mouse_move_speed_ms,
+                    path_move_ms,
                 )?;
             }
-            RecordedEventKind::Moves { points } => {
-                for (x, y) in points {
-                    move_mouse_with_speed(
-                        &mut gui,
-                        &cancel,
-                        &mut current_pos,
-                        (x, y),
-                        mouse_move_speed_ms,
-                    )?;
-                }
-            }
-            RecordedEventKind::Wait { ms } => {
-                sleep_with_cancel(&cancel, ms)?;
+        }
+        RecordedEventKind::Scroll { dx, dy } => {
+            if let Some(target) = pos {
+                move_mouse_with_speed(
+                    backend,
+                    cancel,
+                    pause,
+                    current_pos,
+                    target,
+                    mouse_move_speed_ms,
+                )?;
             }
-            RecordedEventKind::FindTarget {
-                patch_png_base64,
-                patch_size: _,
+            backend.scroll(dx, dy)?;
+        }
+        RecordedEventKind::Wait { ms } => {
+            sleep_with_cancel(backend, cancel, pause, options.scale_ms(ms))?;
+        }
+        RecordedEventKind::FindTarget {
+            patch_png_base64,
+            patch_size: _,
+            precision,
+            timeout_ms,
+            search_anchor,
+            search_region_size,
+            match_strategy,
+            target_offset: (dx, dy),
+            scale_sweep,
+            match_filter,
+            search_monitor,
+        } => {
+            let anchor_pos = search_anchor_pos(backend, search_anchor, pos, search.last_found);
+            let area = search_monitor.area(monitor, &backend.monitors()).map_err(anyhow::Error::msg)?;
+            let found = find_target_position(
+                backend,
+                cancel,
+                search,
+                &patch_png_base64,
                 precision,
                 timeout_ms,
-                search_anchor,
                 search_region_size,
-            } => {
-                let anchor_pos = match search_anchor {
-                    SearchAnchor::RecordedClick => pos,
-                    SearchAnchor::CurrentMouse => get_mouse_pos().or(pos).or(last_smart_found),
-                    SearchAnchor::LastFound => last_smart_found.or(pos).or(get_mouse_pos()),
-                };
-                let found = find_target_position(
-                    &mut gui,
-                    &cancel,
-                    &mut last_smart_found,
-                    &patch_png_base64,
-                    precision,
-                    timeout_ms,
-                    search_region_size,
-                    anchor_pos,
-                )?;
+                anchor_pos,
+                area,
+                match_strategy,
+                scale_sweep,
+                match_filter,
+            )?;
+            let found = (found.0 + dx, found.1 + dy);
+
+            move_mouse_with_speed(
+                backend,
+                cancel,
+                pause,
+                current_pos,
+                found,
+                mouse_move_speed_ms,
+            )?;
+        }
+        RecordedEventKind::LeftDown { patch_png_base64 } => {
+            let target_pos = resolve_click_target_position(
+                backend,
+                cancel,
+                search,
+                click_meta.as_ref(),
+                patch_png_base64.as_deref(),
+                pos,
+                monitor,
+            )?;
 
+            if let Some((x, y)) = target_pos {
                 move_mouse_with_speed(
-                    &mut gui,
-                    &cancel,
-                    &mut current_pos,
-                    found,
+                    backend,
+                    cancel,
+                    pause,
+                    current_pos,
+                    (x, y),
                     mouse_move_speed_ms,
                 )?;
             }
-            RecordedEventKind::LeftDown { patch_png_base64 } => {
-                let target_pos = resolve_click_target_position(
-                    &mut gui,
-                    &cancel,
-                    &mut last_smart_found,
-                    click_meta.as_ref(),
-                    patch_png_base64.as_deref(),
-                    pos,
-                )?;
+            let _ = backend.button_down(MouseButton::Left);
+        }
+        RecordedEventKind::LeftUp { patch_png_base64 } => {
+            let target_pos = resolve_click_target_position(
+                backend,
+                cancel,
+                search,
+                click_meta.as_ref(),
+                patch_png_base64.as_deref(),
+                pos,
+                monitor,
+            )?;
 
-                if let Some((x, y)) = target_pos {
-                    move_mouse_with_speed(
-                        &mut gui,
-                        &cancel,
-                        &mut current_pos,
-                        (x, y),
-                        mouse_move_speed_ms,
-                    )?;
-                }
-                let _ = gui.click_down(MouseClick::LEFT);
-            }
-            RecordedEventKind::LeftUp { patch_png_base64 } => {
-                let target_pos = resolve_click_target_position(
-                    &mut gui,
-                    &cancel,
-                    &mut last_smart_found,
-                    click_meta.as_ref(),
-                    patch_png_base64.as_deref(),
-                    pos,
+            if let Some(target) = target_pos {
+                move_mouse_with_speed(
+                    backend,
+                    cancel,
+                    pause,
+                    current_pos,
+                    target,
+                    mouse_move_speed_ms,
                 )?;
+            }
+            let _ = backend.button_up(MouseButton::Left);
+        }
+        RecordedEventKind::LeftClick { patch_png_base64 } => {
+            let target_pos = resolve_click_target_position(
+                backend,
+                cancel,
+                search,
+                click_meta.as_ref(),
+                patch_png_base64.as_deref(),
+                pos,
+                monitor,
+            )?;
 
-                if let Some(target) = target_pos {
-                    move_mouse_with_speed(
-                        &mut gui,
-                        &cancel,
-                        &mut current_pos,
-                        target,
-                        mouse_move_speed_ms,
-                    )?;
+            if let Some((x, y)) = target_pos {
+                move_mouse_with_speed(
+                    backend,
+                    cancel,
+                    pause,
+                    current_pos,
+                    (x, y),
+                    mouse_move_speed_ms,
+                )?;
+            }
+            click_once_with_speed(backend, MouseButton::Left, click_speed_ms);
+            if click_meta
+                .as_ref()
+                .map(|m| m.left_mode == ClickEdgeMode::Double)
+                .unwrap_or(false)
+            {
+                if click_speed_ms > 0 {
+                    backend.sleep(Duration::from_millis(click_speed_ms));
                 }
-                let _ = gui.click_up(MouseClick::LEFT);
+                click_once_with_speed(backend, MouseButton::Left, click_speed_ms);
             }
-            RecordedEventKind::LeftClick { patch_png_base64 } => {
-                let target_pos = resolve_click_target_position(
-                    &mut gui,
-                    &cancel,
-                    &mut last_smart_found,
-                    click_meta.as_ref(),
-                    patch_png_base64.as_deref(),
-                    pos,
-                )?;
+        }
+        RecordedEventKind::RightDown { patch_png_base64 } => {
+            let target_pos = resolve_click_target_position(
+                backend,
+                cancel,
+                search,
+                click_meta.as_ref(),
+                patch_png_base64.as_deref(),
+                pos,
+                monitor,
+            )?;
 
-                if let Some((x, y)) = target_pos {
-                    move_mouse_with_speed(
-                        &mut gui,
-                        &cancel,
-                        &mut current_pos,
-                        (x, y),
-                        mouse_move_speed_ms,
-                    )?;
-                }
-                click_once_with_speed(&mut gui, MouseButton::Left, click_speed_ms);
-                if click_meta
-                    .as_ref()
-                    .map(|m| m.left_mode == ClickEdgeMode::Double)
-                    .unwrap_or(false)
-                {
-                    if click_speed_ms > 0 {
-                        std::thread::sleep(Duration::from_millis(click_speed_ms));
-                    }
-                    click_once_with_speed(&mut gui, MouseButton::Left, click_speed_ms);
-                }
+            if let Some((x, y)) = target_pos {
+                move_mouse_with_speed(
+                    backend,
+                    cancel,
+                    pause,
+                    current_pos,
+                    (x, y),
+                    mouse_move_speed_ms,
+                )?;
             }
-            RecordedEventKind::RightDown { patch_png_base64 } => {
-                let target_pos = resolve_click_target_position(
-                    &mut gui,
-                    &cancel,
-                    &mut last_smart_found,
-                    click_meta.as_ref(),
-                    patch_png_base64.as_deref(),
-                    pos,
+            let _ = backend.button_down(MouseButton::Right);
+        }
+        RecordedEventKind::RightUp { patch_png_base64 } => {
+            let target_pos = resolve_click_target_position(
+                backend,
+                cancel,
+                search,
+                click_meta.as_ref(),
+                patch_png_base64.as_deref(),
+                pos,
+                monitor,
+            )?;
+
+            if let Some(target) = target_pos {
+                move_mouse_with_speed(
+                    backend,
+                    cancel,
+                    pause,
+                    current_pos,
+                    target,
+                    mouse_move_speed_ms,
                 )?;
+            }
+            let _ = backend.button_up(MouseButton::Right);
+        }
+        RecordedEventKind::RightClick { patch_png_base64 } => {
+            let target_pos = resolve_click_target_position(
+                backend,
+                cancel,
+                search,
+                click_meta.as_ref(),
+                patch_png_base64.as_deref(),
+                pos,
+                monitor,
+            )?;
 
-                if let Some((x, y)) = target_pos {
-                    move_mouse_with_speed(
-                        &mut gui,
-                        &cancel,
-                        &mut current_pos,
-                        (x, y),
-                        mouse_move_speed_ms,
-                    )?;
+            if let Some((x, y)) = target_pos {
+                move_mouse_with_speed(
+                    backend,
+                    cancel,
+                    pause,
+                    current_pos,
+                    (x, y),
+                    mouse_move_speed_ms,
+                )?;
+            }
+            click_once_with_speed(backend, MouseButton::Right, click_speed_ms);
+            if click_meta
+                .as_ref()
+                .map(|m| m.right_mode == ClickEdgeMode::Double)
+                .unwrap_or(false)
+            {
+                if click_speed_ms > 0 {
+                    backend.sleep(Duration::from_millis(click_speed_ms));
                 }
-                let _ = gui.click_down(MouseClick::RIGHT);
+                click_once_with_speed(backend, MouseButton::Right, click_speed_ms);
             }
-            RecordedEventKind::RightUp { patch_png_base64 } => {
-                let target_pos = resolve_click_target_position(
-                    &mut gui,
-                    &cancel,
-                    &mut last_smart_found,
-                    click_meta.as_ref(),
-                    patch_png_base64.as_deref(),
-                    pos,
-                )?;
+        }
+        RecordedEventKind::MiddleDown { patch_png_base64 } => {
+            let target_pos = resolve_click_target_position(
+                backend,
+                cancel,
+                search,
+                click_meta.as_ref(),
+                patch_png_base64.as_deref(),
+                pos,
+                monitor,
+            )?;
 
-                if let Some(target) = target_pos {
-                    move_mouse_with_speed(
-                        &mut gui,
-                        &cancel,
-                        &mut current_pos,
-                        target,
-                        mouse_move_speed_ms,
-                    )?;
-                }
-                let _ = gui.click_up(MouseClick::RIGHT);
+            if let Some((x, y)) = target_pos {
+                move_mouse_with_speed(
+                    backend,
+                    cancel,
+                    pause,
+                    current_pos,
+                    (x, y),
+                    mouse_move_speed_ms,
+                )?;
             }
-            RecordedEventKind::RightClick { patch_png_base64 } => {
-                let target_pos = resolve_click_target_position(
-                    &mut gui,
-                    &cancel,
-                    &mut last_smart_found,
-                    click_meta.as_ref(),
-                    patch_png_base64.as_deref(),
-                    pos,
+            let _ = backend.button_down(MouseButton::Middle);
+        }
+        RecordedEventKind::MiddleUp { patch_png_base64 } => {
+            let target_pos = resolve_click_target_position(
+                backend,
+                cancel,
+                search,
+                click_meta.as_ref(),
+                patch_png_base64.as_deref(),
+                pos,
+                monitor,
+            )?;
+
+            if let Some(target) = target_pos {
+                move_mouse_with_speed(
+                    backend,
+                    cancel,
+                    pause,
+                    current_pos,
+                    target,
+                    mouse_move_speed_ms,
                 )?;
+            }
+            let _ = backend.button_up(MouseButton::Middle);
+        }
+        RecordedEventKind::MiddleClick { patch_png_base64 } => {
+            let target_pos = resolve_click_target_position(
+                backend,
+                cancel,
+                search,
+                click_meta.as_ref(),
+                patch_png_base64.as_deref(),
+                pos,
+                monitor,
+            )?;
 
-                if let Some((x, y)) = target_pos {
-                    move_mouse_with_speed(
-                        &mut gui,
-                        &cancel,
-                        &mut current_pos,
-                        (x, y),
-                        mouse_move_speed_ms,
-                    )?;
+            if let Some((x, y)) = target_pos {
+                move_mouse_with_speed(
+                    backend,
+                    cancel,
+                    pause,
+                    current_pos,
+                    (x, y),
+                    mouse_move_speed_ms,
+                )?;
+            }
+            click_once_with_speed(backend, MouseButton::Middle, click_speed_ms);
+            if click_meta
+                .as_ref()
+                .map(|m| m.middle_mode == ClickEdgeMode::Double)
+                .unwrap_or(false)
+            {
+                if click_speed_ms > 0 {
+                    backend.sleep(Duration::from_millis(click_speed_ms));
                 }
-                click_once_with_speed(&mut gui, MouseButton::Right, click_speed_ms);
-                if click_meta
-                    .as_ref()
-                    .map(|m| m.right_mode == ClickEdgeMode::Double)
-                    .unwrap_or(false)
-                {
-                    if click_speed_ms > 0 {
-                        std::thread::sleep(Duration::from_millis(click_speed_ms));
+                click_once_with_speed(backend, MouseButton::Middle, click_speed_ms);
+            }
+        }
+        RecordedEventKind::KeyDown { key, modifiers } => {
+            for modifier in modifiers.key_names() {
+                backend.key_down(modifier)?;
+            }
+            backend.key_down(&key)?;
+        }
+        RecordedEventKind::KeyUp { key, modifiers } => {
+            backend.key_up(&key)?;
+            for modifier in modifiers.key_names().into_iter().rev() {
+                backend.key_up(modifier)?;
+            }
+        }
+        RecordedEventKind::TypeText { text } => {
+            backend.type_text(&text)?;
+        }
+        RecordedEventKind::IfImage {
+            patch_png_base64,
+            patch_size: _,
+            precision,
+            timeout_ms,
+            search_anchor,
+            search_region_size,
+        } => {
+            let anchor_pos = search_anchor_pos(backend, search_anchor, pos, search.last_found);
+            // Conditions store no match settings; search with the `FindTarget` defaults.
+            let found = find_target_position(
+                backend,
+                cancel,
+                search,
+                &patch_png_base64,
+                precision,
+                timeout_ms,
+                search_region_size,
+                anchor_pos,
+                None,
+                MatchStrategy::BestScore,
+                None,
+                MatchFilter::Grayscale,
+            );
+            if cancel.load(Ordering::Relaxed) {
+                anyhow::bail!("Cancelled");
+            }
+            // Any failure to find the image, including a bad patch, picks the other branch.
+            if found.is_err() {
+                return Ok(Some(jumps[row].unwrap_or(row) + 1));
+            }
+        }
+        RecordedEventKind::WaitForImage {
+            patch_png_base64,
+            patch_size: _,
+            precision,
+            timeout_ms,
+            poll_ms,
+            search_anchor,
+            search_region_size,
+            on_timeout,
+        }
+        | RecordedEventKind::WaitUntilImageGone {
+            patch_png_base64,
+            patch_size: _,
+            precision,
+            timeout_ms,
+            poll_ms,
+            search_anchor,
+            search_region_size,
+            on_timeout,
+        } => {
+            let anchor_pos = search_anchor_pos(backend, search_anchor, pos, search.last_found);
+            let met = wait_for_image_state(
+                backend,
+                cancel,
+                search,
+                &patch_png_base64,
+                precision,
+                timeout_ms,
+                poll_ms,
+                search_region_size,
+                anchor_pos,
+                gone,
+            )?;
+            if !met {
+                match on_timeout {
+                    OnTimeout::Fail if gone => anyhow::bail!("WaitUntilImageGone timed out ({} ms)", timeout_ms),
+                    OnTimeout::Fail => {
+                        search.missed(best_candidate(backend, search));
+                        anyhow::bail!("WaitForImage timed out ({} ms){}", timeout_ms, search.miss_note(precision));
+                    }
+                    OnTimeout::Continue => {}
+                    OnTimeout::GotoLabel(name) => {
+                        let Some(&target) = labels.get(&name) else {
+                            anyhow::bail!("No label named \"{name}\"");
+                        };
+                        return Ok(Some(target));
                     }
-                    click_once_with_speed(&mut gui, MouseButton::Right, click_speed_ms);
                 }
             }
-            RecordedEventKind::MiddleDown { patch_png_base64 } => {
-                let target_pos = resolve_click_target_position(
-                    &mut gui,
-                    &cancel,
-                    &mut last_smart_found,
-                    click_meta.as_ref(),
-                    patch_png_base64.as_deref(),
-                    pos,
-                )?;
+        }
+        // Handled by the caller.
+        RecordedEventKind::RepeatStart { .. }
+        | RecordedEventKind::RepeatEnd
+        | RecordedEventKind::Else
+        | RecordedEventKind::EndIf
+        | RecordedEventKind::Label { .. }
+        | RecordedEventKind::BindWindow { .. }
+        | RecordedEventKind::EndWindow => {}
+    }
 
-                if let Some((x, y)) = target_pos {
-                    move_mouse_with_speed(
-                        &mut gui,
-                        &cancel,
-                        &mut current_pos,
-                        (x, y),
-                        mouse_move_speed_ms,
-                    )?;
-                }
-                let _ = gui.click_down(MouseClick::MIDDLE);
-            }
-            RecordedEventKind::MiddleUp { patch_png_base64 } => {
-                let target_pos = resolve_click_target_position(
-                    &mut gui,
-                    &cancel,
-                    &mut last_smart_found,
-                    click_meta.as_ref(),
-                    patch_png_base64.as_deref(),
-                    pos,
-                )?;
+    Ok(None)
+}
 
-                if let Some(target) = target_pos {
-                    move_mouse_with_speed(
-                        &mut gui,
-                        &cancel,
-                        &mut current_pos,
-                        target,
-                        mouse_move_speed_ms,
-                    )?;
-                }
-                let _ = gui.click_up(MouseClick::MIDDLE);
-            }
-            RecordedEventKind::MiddleClick { patch_png_base64 } => {
-                let target_pos = resolve_click_target_position(
-                    &mut gui,
-                    &cancel,
-                    &mut last_smart_found,
-                    click_meta.as_ref(),
-                    patch_png_base64.as_deref(),
-                    pos,
-                )?;
+/// Brings the bound window to the front and returns its client origin.
+fn locate_window<B: InputBackend>(backend: &mut B, title: &str, class: &str) -> anyhow::Result<(i32, i32)> {
+    match backend.activate_window(title, class)? {
+        Some(window) => Ok(window.origin),
+        None => anyhow::bail!("No open window matches {}", window_pattern_label(title, class)),
+    }
+}
 
-                if let Some((x, y)) = target_pos {
-                    move_mouse_with_speed(
-                        &mut gui,
-                        &cancel,
-                        &mut current_pos,
-                        (x, y),
-                        mouse_move_speed_ms,
-                    )?;
-                }
-                click_once_with_speed(&mut gui, MouseButton::Middle, click_speed_ms);
-                if click_meta
-                    .as_ref()
-                    .map(|m| m.middle_mode == ClickEdgeMode::Double)
-                    .unwrap_or(false)
-                {
-                    if click_speed_ms > 0 {
-                        std::thread::sleep(Duration::from_millis(click_speed_ms));
-                    }
-                    click_once_with_speed(&mut gui, MouseButton::Middle, click_speed_ms);
-                }
+/// Row `row` in desktop coordinates. Rows inside a window block are shifted by
+/// the window's client origin, locating the window first when a jump skipped
+/// its `BindWindow` row.
+fn window_relative_event<B: InputBackend>(
+    backend: &mut B,
+    events: &[RecordedEvent],
+    scopes: &[Option<usize>],
+    window_origins: &mut HashMap<usize, (i32, i32)>,
+    row: usize,
+) -> anyhow::Result<RecordedEvent> {
+    let mut event = events[row].clone();
+    let Some(bind) = scopes[row] else {
+        return Ok(event);
+    };
+
+    let (dx, dy) = match (window_origins.get(&bind), &events[bind].kind) {
+        (Some(&origin), _) => origin,
+        (None, RecordedEventKind::BindWindow { title, class }) => {
+            let origin = locate_window(backend, title, class)?;
+            window_origins.insert(bind, origin);
+            origin
+        }
+        (None, _) => (0, 0),
+    };
+    event.map_positions(|(x, y)| (x + dx, y + dy));
+    Ok(event)
+}
+
+fn click_once_with_speed<B: InputBackend>(backend: &mut B, button: MouseButton, speed_ms: u64) {
+    let _ = backend.button_down(button);
+    if speed_ms > 0 {
+        backend.sleep(Duration::from_millis(speed_ms));
+    }
+    let _ = backend.button_up(button);
+}
+
+/// Template search results carried between rows, plus what the current row's
+/// searches found for the run report.
+#[derive(Debug, Default)]
+struct SearchState {
+    /// Center of the last match in the run, for `SearchAnchor::LastFound`.
+    last_found: Option<(i32, i32)>,
+    row_score: Option<f32>,
+    row_search: Option<Duration>,
+    /// Template and region of the row's last search, for failure captures.
+    row_patch: Option<Vec<u8>>,
+    row_region: Option<(u32, u32, u32, u32)>,
+    /// Desktop position of the searched screen's top-left pixel; `row_region`
+    /// and the backend's match centers are relative to it.
+    row_origin: (i32, i32),
+    row_template_size: Option<(u32, u32)>,
+    /// Filter of the prepared template, reused when the sweep re-prepares it.
+    row_filter: MatchFilter,
+    /// Template scale of the row's match, when it searched with a sweep.
+    row_scale: Option<f32>,
+    /// Scale that matched last in the run; later sweeps try it first.
+    last_scale: Option<f32>,
+    /// Best candidate below the threshold when the row's search timed out.
+    row_miss: Option<(i32, i32, f32)>,
+}
+
+impl SearchState {
+    fn start_row(&mut self) {
+        self.row_score = None;
+        self.row_search = None;
+        self.row_patch = None;
+        self.row_region = None;
+        self.row_template_size = None;
+        self.row_scale = None;
+        self.row_miss = None;
+    }
+
+    fn found(&mut self, pos: (i32, i32), score: f32) {
+        self.last_found = Some(pos);
+        self.row_score = Some(score);
+    }
+
+    fn missed(&mut self, best: Option<(i32, i32, f32)>) {
+        self.row_miss = best;
+        self.row_score = best.map(|(_, _, score)| score);
+    }
+
+    /// "; best match 87.3% at (x,y), needs 90.0%" for timeout errors.
+    fn miss_note(&self, precision: f32) -> String {
+        match self.row_miss {
+            Some((x, y, score)) => format!(
+                "; best match {:.1}% at ({x},{y}), needs {:.1}%",
+                score * 100.0,
+                precision * 100.0
+            ),
+            None => format!("; nothing on screen scores {:.0}% or more", CANDIDATE_FLOOR * 100.0),
+        }
+    }
+}
+
+/// Brings the open repeat blocks in line with a jump to `target`: blocks that
+/// do not contain it are left, blocks that do are entered on their first pass.
+fn enter_repeat_blocks(
+    events: &[RecordedEvent],
+    jumps: &[Option<usize>],
+    repeats: &mut Vec<(usize, u32)>,
+    target: usize,
+) {
+    let contains = |start: usize| start < target && jumps[start].is_some_and(|end| target < end);
+    repeats.retain(|&(start, _)| contains(start));
+    for (start, ev) in events[..target].iter().enumerate() {
+        if let RecordedEventKind::RepeatStart { count } = ev.kind {
+            if contains(start) && !repeats.iter().any(|&(open, _)| open == start) {
+                repeats.push((start, count.saturating_sub(1)));
             }
         }
     }
+    repeats.sort_by_key(|&(start, _)| start);
+}
 
-    progress.store(usize::MAX, Ordering::Relaxed);
+/// Blocks while paused. A pending step lets exactly this row through.
+fn wait_for_row_start<B: InputBackend>(
+    backend: &mut B,
+    cancel: &Arc<AtomicBool>,
+    pause: &PlaybackPause,
+) -> anyhow::Result<()> {
+    pause.stepping.store(false, Ordering::Relaxed);
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            anyhow::bail!("Cancelled");
+        }
+        if !pause.is_paused() {
+            return Ok(());
+        }
+        if pause.step_pending.swap(false, Ordering::Relaxed) {
+            pause.stepping.store(true, Ordering::Relaxed);
+            return Ok(());
+        }
+        backend.sleep(Duration::from_millis(10));
+    }
+}
 
+/// Mid-row pause point; a stepped row runs to its end.
+fn wait_while_paused<B: InputBackend>(
+    backend: &mut B,
+    cancel: &Arc<AtomicBool>,
+    pause: &PlaybackPause,
+) -> anyhow::Result<()> {
+    while pause.is_paused() && !pause.stepping.load(Ordering::Relaxed) {
+        if cancel.load(Ordering::Relaxed) {
+            anyhow::bail!("Cancelled");
+        }
+        backend.sleep(Duration::from_millis(10));
+    }
+    Ok(())
+}
+
+fn sleep_with_cancel<B: InputBackend>(
+    backend: &mut B,
+    cancel: &Arc<AtomicBool>,
+    pause: &PlaybackPause,
+    total_ms: u64,
+) -> anyhow::Result<()> {
+    let mut remaining = Duration::from_millis(total_ms);
+    while remaining > Duration::from_millis(0) {
+        if cancel.load(Ordering::Relaxed) {
+            anyhow::bail!("Cancelled");
+        }
+        wait_while_paused(backend, cancel, pause)?;
+        let step = remaining.min(Duration::from_millis(10));
+        backend.sleep(step);
+        remaining = remaining.saturating_sub(step);
+    }
     Ok(())
 }
 
-fn move_mouse_with_speed(
-    gui: &mut RustAutoGui,
+fn move_mouse_with_speed<B: InputBackend>(
+    backend: &mut B,
     cancel: &Arc<AtomicBool>,
+    pause: &PlaybackPause,
     current_pos: &mut Option<(i32, i32)>,
     target: (i32, i32),
     total_ms: u64,
 ) -> anyhow::Result<()> {
     let start = current_pos
-        .or_else(get_mouse_pos)
+        .or_else(|| backend.cursor_pos())
         .unwrap_or(target);
     if start == target {
         *current_pos = Some(target);
@@ -383,37 +1000,52 @@ fn move_mouse_with_speed(
     }
 
     if total_ms == 0 {
-        let _ = gui.move_mouse_to_pos(target.0.max(0) as u32, target.1.max(0) as u32, 0.0);
+        let _ = backend.move_mouse_to(target.0, target.1);
         *current_pos = Some(target);
         return Ok(());
     }
 
-    let steps = ((total_ms.max(1) + 9) / 10).clamp(1, 60) as i32;
+    let steps = total_ms.max(1).div_ceil(10).clamp(1, 60) as i32;
     let step_ms = (total_ms.max(1) / steps as u64).max(1);
 
     for i in 1..=steps {
         if cancel.load(Ordering::Relaxed) {
             anyhow::bail!("Cancelled");
         }
+        wait_while_paused(backend, cancel, pause)?;
 
         let t = i as f32 / steps as f32;
         let nx = start.0 as f32 + (target.0 - start.0) as f32 * t;
         let ny = start.1 as f32 + (target.1 - start.1) as f32 * t;
-        let _ = gui.move_mouse_to_pos(nx.max(0.0) as u32, ny.max(0.0) as u32, 0.0);
-        std::thread::sleep(Duration::from_millis(step_ms));
+        let _ = backend.move_mouse_to(nx as i32, ny as i32);
+        backend.sleep(Duration::from_millis(step_ms));
     }
 
     *current_pos = Some(target);
     Ok(())
 }
 
-fn resolve_click_target_position(
-    gui: &mut RustAutoGui,
+fn search_anchor_pos<B: InputBackend>(
+    backend: &mut B,
+    search_anchor: SearchAnchor,
+    pos: Option<(i32, i32)>,
+    last_smart_found: Option<(i32, i32)>,
+) -> Option<(i32, i32)> {
+    match search_anchor {
+        SearchAnchor::RecordedClick => pos,
+        SearchAnchor::CurrentMouse => backend.cursor_pos().or(pos).or(last_smart_found),
+        SearchAnchor::LastFound => last_smart_found.or(pos).or(backend.cursor_pos()),
+    }
+}
+
+fn resolve_click_target_position<B: InputBackend>(
+    backend: &mut B,
     cancel: &Arc<AtomicBool>,
-    last_smart_found: &mut Option<(i32, i32)>,
+    search: &mut SearchState,
     click_meta: Option<&ClickListMeta>,
     patch_png_base64: Option<&str>,
     pos: Option<(i32, i32)>,
+    monitor: Option<u32>,
 ) -> anyhow::Result<Option<(i32, i32)>> {
     let Some(meta) = click_meta else {
         anyhow::bail!("Click row is missing click metadata");
@@ -431,91 +1063,297 @@ fn resolve_click_target_position(
     let precision = meta.target_precision.clamp(0.5, 1.0);
     let timeout_ms = meta.target_timeout_ms.clamp(200, 10000);
 
+    let anchor_pos = pos.or(search.last_found).or(backend.cursor_pos());
+    let area = meta.search_monitor.area(monitor, &backend.monitors()).map_err(anyhow::Error::msg)?;
     let found = find_target_position(
-        gui,
+        backend,
         cancel,
-        last_smart_found,
+        search,
         patch_b64,
         precision,
         timeout_ms,
         None,
-        pos.or(*last_smart_found).or(get_mouse_pos()),
+        anchor_pos,
+        area,
+        meta.match_strategy,
+        meta.scale_sweep,
+        meta.match_filter,
     )?;
 
-    Ok(Some(found))
+    let (dx, dy) = meta.target_offset;
+    Ok(Some((found.0 + dx, found.1 + dy)))
 }
 
-fn find_target_position(
-    gui: &mut RustAutoGui,
+#[allow(clippy::too_many_arguments)]
+fn find_target_position<B: InputBackend>(
+    backend: &mut B,
     cancel: &Arc<AtomicBool>,
-    last_smart_found: &mut Option<(i32, i32)>,
+    search: &mut SearchState,
     patch_png_base64: &str,
     precision: f32,
     timeout_ms: u64,
     search_region_size: Option<u32>,
     anchor_pos: Option<(i32, i32)>,
+    area: Option<ScreenLayout>,
+    strategy: MatchStrategy,
+    scale_sweep: Option<ScaleSweep>,
+    filter: MatchFilter,
 ) -> anyhow::Result<(i32, i32)> {
-    let patch_png = general_purpose::STANDARD
-        .decode(patch_png_base64)
-        .map_err(|e| anyhow::anyhow!("FindTarget decode failed: {e}"))?;
+    prepare_template_search(backend, search, patch_png_base64, search_region_size, anchor_pos, area, filter)?;
 
-    let region = match (search_region_size, anchor_pos) {
-        (Some(size), Some((x, y))) => {
-            let (sw, sh) = gui.get_screen_size();
-            compute_region_around_point(sw, sh, x, y, size)
-        }
-        _ => None,
+    // Empty without a sweep: the 1x template prepared above is searched as is.
+    let scaled = match (scale_sweep, &search.row_patch) {
+        (Some(sweep), Some(patch_png)) => matching::scaled_patches(patch_png, &sweep.scales(search.last_scale))?,
+        _ => Vec::new(),
     };
 
-    gui.prepare_template_from_raw_encoded(&patch_png, region, MatchMode::FFT)?;
-
-    let started_at = Instant::now();
+    let started_at = backend.elapsed();
     let timeout = Duration::from_millis(timeout_ms);
 
-    loop {
+    let result = loop {
         if cancel.load(Ordering::Relaxed) {
-            anyhow::bail!("Cancelled");
+            break Err(anyhow::anyhow!("Cancelled"));
         }
-        if started_at.elapsed() > timeout {
-            anyhow::bail!("FindTarget timed out ({} ms)", timeout_ms);
+        if backend.elapsed().saturating_sub(started_at) > timeout {
+            let best = if scaled.len() > 1 {
+                scaled
+                    .iter()
+                    .filter_map(|patch| {
+                        backend.prepare_template(&patch.png, search.row_region, search.row_filter).ok()?;
+                        best_candidate(backend, search)
+                    })
+                    .max_by(|a, b| a.2.total_cmp(&b.2))
+            } else {
+                best_candidate(backend, search)
+            };
+            search.missed(best);
+            break Err(anyhow::anyhow!(
+                "FindTarget timed out ({} ms){}",
+                timeout_ms,
+                search.miss_note(precision)
+            ));
         }
 
-        match gui.find_image_on_screen(precision)? {
-            Some(locations) if !locations.is_empty() => {
-                let (x, y, _corr) = locations[0];
-                let found = (x as i32, y as i32);
-                *last_smart_found = Some(found);
-                return Ok(found);
+        match search_scales(backend, search, &scaled, precision, strategy, anchor_pos) {
+            Ok(Some((x, y, corr, scale))) => {
+                let found = (x, y);
+                search.found(found, corr);
+                if scale_sweep.is_some() {
+                    search.last_scale = Some(scale);
+                    search.row_scale = Some(scale);
+                }
+                break Ok(found);
             }
-            _ => std::thread::sleep(Duration::from_millis(50)),
+            Ok(None) => backend.sleep(Duration::from_millis(50)),
+            Err(err) => break Err(err),
+        }
+    };
+    *search.row_search.get_or_insert_default() += backend.elapsed().saturating_sub(started_at);
+    result
+}
+
+/// One search pass over `scaled`, most likely scale first, stopping at the
+/// first scale with a usable match. Returns the match with its scale last.
+fn search_scales<B: InputBackend>(
+    backend: &mut B,
+    search: &SearchState,
+    scaled: &[matching::ScaledPatch],
+    precision: f32,
+    strategy: MatchStrategy,
+    anchor_pos: Option<(i32, i32)>,
+) -> anyhow::Result<Option<(i32, i32, f32, f32)>> {
+    if scaled.len() <= 1 {
+        let locations = find_on_screen(backend, search, precision)?.unwrap_or_default();
+        let template_size = search.row_template_size.unwrap_or((1, 1));
+        // `Nth` keeps searching until at least that many matches are on screen.
+        return Ok(matching::select_match(locations, strategy, anchor_pos, template_size).map(|(x, y, corr)| (x, y, corr, 1.0)));
+    }
+
+    for patch in scaled {
+        backend.prepare_template(&patch.png, search.row_region, search.row_filter)?;
+        let locations = find_on_screen(backend, search, precision)?.unwrap_or_default();
+        if let Some((x, y, corr)) = matching::select_match(locations, strategy, anchor_pos, patch.size) {
+            return Ok(Some((x, y, corr, patch.scale)));
+        }
+    }
+    Ok(None)
+}
+
+/// Polls every `poll_ms` until the template is on screen, or off it when `gone`.
+/// Returns `false` if `timeout_ms` runs out first. Like `IfImage`, waits match
+/// in grayscale at 1x over the whole desktop.
+#[allow(clippy::too_many_arguments)]
+fn wait_for_image_state<B: InputBackend>(
+    backend: &mut B,
+    cancel: &Arc<AtomicBool>,
+    search: &mut SearchState,
+    patch_png_base64: &str,
+    precision: f32,
+    timeout_ms: u64,
+    poll_ms: u64,
+    search_region_size: Option<u32>,
+    anchor_pos: Option<(i32, i32)>,
+    gone: bool,
+) -> anyhow::Result<bool> {
+    prepare_template_search(
+        backend,
+        search,
+        patch_png_base64,
+        search_region_size,
+        anchor_pos,
+        None,
+        MatchFilter::Grayscale,
+    )?;
+
+    let started_at = backend.elapsed();
+    let timeout = Duration::from_millis(timeout_ms);
+
+    let result = loop {
+        if cancel.load(Ordering::Relaxed) {
+            break Err(anyhow::anyhow!("Cancelled"));
         }
+
+        let best = match find_on_screen(backend, search, precision) {
+            Ok(locations) => locations.and_then(|locations| locations.first().copied()),
+            Err(err) => break Err(err),
+        };
+        if let Some((x, y, corr)) = best {
+            search.found((x, y), corr);
+        }
+        if best.is_some() != gone {
+            break Ok(true);
+        }
+        if backend.elapsed().saturating_sub(started_at) > timeout {
+            break Ok(false);
+        }
+        backend.sleep(Duration::from_millis(poll_ms.max(10)));
+    };
+    *search.row_search.get_or_insert_default() += backend.elapsed().saturating_sub(started_at);
+    result
+}
+
+/// Lowest score `best_candidate` reports. Anything weaker is noise, and a
+/// search at 0 would list nearly every position on the screen.
+pub(crate) const CANDIDATE_FLOOR: f32 = 0.5;
+
+/// Scores the prepared template over the whole search area and returns the
+/// strongest candidate scoring at least `CANDIDATE_FLOOR`. Slower than a
+/// normal search, so it only runs once a search has already failed.
+fn best_candidate<B: InputBackend>(backend: &mut B, search: &SearchState) -> Option<(i32, i32, f32)> {
+    match find_on_screen(backend, search, CANDIDATE_FLOOR) {
+        Ok(Some(locations)) => locations.into_iter().max_by(|a, b| a.2.total_cmp(&b.2)),
+        _ => None,
     }
 }
 
-fn compute_region_around_point(
-    screen_w: i32,
-    screen_h: i32,
-    center_x: i32,
-    center_y: i32,
-    region_size: u32,
-) -> Option<(u32, u32, u32, u32)> {
-    if screen_w <= 0 || screen_h <= 0 {
+/// `find_template` with the match centers moved into desktop coordinates.
+fn find_on_screen<B: InputBackend>(
+    backend: &mut B,
+    search: &SearchState,
+    precision: f32,
+) -> anyhow::Result<Option<Vec<(i32, i32, f32)>>> {
+    let (ox, oy) = search.row_origin;
+    Ok(backend.find_template(precision)?.map(|locations| {
+        locations
+            .into_iter()
+            .map(|(x, y, score)| (x as i32 + ox, y as i32 + oy, score))
+            .collect()
+    }))
+}
+
+/// Best candidate for a template on the current screen, for "Test match now".
+pub(crate) fn test_template_match(
+    patch_png_base64: &str,
+    search_region_size: Option<u32>,
+    anchor_pos: Option<(i32, i32)>,
+    area: Option<ScreenLayout>,
+    filter: MatchFilter,
+) -> Result<Option<(i32, i32, f32)>, String> {
+    let mut backend = RustAutoGuiBackend::new().map_err(|e| e.to_string())?;
+    let mut search = SearchState::default();
+    prepare_template_search(
+        &mut backend,
+        &mut search,
+        patch_png_base64,
+        search_region_size,
+        anchor_pos,
+        area,
+        filter,
+    )
+    .map_err(|e| e.to_string())?;
+    Ok(best_candidate(&mut backend, &search))
+}
+
+fn prepare_template_search<B: InputBackend>(
+    backend: &mut B,
+    search: &mut SearchState,
+    patch_png_base64: &str,
+    search_region_size: Option<u32>,
+    anchor_pos: Option<(i32, i32)>,
+    area: Option<ScreenLayout>,
+    filter: MatchFilter,
+) -> anyhow::Result<()> {
+    let patch_png = general_purpose::STANDARD
+        .decode(patch_png_base64)
+        .map_err(|e| anyhow::anyhow!("FindTarget decode failed: {e}"))?;
+
+    let bounds = backend.screen_bounds();
+    let area = area.unwrap_or(bounds);
+    let region = match (search_region_size, anchor_pos) {
+        (Some(size), Some(anchor)) => compute_region_around_point(area, anchor, size),
+        _ if area != bounds => Some(area),
+        _ => None,
+    };
+    let region = region.and_then(|region| capture_region(bounds, region));
+
+    backend.prepare_template(&patch_png, region, filter)?;
+    search.row_template_size = ::image::ImageReader::new(std::io::Cursor::new(&patch_png))
+        .with_guessed_format()
+        .ok()
+        .and_then(|reader| reader.into_dimensions().ok());
+    search.row_patch = Some(patch_png);
+    search.row_region = region;
+    search.row_origin = bounds.origin;
+    search.row_filter = filter;
+    Ok(())
+}
+
+/// A `region_size` square (at least 100 px) around `center`, shifted to stay
+/// inside `area`, in desktop coordinates.
+fn compute_region_around_point(area: ScreenLayout, center: (i32, i32), region_size: u32) -> Option<ScreenLayout> {
+    let (aw, ah) = area.size;
+    if aw <= 0 || ah <= 0 {
         return None;
     }
 
     let size = region_size.max(100) as i32;
-    let half = size / 2;
+    let (width, height) = (size.min(aw), size.min(ah));
+    let (ax, ay) = area.origin;
 
-    let sw = screen_w as i32;
-    let sh = screen_h as i32;
+    let cx = center.0.clamp(ax, ax + aw - 1);
+    let cy = center.1.clamp(ay, ay + ah - 1);
+    let left = (cx - width / 2).clamp(ax, ax + aw - width);
+    let top = (cy - height / 2).clamp(ay, ay + ah - height);
 
-    let cx = center_x.clamp(0, sw.saturating_sub(1));
-    let cy = center_y.clamp(0, sh.saturating_sub(1));
-
-    let mut left = cx - half;
-    let mut top = cy - half;
-    left = left.clamp(0, sw.saturating_sub(size));
-    top = top.clamp(0, sh.saturating_sub(size));
+    Some(ScreenLayout {
+        origin: (left, top),
+        size: (width, height),
+    })
+}
 
-    Some((left as u32, top as u32, size as u32, size as u32))
+/// `region` clipped to `bounds` and made relative to its origin, the form
+/// `InputBackend::prepare_template` takes.
+fn capture_region(bounds: ScreenLayout, region: ScreenLayout) -> Option<(u32, u32, u32, u32)> {
+    let left = region.origin.0.max(bounds.origin.0);
+    let top = region.origin.1.max(bounds.origin.1);
+    let right = (region.origin.0 + region.size.0).min(bounds.origin.0 + bounds.size.0);
+    let bottom = (region.origin.1 + region.size.1).min(bounds.origin.1 + bounds.size.1);
+    (right > left && bottom > top).then(|| {
+        (
+            (left - bounds.origin.0) as u32,
+            (top - bounds.origin.1) as u32,
+            (right - left) as u32,
+            (bottom - top) as u32,
+        )
+    })
 }
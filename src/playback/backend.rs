@@ -0,0 +1,358 @@
+use super::*;
+
+use super::matching::{FftTemplate, FilteredTemplate};
+use rustautogui::{MatchMode, MouseClick, RustAutoGui};
+
+/// Everything `playback_with_backend()` needs from the desktop: cursor moves,
+/// button edges, screen bounds, template search and the clock used for waits.
+pub(crate) trait InputBackend {
+    fn cursor_pos(&mut self) -> Option<(i32, i32)>;
+    fn move_mouse_to(&mut self, x: i32, y: i32) -> anyhow::Result<()>;
+    fn button_down(&mut self, button: MouseButton) -> anyhow::Result<()>;
+    fn button_up(&mut self, button: MouseButton) -> anyhow::Result<()>;
+    /// Wheel notches; positive `dx` scrolls right, positive `dy` scrolls down.
+    fn scroll(&mut self, dx: i32, dy: i32) -> anyhow::Result<()>;
+    /// Desktop area `find_template` and `save_screenshot` cover. Search
+    /// regions and match centers are relative to its origin.
+    fn screen_bounds(&mut self) -> ScreenLayout;
+    /// Connected monitors in desktop coordinates, primary first.
+    fn monitors(&mut self) -> Vec<ScreenLayout>;
+
+    /// Key names follow rustautogui (`"return"`, `"ctrl"`, `"a"`, ...).
+    fn key_down(&mut self, key: &str) -> anyhow::Result<()>;
+    fn key_up(&mut self, key: &str) -> anyhow::Result<()>;
+    fn type_text(&mut self, text: &str) -> anyhow::Result<()>;
+
+    /// Loads an encoded template image for the following `find_template` calls.
+    /// Filters other than `Grayscale` go through `FilteredTemplate`.
+    fn prepare_template(
+        &mut self,
+        patch_png: &[u8],
+        region: Option<(u32, u32, u32, u32)>,
+        filter: MatchFilter,
+    ) -> anyhow::Result<()>;
+
+    /// Returns match centers and correlation values at or above `precision`.
+    fn find_template(&mut self, precision: f32) -> anyhow::Result<Option<Vec<(u32, u32, f32)>>>;
+
+    /// Brings the topmost window matching the title and class regexes to the
+    /// front and returns it; `None` when no window matches.
+    fn activate_window(&mut self, title: &str, class: &str) -> anyhow::Result<Option<WindowInfo>>;
+
+    /// Writes the whole screen to `path` as a PNG, for failure captures.
+    fn save_screenshot(&mut self, path: &Path) -> anyhow::Result<()>;
+
+    fn sleep(&mut self, duration: Duration);
+
+    /// Monotonic time since the backend was created.
+    fn elapsed(&self) -> Duration;
+}
+
+pub(crate) struct RustAutoGuiBackend {
+    gui: RustAutoGui,
+    started_at: Instant,
+    /// Set instead of rustautogui's own template while a filtered one is prepared.
+    filtered: Option<FilteredTemplate>,
+    filtered_region: Option<(u32, u32, u32, u32)>,
+    /// Set for grayscale searches on one monitor outside rustautogui's screen,
+    /// with their region.
+    monitor_fft: Option<(FftTemplate, (u32, u32, u32, u32))>,
+    /// The virtual desktop when it reaches past the screen rustautogui
+    /// captures, as with a second monitor on Windows. Searches reaching past
+    /// rustautogui's screen and screenshots then grab the desktop themselves.
+    desktop: Option<ScreenLayout>,
+}
+
+impl RustAutoGuiBackend {
+    pub(crate) fn new() -> anyhow::Result<Self> {
+        let mut gui = RustAutoGui::new(false)?;
+        let (w, h) = gui.get_screen_size();
+        let desktop = screen_layout().filter(|desktop| desktop.origin != (0, 0) || desktop.size != (w, h));
+        Ok(Self {
+            gui,
+            started_at: Instant::now(),
+            filtered: None,
+            filtered_region: None,
+            monitor_fft: None,
+            desktop,
+        })
+    }
+
+    /// rustautogui keeps its screen grabs to itself, so filtered searches
+    /// capture the screen themselves.
+    fn grab_screen(&mut self) -> anyhow::Result<::image::GrayImage> {
+        let screen = capture_screen(self.screen_bounds()).map_err(anyhow::Error::msg)?;
+        Ok(::image::DynamicImage::ImageRgba8(screen).to_luma8())
+    }
+}
+
+/// `region`, relative to `desktop` and defaulting to all of it, when it lies
+/// wholly on one of `monitors`.
+fn region_on_one_monitor(
+    desktop: ScreenLayout,
+    monitors: &[ScreenLayout],
+    region: Option<(u32, u32, u32, u32)>,
+) -> Option<(u32, u32, u32, u32)> {
+    let (x, y, w, h) = region.unwrap_or((0, 0, desktop.size.0 as u32, desktop.size.1 as u32));
+    let left = x as i32 + desktop.origin.0;
+    let top = y as i32 + desktop.origin.1;
+    monitors
+        .iter()
+        .any(|monitor| {
+            left >= monitor.origin.0
+                && top >= monitor.origin.1
+                && left + w as i32 <= monitor.origin.0 + monitor.size.0
+                && top + h as i32 <= monitor.origin.1 + monitor.size.1
+        })
+        .then_some((x, y, w, h))
+}
+
+/// `region`, relative to `desktop`, moved onto rustautogui's screen of
+/// `gui_size` at the desktop origin. `None` unless it lies wholly inside it.
+fn region_on_gui_screen(
+    desktop: ScreenLayout,
+    gui_size: (i32, i32),
+    region: Option<(u32, u32, u32, u32)>,
+) -> Option<(u32, u32, u32, u32)> {
+    let (x, y, w, h) = region?;
+    let left = x as i32 + desktop.origin.0;
+    let top = y as i32 + desktop.origin.1;
+    let inside = left >= 0 && top >= 0 && left + w as i32 <= gui_size.0 && top + h as i32 <= gui_size.1;
+    inside.then_some((left as u32, top as u32, w, h))
+}
+
+fn mouse_click(button: MouseButton) -> MouseClick {
+    match button {
+        MouseButton::Left => MouseClick::LEFT,
+        MouseButton::Right => MouseClick::RIGHT,
+        MouseButton::Middle => MouseClick::MIDDLE,
+    }
+}
+
+/// Recorded key names that rustautogui spells differently.
+fn rustautogui_key(key: &str) -> &str {
+    match key {
+        // rustautogui only knows space as " " on X11.
+        "space" => " ",
+        "pageup" => "pgup",
+        "pagedown" => "pgdn",
+        _ => key,
+    }
+}
+
+impl InputBackend for RustAutoGuiBackend {
+    fn cursor_pos(&mut self) -> Option<(i32, i32)> {
+        get_mouse_pos()
+    }
+
+    fn move_mouse_to(&mut self, x: i32, y: i32) -> anyhow::Result<()> {
+        // rustautogui only takes positions on its own screen; other monitors,
+        // including ones left of or above the primary, get a direct jump.
+        let (w, h) = self.gui.get_screen_size();
+        if (0..w).contains(&x) && (0..h).contains(&y) {
+            self.gui.move_mouse_to_pos(x as u32, y as u32, 0.0)?;
+        } else {
+            jump_mouse_to(x, y).map_err(anyhow::Error::msg)?;
+        }
+        Ok(())
+    }
+
+    fn button_down(&mut self, button: MouseButton) -> anyhow::Result<()> {
+        self.gui.click_down(mouse_click(button))?;
+        Ok(())
+    }
+
+    fn button_up(&mut self, button: MouseButton) -> anyhow::Result<()> {
+        self.gui.click_up(mouse_click(button))?;
+        Ok(())
+    }
+
+    fn scroll(&mut self, dx: i32, dy: i32) -> anyhow::Result<()> {
+        match dy.signum() {
+            1 => self.gui.scroll_down(dy.unsigned_abs())?,
+            -1 => self.gui.scroll_up(dy.unsigned_abs())?,
+            _ => {}
+        }
+        match dx.signum() {
+            1 => self.gui.scroll_right(dx.unsigned_abs())?,
+            -1 => self.gui.scroll_left(dx.unsigned_abs())?,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn screen_bounds(&mut self) -> ScreenLayout {
+        self.desktop.unwrap_or(ScreenLayout {
+            origin: (0, 0),
+            size: self.gui.get_screen_size(),
+        })
+    }
+
+    fn monitors(&mut self) -> Vec<ScreenLayout> {
+        let monitors = monitors();
+        if monitors.is_empty() {
+            return vec![self.screen_bounds()];
+        }
+        monitors
+    }
+
+    fn key_down(&mut self, key: &str) -> anyhow::Result<()> {
+        self.gui.key_down(rustautogui_key(key))?;
+        Ok(())
+    }
+
+    fn key_up(&mut self, key: &str) -> anyhow::Result<()> {
+        self.gui.key_up(rustautogui_key(key))?;
+        Ok(())
+    }
+
+    fn type_text(&mut self, text: &str) -> anyhow::Result<()> {
+        self.gui.keyboard_input(text)?;
+        Ok(())
+    }
+
+    fn prepare_template(
+        &mut self,
+        patch_png: &[u8],
+        region: Option<(u32, u32, u32, u32)>,
+        filter: MatchFilter,
+    ) -> anyhow::Result<()> {
+        // rustautogui only searches its own screen. Grayscale searches past it
+        // grab their monitor and match with `FftTemplate`; only those spanning
+        // monitors go through `FilteredTemplate`.
+        let gui_region = match self.desktop {
+            None => Some(region),
+            Some(desktop) => region_on_gui_screen(desktop, self.gui.get_screen_size(), region).map(Some),
+        };
+        let monitor_region = match (filter, gui_region, self.desktop) {
+            (MatchFilter::Grayscale, None, Some(desktop)) => region_on_one_monitor(desktop, &monitors(), region),
+            _ => None,
+        };
+        self.filtered = None;
+        self.monitor_fft = None;
+        if let (MatchFilter::Grayscale, Some(gui_region)) = (filter, gui_region) {
+            self.gui.prepare_template_from_raw_encoded(patch_png, gui_region, MatchMode::FFT)?;
+        } else if let Some(region) = monitor_region {
+            self.monitor_fft = Some((FftTemplate::new(patch_png, (region.2, region.3))?, region));
+        } else {
+            self.filtered = Some(FilteredTemplate::new(patch_png, filter)?);
+            self.filtered_region = region;
+        }
+        Ok(())
+    }
+
+    fn find_template(&mut self, precision: f32) -> anyhow::Result<Option<Vec<(u32, u32, f32)>>> {
+        if let Some(template) = self.filtered.take() {
+            let matches = self.grab_screen().map(|screen| template.find(&screen, self.filtered_region, precision));
+            self.filtered = Some(template);
+            let matches = matches?;
+            return Ok((!matches.is_empty()).then_some(matches));
+        }
+        // rustautogui's screen sits at the desktop origin.
+        let (dx, dy) = self.desktop.map_or((0, 0), |desktop| desktop.origin);
+        if let Some((template, region)) = &self.monitor_fft {
+            let (x, y, w, h) = *region;
+            let area = ScreenLayout {
+                origin: (x as i32 + dx, y as i32 + dy),
+                size: (w as i32, h as i32),
+            };
+            let screen = ::image::DynamicImage::ImageRgba8(capture_screen(area).map_err(anyhow::Error::msg)?).to_luma8();
+            let matches: Vec<_> = template
+                .find(&screen, precision)
+                .into_iter()
+                .map(|(mx, my, score)| (mx + x, my + y, score))
+                .collect();
+            return Ok((!matches.is_empty()).then_some(matches));
+        }
+        Ok(self.gui.find_image_on_screen(precision)?.map(|matches| {
+            matches
+                .into_iter()
+                .map(|(x, y, score)| ((x as i32 - dx) as u32, (y as i32 - dy) as u32, score))
+                .collect()
+        }))
+    }
+
+    fn activate_window(&mut self, title: &str, class: &str) -> anyhow::Result<Option<WindowInfo>> {
+        let Some(window) = find_window(title, class).map_err(anyhow::Error::msg)? else {
+            return Ok(None);
+        };
+        activate_window(window.id);
+        // Give the window manager time to restore and raise it, then read the
+        // client area again since restoring can move it.
+        std::thread::sleep(Duration::from_millis(150));
+        let matcher = WindowMatcher::new(title, class).map_err(anyhow::Error::msg)?;
+        Ok(list_windows()
+            .into_iter()
+            .find(|candidate| candidate.id == window.id && matcher.matches(candidate))
+            .or(Some(window)))
+    }
+
+    fn save_screenshot(&mut self, path: &Path) -> anyhow::Result<()> {
+        if let Some(desktop) = self.desktop {
+            capture_screen(desktop).map_err(anyhow::Error::msg)?.save(path)?;
+            return Ok(());
+        }
+        let path = path.to_str().ok_or_else(|| anyhow::anyhow!("Screenshot path is not UTF-8"))?;
+        self.gui.save_screenshot(path)?;
+        Ok(())
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        std::thread::sleep(duration);
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn regions_inside_the_primary_screen_keep_the_fft_search() {
+        // A 1280 px monitor left of a 1920x1080 primary
+        let desktop = ScreenLayout {
+            origin: (-1280, 0),
+            size: (3200, 1080),
+        };
+        let gui_size = (1920, 1080);
+        assert_eq!(
+            region_on_gui_screen(desktop, gui_size, Some((1380, 50, 200, 100))),
+            Some((100, 50, 200, 100))
+        );
+        assert_eq!(region_on_gui_screen(desktop, gui_size, Some((1200, 50, 200, 100))), None);
+        assert_eq!(region_on_gui_screen(desktop, gui_size, Some((3100, 50, 200, 100))), None);
+        assert_eq!(region_on_gui_screen(desktop, gui_size, None), None);
+    }
+
+    #[test]
+    fn regions_on_one_monitor_keep_an_fft_search() {
+        let left = ScreenLayout {
+            origin: (-1280, 0),
+            size: (1280, 1024),
+        };
+        let primary = ScreenLayout {
+            origin: (0, 0),
+            size: (1920, 1080),
+        };
+        let desktop = ScreenLayout {
+            origin: (-1280, 0),
+            size: (3200, 1080),
+        };
+        let monitors = [primary, left];
+        assert_eq!(
+            region_on_one_monitor(desktop, &monitors, Some((100, 50, 200, 100))),
+            Some((100, 50, 200, 100))
+        );
+        assert_eq!(
+            region_on_one_monitor(desktop, &monitors, Some((0, 0, 1280, 1024))),
+            Some((0, 0, 1280, 1024))
+        );
+        // Reaching below the shorter monitor or across both needs the fallback.
+        assert_eq!(region_on_one_monitor(desktop, &monitors, Some((100, 1000, 200, 50))), None);
+        assert_eq!(region_on_one_monitor(desktop, &monitors, Some((1200, 50, 200, 100))), None);
+        assert_eq!(region_on_one_monitor(desktop, &monitors, None), None);
+    }
+}
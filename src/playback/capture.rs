@@ -0,0 +1,100 @@
+use super::*;
+
+use crate::report::{iso8601_utc, xml_escape};
+
+/// Saves what was on screen when editor row `row` failed into a new
+/// timestamped folder under `dir`: the full screen, the crop that was searched,
+/// the expected template and an `index.html` showing them side by side.
+pub(super) fn save_failure_capture<B: InputBackend>(
+    backend: &mut B,
+    dir: &Path,
+    row: usize,
+    action: &str,
+    error: &str,
+    search: &SearchState,
+) -> anyhow::Result<PathBuf> {
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+    let stamp = format!("{}.{:03}", iso8601_utc(now_ms / 1000).replace(':', "-"), now_ms % 1000);
+    let folder = dir.join(format!("{stamp}-row{row}"));
+    std::fs::create_dir_all(&folder)
+        .map_err(|e| anyhow::anyhow!("{}: {e}", folder.display()))?;
+
+    // Missing pieces are listed in the summary instead of failing the capture.
+    let mut notes = Vec::new();
+    let screen = match backend.save_screenshot(&folder.join("screen.png")) {
+        Ok(()) => ::image::open(folder.join("screen.png")).ok(),
+        Err(err) => {
+            notes.push(format!("Screenshot failed: {err}"));
+            None
+        }
+    };
+
+    let (template, region) = (search.row_patch.as_ref(), search.row_region);
+    if let Some(patch_png) = template {
+        std::fs::write(folder.join("template.png"), patch_png)?;
+    }
+
+    let mut region_saved = false;
+    if let (Some(screen), Some((x, y, w, h))) = (&screen, region) {
+        let w = w.min(screen.width().saturating_sub(x));
+        let h = h.min(screen.height().saturating_sub(y));
+        if w > 0 && h > 0 {
+            match screen.crop_imm(x, y, w, h).save(folder.join("region.png")) {
+                Ok(()) => region_saved = true,
+                Err(err) => notes.push(format!("Region crop failed: {err}")),
+            }
+        }
+    }
+
+    let mut details = vec![format!("Error: {error}")];
+    details.push(match region {
+        Some((x, y, w, h)) => {
+            let (ox, oy) = search.row_origin;
+            format!("Search region: {w}x{h} at ({},{})", x as i32 + ox, y as i32 + oy)
+        }
+        None if template.is_some() => "Search region: whole screen".to_string(),
+        None => "The row made no template search.".to_string(),
+    });
+    match (search.row_miss, search.row_score) {
+        (Some((x, y, score)), _) => details.push(format!("Best candidate: {:.1}% at ({x},{y})", score * 100.0)),
+        (None, Some(score)) => details.push(format!("Best match: {:.1}%", score * 100.0)),
+        (None, None) => {}
+    }
+    details.extend(notes);
+
+    let figure = |file: &str, caption: &str| {
+        format!("<figure><img src=\"{file}\"><figcaption>{caption}</figcaption></figure>")
+    };
+    let mut figures = Vec::new();
+    if template.is_some() {
+        figures.push(figure("template.png", "Expected template"));
+    }
+    if region_saved {
+        figures.push(figure("region.png", "Search region"));
+    }
+    if screen.is_some() {
+        figures.push(figure("screen.png", "Full screen"));
+    }
+
+    let title = xml_escape(&format!("Row {row} failed: {action}"));
+    let html = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>\n\
+         body {{ font-family: sans-serif; background: #1a1e24; color: #e6e6e6; }}\n\
+         .images {{ display: flex; gap: 16px; align-items: flex-start; }}\n\
+         figure {{ margin: 0; }}\n\
+         img {{ max-width: 60vw; border: 1px solid #4a5462; image-rendering: pixelated; }}\n\
+         </style>\n</head>\n<body>\n<h1>{title}</h1>\n{}\n<div class=\"images\">\n{}\n</div>\n</body>\n</html>\n",
+        details
+            .iter()
+            .map(|line| format!("<p>{}</p>", xml_escape(line)))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        figures.join("\n"),
+    );
+    std::fs::write(folder.join("index.html"), html)?;
+
+    Ok(folder)
+}
@@ -0,0 +1,621 @@
+use super::*;
+
+use ::image::GrayImage;
+use rustfft::num_complex::Complex;
+use rustfft::{FftDirection, FftPlanner};
+
+/// Picks one hit from a template search according to `strategy`.
+///
+/// A search reports every offset above the threshold, so one on-screen match
+/// usually shows up as a cluster of neighbouring hits. Hits closer than half
+/// the template size to a better one are merged into it first, leaving one
+/// hit per match. `None` when there is nothing to pick, including an `Nth`
+/// past the number of matches.
+pub(super) fn select_match(
+    locations: Vec<(i32, i32, f32)>,
+    strategy: MatchStrategy,
+    anchor: Option<(i32, i32)>,
+    template_size: (u32, u32),
+) -> Option<(i32, i32, f32)> {
+    let (tw, th) = template_size;
+    let (merge_x, merge_y) = ((tw / 2).max(1), (th / 2).max(1));
+
+    let mut locations = locations;
+    locations.sort_by(|a, b| b.2.total_cmp(&a.2));
+    let mut matches: Vec<(i32, i32, f32)> = Vec::new();
+    for hit in locations {
+        let merged = matches
+            .iter()
+            .any(|kept| kept.0.abs_diff(hit.0) < merge_x && kept.1.abs_diff(hit.1) < merge_y);
+        if !merged {
+            matches.push(hit);
+        }
+    }
+
+    match strategy {
+        MatchStrategy::BestScore => matches.first().copied(),
+        MatchStrategy::NearestAnchor => match anchor {
+            Some((ax, ay)) => matches.into_iter().min_by_key(|&(x, y, _)| {
+                let (dx, dy) = (x as i64 - ax as i64, y as i64 - ay as i64);
+                dx * dx + dy * dy
+            }),
+            None => matches.first().copied(),
+        },
+        MatchStrategy::Nth(n) => {
+            // Matches whose centers are within half a template height share a row.
+            matches.sort_by_key(|&(x, y, _)| (y, x));
+            let mut rows: Vec<Vec<(i32, i32, f32)>> = Vec::new();
+            for hit in matches {
+                match rows.last_mut() {
+                    Some(row) if hit.1.abs_diff(row[0].1) < merge_y => row.push(hit),
+                    _ => rows.push(vec![hit]),
+                }
+            }
+            rows.into_iter()
+                .flat_map(|mut row| {
+                    row.sort_by_key(|&(x, _, _)| x);
+                    row
+                })
+                .nth(n.max(1) as usize - 1)
+        }
+        MatchStrategy::Leftmost => matches.into_iter().min_by_key(|&(x, y, _)| (x, y)),
+        MatchStrategy::Topmost => matches.into_iter().min_by_key(|&(x, y, _)| (y, x)),
+        MatchStrategy::Random if matches.is_empty() => None,
+        MatchStrategy::Random => {
+            let seed = std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.subsec_nanos() as usize)
+                .unwrap_or(0);
+            matches.get(seed % matches.len()).copied()
+        }
+    }
+}
+
+/// The row's template resized to one scale of a `ScaleSweep`.
+pub(super) struct ScaledPatch {
+    pub scale: f32,
+    pub png: Vec<u8>,
+    pub size: (u32, u32),
+}
+
+/// Resizes `patch_png` to each of `scales`, keeping their order. Scales that
+/// would shrink the template below 4 px are dropped.
+pub(super) fn scaled_patches(patch_png: &[u8], scales: &[f32]) -> anyhow::Result<Vec<ScaledPatch>> {
+    let patch = ::image::load_from_memory(patch_png)
+        .map_err(|e| anyhow::anyhow!("FindTarget decode failed: {e}"))?;
+    let (w, h) = (patch.width(), patch.height());
+
+    let mut out = Vec::with_capacity(scales.len());
+    for &scale in scales {
+        let size = (
+            (w as f32 * scale).round() as u32,
+            (h as f32 * scale).round() as u32,
+        );
+        if size.0 < 4 || size.1 < 4 {
+            continue;
+        }
+        let png = if size == (w, h) {
+            patch_png.to_vec()
+        } else {
+            let resized = patch.resize_exact(size.0, size.1, ::image::imageops::FilterType::Triangle);
+            let mut png = Vec::new();
+            resized.write_to(&mut std::io::Cursor::new(&mut png), ::image::ImageFormat::Png)?;
+            png
+        };
+        out.push(ScaledPatch { scale, png, size });
+    }
+    Ok(out)
+}
+
+/// A template for the `MatchFilter`s the backends can't search themselves,
+/// matched here against a grayscale screen grab.
+pub(super) struct FilteredTemplate {
+    filter: MatchFilter,
+    size: (u32, u32),
+    /// Filtered pixels, with 0 wherever `weights` is 0.
+    pixels: GrayImage,
+    /// 1.0 for compared pixels, 0.0 for ignored ones.
+    weights: Vec<f32>,
+}
+
+impl FilteredTemplate {
+    pub(super) fn new(patch_png: &[u8], filter: MatchFilter) -> anyhow::Result<Self> {
+        let patch = ::image::load_from_memory(patch_png)
+            .map_err(|e| anyhow::anyhow!("FindTarget decode failed: {e}"))?;
+        let (w, h) = (patch.width(), patch.height());
+
+        let (pixels, weights) = match filter {
+            MatchFilter::Grayscale => (patch.to_luma8(), vec![1.0; (w * h) as usize]),
+            // The template's outer ring has no neighbours to take a gradient from.
+            MatchFilter::Edges => {
+                let weights = (0..h)
+                    .flat_map(|y| (0..w).map(move |x| (x > 0 && y > 0 && x + 1 < w && y + 1 < h) as u8 as f32))
+                    .collect();
+                (edge_map(&patch.to_luma8()), weights)
+            }
+            MatchFilter::Masked => {
+                let weights = patch.to_rgba8().pixels().map(|p| (p[3] >= 128) as u8 as f32).collect();
+                (patch.to_luma8(), weights)
+            }
+        };
+        if weights.iter().sum::<f32>() < 4.0 {
+            anyhow::bail!("The template mask leaves almost no pixels to compare");
+        }
+        Ok(Self {
+            filter,
+            size: (w, h),
+            pixels,
+            weights,
+        })
+    }
+
+    /// Match centers at or above `precision`, best first, in screen coordinates
+    /// like `InputBackend::find_template`. Only local score peaks are reported.
+    ///
+    /// Large templates are first matched at a reduced size and then refined
+    /// at full size around the promising spots, which keeps whole-screen
+    /// searches at interactive speed.
+    pub(super) fn find(
+        &self,
+        screen: &GrayImage,
+        region: Option<(u32, u32, u32, u32)>,
+        precision: f32,
+    ) -> Vec<(u32, u32, f32)> {
+        let (rx, ry, rw, rh) = region.unwrap_or((0, 0, screen.width(), screen.height()));
+        let rx = rx.min(screen.width());
+        let ry = ry.min(screen.height());
+        let rw = rw.min(screen.width() - rx);
+        let rh = rh.min(screen.height() - ry);
+        let (tw, th) = self.size;
+        if tw > rw || th > rh {
+            return Vec::new();
+        }
+
+        let crop = ::image::imageops::crop_imm(screen, rx, ry, rw, rh).to_image();
+        let crop = match self.filter {
+            MatchFilter::Edges => edge_map(&crop),
+            MatchFilter::Grayscale | MatchFilter::Masked => crop,
+        };
+        let full = Scorer::new(&self.pixels, &self.weights);
+
+        let factor = (tw.min(th) / 8).clamp(1, 4);
+        let mut hits = if factor == 1 {
+            let scores = full.score_map(&crop);
+            local_peaks(&scores, crop.width() - tw + 1, precision)
+        } else {
+            let small = |img: &GrayImage| {
+                ::image::imageops::resize(
+                    img,
+                    (img.width() / factor).max(1),
+                    (img.height() / factor).max(1),
+                    ::image::imageops::FilterType::Triangle,
+                )
+            };
+            let weight_img = GrayImage::from_raw(tw, th, self.weights.iter().map(|w| (w * 255.0) as u8).collect())
+                .unwrap_or_default();
+            let small_weights: Vec<f32> = small(&weight_img).pixels().map(|p| (p[0] >= 128) as u8 as f32).collect();
+            let coarse = Scorer::new(&small(&self.pixels), &small_weights);
+            let small_crop = small(&crop);
+            if coarse.size.0 > small_crop.width() || coarse.size.1 > small_crop.height() {
+                return Vec::new();
+            }
+
+            // Downscaling blurs the scores, edge maps especially, so the true
+            // match can score low here: refine the strongest peaks whatever
+            // their score, plus fairly good ones up to a cap.
+            let scores = coarse.score_map(&small_crop);
+            let candidates: Vec<_> = local_peaks(&scores, small_crop.width() - coarse.size.0 + 1, f32::MIN)
+                .into_iter()
+                .enumerate()
+                .take_while(|(i, peak)| *i < 32 || (*i < 256 && peak.2 >= precision - 0.25))
+                .map(|(_, peak)| peak)
+                .collect();
+
+            let (max_x, max_y) = (crop.width() - tw, crop.height() - th);
+            let mut refined: Vec<(u32, u32, f32)> = Vec::new();
+            for (cx, cy, _) in candidates {
+                let (cx, cy) = (cx * factor, cy * factor);
+                let mut best = None;
+                for oy in cy.saturating_sub(factor)..=(cy + factor).min(max_y) {
+                    for ox in cx.saturating_sub(factor)..=(cx + factor).min(max_x) {
+                        let score = full.score_at(&crop, ox, oy);
+                        if best.is_none_or(|(_, _, s)| score > s) {
+                            best = Some((ox, oy, score));
+                        }
+                    }
+                }
+                if let Some(hit) = best.filter(|hit| hit.2 >= precision) {
+                    if !refined.iter().any(|r| (r.0, r.1) == (hit.0, hit.1)) {
+                        refined.push(hit);
+                    }
+                }
+            }
+            refined
+        };
+
+        for hit in &mut hits {
+            hit.0 += rx + tw / 2;
+            hit.1 += ry + th / 2;
+        }
+        hits.sort_by(|a, b| b.2.total_cmp(&a.2));
+        hits
+    }
+}
+
+/// A grayscale template matched by FFT cross-correlation against one search
+/// area at a time, for areas the backend's own FFT search can't reach.
+pub(super) struct FftTemplate {
+    size: (u32, u32),
+    /// Power-of-two transform size covering the search area.
+    padded: (usize, usize),
+    /// Conjugated spectrum of the zero-mean template.
+    spectrum: Vec<Complex<f32>>,
+    variance: f64,
+}
+
+impl FftTemplate {
+    /// Prepares `patch_png` for search areas of up to `area_size`.
+    pub(super) fn new(patch_png: &[u8], area_size: (u32, u32)) -> anyhow::Result<Self> {
+        let patch = ::image::load_from_memory(patch_png)
+            .map_err(|e| anyhow::anyhow!("FindTarget decode failed: {e}"))?
+            .to_luma8();
+        let (tw, th) = patch.dimensions();
+        let padded = (
+            area_size.0.max(tw).next_power_of_two() as usize,
+            area_size.1.max(th).next_power_of_two() as usize,
+        );
+
+        let mean = patch.pixels().map(|p| p[0] as f64).sum::<f64>() / (tw * th).max(1) as f64;
+        let mut spectrum = vec![Complex::default(); padded.0 * padded.1];
+        let mut variance = 0.0;
+        for (x, y, p) in patch.enumerate_pixels() {
+            let centered = p[0] as f64 - mean;
+            variance += centered * centered;
+            spectrum[y as usize * padded.0 + x as usize] = Complex::new(centered as f32, 0.0);
+        }
+        fft_2d(&mut spectrum, padded, FftDirection::Forward);
+        for value in &mut spectrum {
+            *value = value.conj();
+        }
+        Ok(Self {
+            size: (tw, th),
+            padded,
+            spectrum,
+            variance,
+        })
+    }
+
+    /// Match centers at or above `precision`, best first, relative to the
+    /// area's top-left corner. Only local score peaks are reported, as with
+    /// `FilteredTemplate::find`.
+    pub(super) fn find(&self, area: &GrayImage, precision: f32) -> Vec<(u32, u32, f32)> {
+        let (w, h) = area.dimensions();
+        let (tw, th) = self.size;
+        if tw > w || th > h || w as usize > self.padded.0 || h as usize > self.padded.1 {
+            return Vec::new();
+        }
+
+        // Centering the area keeps the f32 transform's rounding small.
+        let mean = area.pixels().map(|p| p[0] as f64).sum::<f64>() / (w * h) as f64;
+        let mut cross = vec![Complex::default(); self.padded.0 * self.padded.1];
+        for (x, y, p) in area.enumerate_pixels() {
+            cross[y as usize * self.padded.0 + x as usize] = Complex::new((p[0] as f64 - mean) as f32, 0.0);
+        }
+        fft_2d(&mut cross, self.padded, FftDirection::Forward);
+        for (value, template) in cross.iter_mut().zip(&self.spectrum) {
+            *value *= template;
+        }
+        fft_2d(&mut cross, self.padded, FftDirection::Inverse);
+        let scale = (self.padded.0 * self.padded.1) as f64;
+
+        // Running sums give each offset's pixel variance.
+        let stride = w as usize + 1;
+        let mut sums = vec![(0.0f64, 0.0f64); stride * (h as usize + 1)];
+        for y in 0..h as usize {
+            for x in 0..w as usize {
+                let v = area.get_pixel(x as u32, y as u32)[0] as f64 - mean;
+                let (above, left, diagonal) = (sums[y * stride + x + 1], sums[(y + 1) * stride + x], sums[y * stride + x]);
+                sums[(y + 1) * stride + x + 1] = (
+                    v + above.0 + left.0 - diagonal.0,
+                    v * v + above.1 + left.1 - diagonal.1,
+                );
+            }
+        }
+        let count = (tw * th) as f64;
+        let (cols, rows) = (w - tw + 1, h - th + 1);
+        let mut scores = Vec::with_capacity((cols * rows) as usize);
+        for oy in 0..rows as usize {
+            for ox in 0..cols as usize {
+                let (x1, y1) = (ox + tw as usize, oy + th as usize);
+                let at = |x: usize, y: usize| sums[y * stride + x];
+                let sum = at(x1, y1).0 - at(ox, y1).0 - at(x1, oy).0 + at(ox, oy).0;
+                let sum_sq = at(x1, y1).1 - at(ox, y1).1 - at(x1, oy).1 + at(ox, oy).1;
+                let area_variance = sum_sq - sum * sum / count;
+                let score = if self.variance <= 1e-6 || area_variance <= 1e-6 {
+                    0.0
+                } else {
+                    cross[oy * self.padded.0 + ox].re as f64 / scale / (self.variance * area_variance).sqrt()
+                };
+                scores.push(score.min(1.0) as f32);
+            }
+        }
+
+        let mut hits = local_peaks(&scores, cols, precision);
+        for hit in &mut hits {
+            hit.0 += tw / 2;
+            hit.1 += th / 2;
+        }
+        hits
+    }
+}
+
+/// In-place 2D transform of a row-major `width` x `height` buffer.
+fn fft_2d(data: &mut [Complex<f32>], (width, height): (usize, usize), direction: FftDirection) {
+    let mut planner = FftPlanner::new();
+    planner.plan_fft(width, direction).process(data);
+
+    let mut columns = vec![Complex::default(); data.len()];
+    for (i, value) in data.iter().enumerate() {
+        columns[(i % width) * height + i / width] = *value;
+    }
+    planner.plan_fft(height, direction).process(&mut columns);
+    for (i, value) in columns.iter().enumerate() {
+        data[(i % height) * width + i / height] = *value;
+    }
+}
+
+/// Weighted zero-mean normalized cross-correlation of one template.
+struct Scorer {
+    size: (u32, u32),
+    /// Template minus its weighted mean, 0 where ignored.
+    centered: Vec<f64>,
+    weights: Vec<f64>,
+    count: f64,
+    variance: f64,
+}
+
+impl Scorer {
+    fn new(template: &GrayImage, weights: &[f32]) -> Self {
+        let weights: Vec<f64> = weights.iter().map(|&w| w as f64).collect();
+        let count = weights.iter().sum::<f64>().max(1.0);
+        let mean = template.pixels().zip(&weights).map(|(p, w)| p[0] as f64 * w).sum::<f64>() / count;
+        let centered: Vec<f64> = template.pixels().zip(&weights).map(|(p, w)| (p[0] as f64 - mean) * w).collect();
+        let variance = centered.iter().map(|t| t * t).sum();
+        Self {
+            size: template.dimensions(),
+            centered,
+            weights,
+            count,
+            variance,
+        }
+    }
+
+    fn score_at(&self, screen: &GrayImage, ox: u32, oy: u32) -> f32 {
+        let (tw, th) = self.size;
+        let (mut sum, mut sum_sq, mut cross) = (0.0f64, 0.0f64, 0.0f64);
+        for ty in 0..th {
+            let row = (ty * tw) as usize;
+            for tx in 0..tw {
+                let i = row + tx as usize;
+                let s = screen.get_pixel(ox + tx, oy + ty)[0] as f64;
+                let w = self.weights[i];
+                sum += w * s;
+                sum_sq += w * s * s;
+                cross += self.centered[i] * s;
+            }
+        }
+        let screen_variance = sum_sq - sum * sum / self.count;
+        if self.variance <= 1e-6 || screen_variance <= 1e-6 {
+            return 0.0;
+        }
+        (cross / (self.variance * screen_variance).sqrt()).min(1.0) as f32
+    }
+
+    /// Scores for every offset, row by row.
+    fn score_map(&self, screen: &GrayImage) -> Vec<f32> {
+        let (tw, th) = self.size;
+        let (cols, rows) = (screen.width() - tw + 1, screen.height() - th + 1);
+        let mut scores = Vec::with_capacity((cols * rows) as usize);
+        for oy in 0..rows {
+            for ox in 0..cols {
+                scores.push(self.score_at(screen, ox, oy));
+            }
+        }
+        scores
+    }
+}
+
+/// Offsets whose score is at least `min_score` and no lower than any of
+/// their eight neighbours, best first.
+fn local_peaks(scores: &[f32], cols: u32, min_score: f32) -> Vec<(u32, u32, f32)> {
+    let cols = cols as usize;
+    let rows = scores.len() / cols.max(1);
+    let mut peaks = Vec::new();
+    for y in 0..rows {
+        for x in 0..cols {
+            let score = scores[y * cols + x];
+            if score < min_score {
+                continue;
+            }
+            let is_peak = (y.saturating_sub(1)..=(y + 1).min(rows - 1))
+                .flat_map(|ny| (x.saturating_sub(1)..=(x + 1).min(cols - 1)).map(move |nx| (nx, ny)))
+                .all(|(nx, ny)| scores[ny * cols + nx] <= score);
+            if is_peak {
+                peaks.push((x as u32, y as u32, score));
+            }
+        }
+    }
+    peaks.sort_by(|a, b| b.2.total_cmp(&a.2));
+    peaks
+}
+
+/// Sobel gradient magnitude, 0 on the one-pixel border.
+fn edge_map(img: &GrayImage) -> GrayImage {
+    let (w, h) = img.dimensions();
+    let mut out = GrayImage::new(w, h);
+    if w < 3 || h < 3 {
+        return out;
+    }
+    let px = |x: u32, y: u32| img.get_pixel(x, y)[0] as i32;
+    for y in 1..h - 1 {
+        for x in 1..w - 1 {
+            let gx = px(x + 1, y - 1) + 2 * px(x + 1, y) + px(x + 1, y + 1)
+                - px(x - 1, y - 1) - 2 * px(x - 1, y) - px(x - 1, y + 1);
+            let gy = px(x - 1, y + 1) + 2 * px(x, y + 1) + px(x + 1, y + 1)
+                - px(x - 1, y - 1) - 2 * px(x, y - 1) - px(x + 1, y - 1);
+            out.put_pixel(x, y, ::image::Luma([((gx.abs() + gy.abs()) / 4).min(255) as u8]));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Two 10x10 matches, each reported as a cluster of neighbouring hits.
+    fn hits() -> Vec<(i32, i32, f32)> {
+        vec![
+            (50, 20, 0.95),
+            (51, 20, 0.93),
+            (10, 40, 0.91),
+            (11, 41, 0.97),
+            (90, 21, 0.92),
+        ]
+    }
+
+    fn pick(strategy: MatchStrategy, anchor: Option<(i32, i32)>) -> Option<(i32, i32)> {
+        select_match(hits(), strategy, anchor, (10, 10)).map(|(x, y, _)| (x, y))
+    }
+
+    #[test]
+    fn clusters_merge_into_their_best_hit() {
+        assert_eq!(pick(MatchStrategy::BestScore, None), Some((11, 41)));
+        assert_eq!(pick(MatchStrategy::Leftmost, None), Some((11, 41)));
+        assert_eq!(pick(MatchStrategy::Topmost, None), Some((50, 20)));
+    }
+
+    #[test]
+    fn nearest_anchor_falls_back_to_best_score() {
+        assert_eq!(pick(MatchStrategy::NearestAnchor, Some((85, 25))), Some((90, 21)));
+        assert_eq!(pick(MatchStrategy::NearestAnchor, None), Some((11, 41)));
+    }
+
+    #[test]
+    fn nth_counts_in_reading_order() {
+        // (50,20) and (90,21) share the top row; (11,41) is below them.
+        assert_eq!(pick(MatchStrategy::Nth(1), None), Some((50, 20)));
+        assert_eq!(pick(MatchStrategy::Nth(2), None), Some((90, 21)));
+        assert_eq!(pick(MatchStrategy::Nth(3), None), Some((11, 41)));
+        assert_eq!(pick(MatchStrategy::Nth(4), None), None);
+    }
+
+    /// Distinct non-flat pixels, so a template matches in one place only.
+    fn texture(w: u32, h: u32) -> GrayImage {
+        GrayImage::from_fn(w, h, |x, y| ::image::Luma([(40 + (x * 29 + y * 53 + x * y * 7) % 180) as u8]))
+    }
+
+    fn png(image: ::image::DynamicImage) -> Vec<u8> {
+        let mut png = Vec::new();
+        image
+            .write_to(&mut std::io::Cursor::new(&mut png), ::image::ImageFormat::Png)
+            .unwrap();
+        png
+    }
+
+    fn screen_with(patch: &GrayImage, left: u32, top: u32) -> GrayImage {
+        let mut screen = GrayImage::new(60, 40);
+        ::image::imageops::replace(&mut screen, patch, left as i64, top as i64);
+        screen
+    }
+
+    fn centers(hits: Vec<(u32, u32, f32)>) -> Vec<(u32, u32)> {
+        hits.into_iter().map(|(x, y, _)| (x, y)).collect()
+    }
+
+    #[test]
+    fn edge_matching_ignores_inverted_colors() {
+        let patch = texture(10, 10);
+        let mut inverted = patch.clone();
+        ::image::imageops::invert(&mut inverted);
+        let screen = screen_with(&inverted, 20, 12);
+        let patch_png = png(patch.into());
+
+        let edges = FilteredTemplate::new(&patch_png, MatchFilter::Edges).unwrap();
+        assert_eq!(centers(edges.find(&screen, None, 0.95)), [(25, 17)]);
+        let grayscale = FilteredTemplate::new(&patch_png, MatchFilter::Grayscale).unwrap();
+        assert!(grayscale.find(&screen, None, 0.95).is_empty());
+    }
+
+    #[test]
+    fn masked_matching_skips_transparent_pixels() {
+        let patch = texture(10, 10);
+        // The right half of the patch is transparent and differs on screen.
+        let rgba = ::image::RgbaImage::from_fn(10, 10, |x, y| {
+            let v = patch.get_pixel(x, y)[0];
+            ::image::Rgba([v, v, v, if x < 5 { 255 } else { 0 }])
+        });
+        let mut shown = patch.clone();
+        for (x, _, pixel) in shown.enumerate_pixels_mut() {
+            if x >= 5 {
+                pixel[0] = 255 - pixel[0] / 3;
+            }
+        }
+        let screen = screen_with(&shown, 8, 20);
+        let patch_png = png(rgba.into());
+
+        let masked = FilteredTemplate::new(&patch_png, MatchFilter::Masked).unwrap();
+        assert_eq!(centers(masked.find(&screen, None, 0.95)), [(13, 25)]);
+        let grayscale = FilteredTemplate::new(&patch_png, MatchFilter::Grayscale).unwrap();
+        assert!(grayscale.find(&screen, None, 0.95).is_empty());
+    }
+
+    #[test]
+    fn large_templates_are_refined_to_the_exact_spot() {
+        // 24 px takes the reduced-size pass first.
+        let patch = texture(24, 24);
+        let screen = screen_with(&patch, 31, 9);
+        let template = FilteredTemplate::new(&png(patch.into()), MatchFilter::Grayscale).unwrap();
+        assert_eq!(centers(template.find(&screen, None, 0.95)), [(43, 21)]);
+    }
+
+    #[test]
+    fn fft_matching_finds_the_same_spots() {
+        let patch = texture(10, 10);
+        let mut screen = screen_with(&patch, 7, 3);
+        ::image::imageops::replace(&mut screen, &patch, 41, 26);
+        let patch_png = png(patch.into());
+
+        let fft = FftTemplate::new(&patch_png, screen.dimensions()).unwrap();
+        let filtered = FilteredTemplate::new(&patch_png, MatchFilter::Grayscale).unwrap();
+        let mut found = centers(fft.find(&screen, 0.95));
+        found.sort();
+        assert_eq!(found, [(12, 8), (46, 31)]);
+        let mut expected = centers(filtered.find(&screen, None, 0.95));
+        expected.sort();
+        assert_eq!(found, expected);
+        for (fft_score, score) in fft.find(&screen, 0.5).iter().zip(filtered.find(&screen, None, 0.5)) {
+            assert!((fft_score.2 - score.2).abs() < 1e-3, "{fft_score:?} vs {score:?}");
+        }
+
+        // Areas past the prepared size find nothing.
+        let small = FftTemplate::new(&patch_png, (16, 16)).unwrap();
+        assert!(small.find(&screen, 0.95).is_empty());
+    }
+
+    #[test]
+    fn regions_limit_the_search_and_keep_screen_coordinates() {
+        let patch = texture(8, 8);
+        let screen = screen_with(&patch, 40, 4);
+        let template = FilteredTemplate::new(&png(patch.into()), MatchFilter::Grayscale).unwrap();
+        assert_eq!(centers(template.find(&screen, Some((30, 0, 30, 20)), 0.95)), [(44, 8)]);
+        assert!(template.find(&screen, Some((0, 0, 30, 40)), 0.95).is_empty());
+    }
+
+    #[test]
+    fn nothing_to_pick_from_no_hits() {
+        for strategy in MatchStrategy::all(1) {
+            assert_eq!(select_match(Vec::new(), strategy, None, (10, 10)), None);
+        }
+    }
+}
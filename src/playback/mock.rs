@@ -0,0 +1,326 @@
+// Deterministic in-memory backend for the playback tests.
+
+use super::*;
+
+use super::matching::FilteredTemplate;
+use ::image::GrayImage;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum MockAction {
+    Move { x: i32, y: i32 },
+    ButtonDown(MouseButton),
+    ButtonUp(MouseButton),
+    Scroll { dx: i32, dy: i32 },
+    KeyDown(String),
+    KeyUp(String),
+    TypeText(String),
+    PrepareTemplate { region: Option<(u32, u32, u32, u32)> },
+    FindTemplate { precision: f32, found: Option<(u32, u32)> },
+    ActivateWindow { id: u64 },
+    SaveScreenshot,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TimedMockAction {
+    pub at_ms: u64,
+    pub action: MockAction,
+}
+
+/// Records every emitted action against a virtual clock that only advances on
+/// `sleep`, so the same event list always yields the same action stream.
+///
+/// Template searches are served from a sequence of screenshots: each search
+/// consumes the next frame and the last frame repeats once the list is exhausted.
+pub(crate) struct MockBackend {
+    clock: Duration,
+    cursor: Option<(i32, i32)>,
+    /// Desktop area the screenshots cover; their top-left pixel sits at its origin.
+    bounds: ScreenLayout,
+    /// Connected monitors; just `bounds` unless `with_monitors` says otherwise.
+    monitors: Vec<ScreenLayout>,
+    screenshots: Vec<GrayImage>,
+    next_screenshot: usize,
+    template: Option<GrayImage>,
+    template_region: Option<(u32, u32, u32, u32)>,
+    filtered: Option<FilteredTemplate>,
+    /// Open windows, topmost first.
+    windows: Vec<WindowInfo>,
+    actions: Vec<TimedMockAction>,
+}
+
+impl MockBackend {
+    pub(crate) fn new(screen_size: (i32, i32)) -> Self {
+        Self {
+            clock: Duration::ZERO,
+            cursor: None,
+            bounds: ScreenLayout {
+                origin: (0, 0),
+                size: screen_size,
+            },
+            monitors: Vec::new(),
+            screenshots: Vec::new(),
+            next_screenshot: 0,
+            template: None,
+            template_region: None,
+            filtered: None,
+            windows: Vec::new(),
+            actions: Vec::new(),
+        }
+    }
+
+    pub(crate) fn with_cursor(mut self, pos: (i32, i32)) -> Self {
+        self.cursor = Some(pos);
+        self
+    }
+
+    /// Monitors in desktop coordinates, primary first. The desktop becomes
+    /// their bounding box, so its origin can be negative.
+    pub(crate) fn with_monitors(mut self, monitors: Vec<ScreenLayout>) -> Self {
+        if let Some(first) = monitors.first() {
+            let (mut left, mut top) = first.origin;
+            let (mut right, mut bottom) = (left + first.size.0, top + first.size.1);
+            for monitor in &monitors {
+                left = left.min(monitor.origin.0);
+                top = top.min(monitor.origin.1);
+                right = right.max(monitor.origin.0 + monitor.size.0);
+                bottom = bottom.max(monitor.origin.1 + monitor.size.1);
+            }
+            self.bounds = ScreenLayout {
+                origin: (left, top),
+                size: (right - left, bottom - top),
+            };
+        }
+        self.monitors = monitors;
+        self
+    }
+
+    pub(crate) fn push_screenshot(&mut self, screenshot: GrayImage) {
+        if self.screenshots.is_empty() {
+            self.bounds.size = (screenshot.width() as i32, screenshot.height() as i32);
+        }
+        self.screenshots.push(screenshot);
+    }
+
+    pub(crate) fn push_screenshot_file(&mut self, path: &str) -> anyhow::Result<()> {
+        let screenshot = ::image::open(path)
+            .map_err(|e| anyhow::anyhow!("Mock screenshot {path} failed to load: {e}"))?
+            .to_luma8();
+        self.push_screenshot(screenshot);
+        Ok(())
+    }
+
+    pub(crate) fn push_window(&mut self, window: WindowInfo) {
+        self.windows.push(window);
+    }
+
+    pub(crate) fn actions(&self) -> &[TimedMockAction] {
+        &self.actions
+    }
+
+    pub(crate) fn into_actions(self) -> Vec<TimedMockAction> {
+        self.actions
+    }
+
+    fn record(&mut self, action: MockAction) {
+        self.actions.push(TimedMockAction {
+            at_ms: self.clock.as_millis() as u64,
+            action,
+        });
+    }
+
+    fn current_screenshot(&mut self) -> Option<&GrayImage> {
+        if self.screenshots.is_empty() {
+            return None;
+        }
+        let index = self.next_screenshot.min(self.screenshots.len() - 1);
+        self.next_screenshot = self.next_screenshot.saturating_add(1);
+        self.screenshots.get(index)
+    }
+}
+
+impl InputBackend for MockBackend {
+    fn cursor_pos(&mut self) -> Option<(i32, i32)> {
+        self.cursor
+    }
+
+    fn move_mouse_to(&mut self, x: i32, y: i32) -> anyhow::Result<()> {
+        self.cursor = Some((x, y));
+        self.record(MockAction::Move { x, y });
+        Ok(())
+    }
+
+    fn button_down(&mut self, button: MouseButton) -> anyhow::Result<()> {
+        self.record(MockAction::ButtonDown(button));
+        Ok(())
+    }
+
+    fn button_up(&mut self, button: MouseButton) -> anyhow::Result<()> {
+        self.record(MockAction::ButtonUp(button));
+        Ok(())
+    }
+
+    fn scroll(&mut self, dx: i32, dy: i32) -> anyhow::Result<()> {
+        self.record(MockAction::Scroll { dx, dy });
+        Ok(())
+    }
+
+    fn screen_bounds(&mut self) -> ScreenLayout {
+        self.bounds
+    }
+
+    fn monitors(&mut self) -> Vec<ScreenLayout> {
+        if self.monitors.is_empty() {
+            return vec![self.bounds];
+        }
+        self.monitors.clone()
+    }
+
+    fn key_down(&mut self, key: &str) -> anyhow::Result<()> {
+        self.record(MockAction::KeyDown(key.to_string()));
+        Ok(())
+    }
+
+    fn key_up(&mut self, key: &str) -> anyhow::Result<()> {
+        self.record(MockAction::KeyUp(key.to_string()));
+        Ok(())
+    }
+
+    fn type_text(&mut self, text: &str) -> anyhow::Result<()> {
+        self.record(MockAction::TypeText(text.to_string()));
+        Ok(())
+    }
+
+    fn prepare_template(
+        &mut self,
+        patch_png: &[u8],
+        region: Option<(u32, u32, u32, u32)>,
+        filter: MatchFilter,
+    ) -> anyhow::Result<()> {
+        let template = ::image::load_from_memory(patch_png)?.to_luma8();
+        self.template = Some(template);
+        self.filtered = match filter {
+            MatchFilter::Grayscale => None,
+            filter => Some(FilteredTemplate::new(patch_png, filter)?),
+        };
+        self.template_region = region;
+        self.record(MockAction::PrepareTemplate { region });
+        Ok(())
+    }
+
+    fn find_template(&mut self, precision: f32) -> anyhow::Result<Option<Vec<(u32, u32, f32)>>> {
+        let Some(template) = self.template.take() else {
+            anyhow::bail!("find_template called before prepare_template");
+        };
+
+        let region = self.template_region;
+        let filtered = self.filtered.take();
+        let matches = self
+            .current_screenshot()
+            .map(|screen| match &filtered {
+                Some(filtered) => filtered.find(screen, region, precision),
+                None => match_template(screen, &template, region, precision),
+            })
+            .unwrap_or_default();
+        self.filtered = filtered;
+        self.template = Some(template);
+
+        let found = matches.first().map(|&(x, y, _)| (x, y));
+        self.record(MockAction::FindTemplate { precision, found });
+
+        Ok((!matches.is_empty()).then_some(matches))
+    }
+
+    fn activate_window(&mut self, title: &str, class: &str) -> anyhow::Result<Option<WindowInfo>> {
+        let matcher = WindowMatcher::new(title, class).map_err(anyhow::Error::msg)?;
+        let Some(index) = self.windows.iter().position(|window| matcher.matches(window)) else {
+            return Ok(None);
+        };
+        let window = self.windows.remove(index);
+        self.windows.insert(0, window.clone());
+        self.record(MockAction::ActivateWindow { id: window.id });
+        Ok(Some(window))
+    }
+
+    fn save_screenshot(&mut self, path: &Path) -> anyhow::Result<()> {
+        // The frame the last search saw, without consuming a new one
+        let Some(index) = self.next_screenshot.checked_sub(1).or((!self.screenshots.is_empty()).then_some(0)) else {
+            anyhow::bail!("Mock backend has no screenshot to save");
+        };
+        let screenshot = &self.screenshots[index.min(self.screenshots.len() - 1)];
+        screenshot.save(path)?;
+        self.record(MockAction::SaveScreenshot);
+        Ok(())
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        self.clock += duration;
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.clock
+    }
+}
+
+/// Brute-force zero-mean normalized cross-correlation, reporting match centers
+/// like `RustAutoGui::find_image_on_screen`. Intended for small test screenshots.
+fn match_template(
+    screen: &GrayImage,
+    template: &GrayImage,
+    region: Option<(u32, u32, u32, u32)>,
+    precision: f32,
+) -> Vec<(u32, u32, f32)> {
+    let (tw, th) = template.dimensions();
+    let (rx, ry, rw, rh) = region.unwrap_or((0, 0, screen.width(), screen.height()));
+    let rw = rw.min(screen.width().saturating_sub(rx));
+    let rh = rh.min(screen.height().saturating_sub(ry));
+    if tw == 0 || th == 0 || tw > rw || th > rh {
+        return Vec::new();
+    }
+
+    let n = (tw * th) as f64;
+    let t_mean = template.pixels().map(|p| p[0] as f64).sum::<f64>() / n;
+    let t_var = template
+        .pixels()
+        .map(|p| (p[0] as f64 - t_mean).powi(2))
+        .sum::<f64>();
+
+    let mut out = Vec::new();
+    for oy in ry..=ry + rh - th {
+        for ox in rx..=rx + rw - tw {
+            let mut s_sum = 0.0;
+            for ty in 0..th {
+                for tx in 0..tw {
+                    s_sum += screen.get_pixel(ox + tx, oy + ty)[0] as f64;
+                }
+            }
+            let s_mean = s_sum / n;
+
+            let (mut cross, mut s_var) = (0.0, 0.0);
+            for ty in 0..th {
+                for tx in 0..tw {
+                    let s = screen.get_pixel(ox + tx, oy + ty)[0] as f64 - s_mean;
+                    let t = template.get_pixel(tx, ty)[0] as f64 - t_mean;
+                    cross += s * t;
+                    s_var += s * s;
+                }
+            }
+
+            let corr = if t_var == 0.0 || s_var == 0.0 {
+                if t_var == s_var && (s_mean - t_mean).abs() < 0.5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            } else {
+                cross / (t_var * s_var).sqrt()
+            } as f32;
+
+            if corr >= precision {
+                out.push((ox + tw / 2, oy + th / 2, corr));
+            }
+        }
+    }
+
+    out.sort_by(|a, b| b.2.total_cmp(&a.2));
+    out
+}
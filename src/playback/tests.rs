@@ -0,0 +1,711 @@
+use super::mock::{MockAction, MockBackend, TimedMockAction};
+use super::*;
+use crate::model::test_support::{bind_window, if_image, label, layout, row, text, wait_for_image};
+
+use ::image::{GrayImage, Luma};
+
+const BLOCK_SIZE: u32 = 8;
+
+/// A 40x30 screen, blank except for a textured block with its top-left corner
+/// at `at`.
+fn screen_with_block(at: Option<(u32, u32)>) -> GrayImage {
+    let mut screen = GrayImage::from_pixel(40, 30, Luma([0]));
+    if let Some((left, top)) = at {
+        ::image::imageops::replace(&mut screen, &block(), left as i64, top as i64);
+    }
+    screen
+}
+
+fn block() -> GrayImage {
+    GrayImage::from_fn(BLOCK_SIZE, BLOCK_SIZE, |x, y| Luma([(40 + (x * 29 + y * 53 + x * y * 7) % 180) as u8]))
+}
+
+/// `block()` as a base64 PNG, like a recorded patch.
+fn block_patch() -> String {
+    let mut png = Vec::new();
+    block()
+        .write_to(&mut std::io::Cursor::new(&mut png), ::image::ImageFormat::Png)
+        .unwrap();
+    general_purpose::STANDARD.encode(png)
+}
+
+/// When each `TypeText` action ran, in virtual milliseconds.
+fn typed_at(backend: &MockBackend) -> Vec<(String, u64)> {
+    backend
+        .actions()
+        .iter()
+        .filter_map(|timed| match &timed.action {
+            MockAction::TypeText(text) => Some((text.clone(), timed.at_ms)),
+            _ => None,
+        })
+        .collect()
+}
+
+fn find_target(precision: f32) -> RecordedEvent {
+    row(RecordedEventKind::FindTarget {
+        patch_png_base64: block_patch(),
+        patch_size: BLOCK_SIZE,
+        precision,
+        timeout_ms: 0,
+        search_anchor: SearchAnchor::RecordedClick,
+        search_region_size: None,
+        match_strategy: MatchStrategy::BestScore,
+        target_offset: (0, 0),
+        scale_sweep: None,
+        match_filter: MatchFilter::Grayscale,
+        search_monitor: MonitorTarget::All,
+    })
+}
+
+fn play(backend: &mut MockBackend, events: Vec<RecordedEvent>, options: PlaybackOptions) -> PlaybackReport {
+    playback_with_backend(
+        backend,
+        events,
+        options,
+        Arc::default(),
+        Arc::default(),
+        Arc::new(AtomicUsize::new(usize::MAX)),
+    )
+}
+
+/// Text typed during the run, one entry per `TypeText` action.
+fn typed(backend: &MockBackend) -> Vec<String> {
+    typed_at(backend).into_iter().map(|(text, _)| text).collect()
+}
+
+#[test]
+fn golden_action_stream() {
+    let screen_path = std::env::temp_dir().join(format!("mouse_recorder_{}_golden.png", std::process::id()));
+    screen_with_block(Some((20, 10))).save(&screen_path).unwrap();
+    let mut backend = MockBackend::new((40, 30)).with_cursor((0, 0));
+    backend.push_screenshot_file(screen_path.to_str().unwrap()).unwrap();
+    std::fs::remove_file(&screen_path).unwrap();
+
+    let meta = ClickListMeta {
+        wait_ms: 10,
+        click_speed_ms: 30,
+        mouse_move_speed_ms: 5,
+        ..ClickListMeta::default()
+    };
+    let events = vec![
+        RecordedEvent {
+            pos: Some((4, 2)),
+            click_meta: Some(meta.clone()),
+            ..row(RecordedEventKind::LeftClick { patch_png_base64: None })
+        },
+        row(RecordedEventKind::Wait { ms: 100 }),
+        find_target(0.9),
+        RecordedEvent {
+            pos: Some((24, 14)),
+            click_meta: Some(ClickListMeta {
+                right_mode: ClickEdgeMode::Down,
+                ..meta
+            }),
+            ..row(RecordedEventKind::RightDown { patch_png_base64: None })
+        },
+    ];
+
+    let report = play(&mut backend, events, PlaybackOptions::default());
+
+    assert_eq!(report.error, None);
+
+    let at = |at_ms, action| TimedMockAction { at_ms, action };
+    assert_eq!(
+        backend.into_actions(),
+        vec![
+            at(10, MockAction::Move { x: 4, y: 2 }),
+            at(15, MockAction::ButtonDown(MouseButton::Left)),
+            at(45, MockAction::ButtonUp(MouseButton::Left)),
+            at(145, MockAction::PrepareTemplate { region: None }),
+            at(145, MockAction::FindTemplate { precision: 0.9, found: Some((24, 14)) }),
+            at(145, MockAction::Move { x: 10, y: 6 }),
+            at(153, MockAction::Move { x: 17, y: 10 }),
+            at(161, MockAction::Move { x: 24, y: 14 }),
+            at(179, MockAction::ButtonDown(MouseButton::Right)),
+        ]
+    );
+}
+
+#[test]
+fn speed_scales_row_waits_and_faithful_gaps() {
+    let timed_text = |ms_from_start, text: &str| RecordedEvent {
+        ms_from_start,
+        click_meta: Some(ClickListMeta {
+            wait_ms: 100,
+            ..ClickListMeta::default()
+        }),
+        ..row(RecordedEventKind::TypeText { text: text.to_string() })
+    };
+    let events = vec![
+        timed_text(0, "a"),
+        RecordedEvent {
+            ms_from_start: 300,
+            ..row(RecordedEventKind::Wait { ms: 200 })
+        },
+        timed_text(1000, "b"),
+    ];
+
+    // Row waits and wait rows shrink with speed; faithful timing replays the
+    // recorded gaps instead, so "b" lands at its recorded time over the speed.
+    for (speed, faithful_timing, a_ms, b_ms) in [
+        (1.0, false, 100, 400),
+        (2.0, false, 50, 200),
+        (0.5, false, 200, 800),
+        (1.0, true, 0, 1000),
+        (2.0, true, 0, 500),
+        (0.5, true, 0, 2000),
+    ] {
+        let mut backend = MockBackend::new((100, 100));
+        let options = PlaybackOptions {
+            speed,
+            faithful_timing,
+            ..PlaybackOptions::default()
+        };
+        let report = play(&mut backend, events.clone(), options);
+
+        assert_eq!(report.error, None);
+        assert_eq!(
+            typed_at(&backend),
+            [("a".to_string(), a_ms), ("b".to_string(), b_ms)],
+            "speed {speed}, faithful {faithful_timing}"
+        );
+    }
+}
+
+#[test]
+fn template_search_misses_on_a_blank_screen() {
+    let mut backend = MockBackend::new((40, 30));
+    backend.push_screenshot(screen_with_block(None));
+
+    let report = play(&mut backend, vec![find_target(0.9)], PlaybackOptions::default());
+
+    let error = report.error.unwrap();
+    assert!(error.contains("timed out"), "{error}");
+    assert_eq!(
+        backend.actions(),
+        [
+            TimedMockAction { at_ms: 0, action: MockAction::PrepareTemplate { region: None } },
+            TimedMockAction { at_ms: 0, action: MockAction::FindTemplate { precision: 0.9, found: None } },
+            // The best-candidate scan after the timeout
+            TimedMockAction { at_ms: 50, action: MockAction::FindTemplate { precision: CANDIDATE_FLOOR, found: None } },
+        ]
+    );
+}
+
+#[test]
+fn key_rows_press_modifiers_around_the_key() {
+    let modifiers = KeyModifiers {
+        ctrl: true,
+        shift: true,
+        ..KeyModifiers::default()
+    };
+    let key = "s".to_string();
+    let events = vec![
+        row(RecordedEventKind::KeyDown { key: key.clone(), modifiers }),
+        row(RecordedEventKind::KeyUp { key, modifiers }),
+        text("hi"),
+    ];
+    let mut backend = MockBackend::new((100, 100));
+
+    let report = play(&mut backend, events, PlaybackOptions::default());
+
+    assert_eq!(report.error, None);
+
+    let actions: Vec<_> = backend.into_actions().into_iter().map(|timed| timed.action).collect();
+    let key = |name: &str| name.to_string();
+    assert_eq!(
+        actions,
+        [
+            MockAction::KeyDown(key("ctrl")),
+            MockAction::KeyDown(key("shift")),
+            MockAction::KeyDown(key("s")),
+            MockAction::KeyUp(key("s")),
+            MockAction::KeyUp(key("shift")),
+            MockAction::KeyUp(key("ctrl")),
+            MockAction::TypeText(key("hi")),
+        ]
+    );
+}
+
+#[test]
+fn scroll_rows_move_to_their_position_first() {
+    let events = vec![RecordedEvent {
+        pos: Some((30, 40)),
+        ..row(RecordedEventKind::Scroll { dx: 0, dy: -2 })
+    }];
+    let mut backend = MockBackend::new((100, 100)).with_cursor((30, 0));
+
+    let report = play(&mut backend, events, PlaybackOptions::default());
+
+    assert_eq!(report.error, None);
+
+    let actions: Vec<_> = backend.into_actions().into_iter().map(|timed| timed.action).collect();
+    assert_eq!(actions.last(), Some(&MockAction::Scroll { dx: 0, dy: -2 }));
+    assert_eq!(actions[actions.len() - 2], MockAction::Move { x: 30, y: 40 });
+}
+
+#[test]
+fn repeat_blocks_run_their_rows_count_times() {
+    let mut backend = MockBackend::new((100, 100));
+    let events = vec![
+        row(RecordedEventKind::RepeatStart { count: 2 }),
+        text("a"),
+        row(RecordedEventKind::RepeatStart { count: 2 }),
+        text("b"),
+        row(RecordedEventKind::RepeatEnd),
+        row(RecordedEventKind::RepeatEnd),
+        row(RecordedEventKind::RepeatStart { count: 0 }),
+        text("never"),
+        row(RecordedEventKind::RepeatEnd),
+        text("done"),
+    ];
+
+    let report = play(&mut backend, events, PlaybackOptions::default());
+
+    assert_eq!(report.error, None);
+
+    assert_eq!(typed(&backend), ["a", "b", "b", "a", "b", "b", "done"]);
+}
+
+/// Waits until the playback thread has reached row `index`.
+fn wait_for_progress(progress: &AtomicUsize, index: usize) {
+    let deadline = std::time::Instant::now() + Duration::from_secs(5);
+    while progress.load(Ordering::Relaxed) != index {
+        assert!(std::time::Instant::now() < deadline, "playback never reached row {index}");
+        std::thread::yield_now();
+    }
+}
+
+#[test]
+fn stepping_a_paused_run_plays_one_row_at_a_time() {
+    let events = vec![text("a"), text("b"), text("c")];
+    let cancel = Arc::new(AtomicBool::new(false));
+    let pause = Arc::new(PlaybackPause::default());
+    let progress = Arc::new(AtomicUsize::new(usize::MAX));
+    pause.step();
+
+    let worker = {
+        let (cancel, pause, progress) = (cancel.clone(), pause.clone(), progress.clone());
+        std::thread::spawn(move || {
+            let mut backend = MockBackend::new((100, 100));
+            let report = playback_with_backend(&mut backend, events, PlaybackOptions::default(), cancel, pause, progress);
+            (backend, report)
+        })
+    };
+
+    // Each step runs a single row and leaves the run paused before the next.
+    wait_for_progress(&progress, 1);
+    assert!(pause.is_paused());
+    pause.step();
+    wait_for_progress(&progress, 2);
+    assert!(pause.is_paused());
+    cancel.store(true, Ordering::Relaxed);
+
+    let (backend, report) = worker.join().unwrap();
+    assert_eq!(typed(&backend), ["a", "b"]);
+    assert_eq!(report.rows.iter().map(|row| row.row).collect::<Vec<_>>(), [0, 1]);
+    assert_eq!(report.error.as_deref(), Some("Cancelled"));
+}
+
+#[test]
+fn if_image_runs_the_branch_matching_the_screen() {
+    let events = vec![
+        if_image(block_patch()),
+        text("found"),
+        row(RecordedEventKind::Else),
+        text("missing"),
+        row(RecordedEventKind::EndIf),
+        text("after"),
+    ];
+
+    let mut backend = MockBackend::new((40, 30));
+    backend.push_screenshot(screen_with_block(Some((10, 5))));
+    let report = play(&mut backend, events.clone(), PlaybackOptions::default());
+    assert_eq!(report.error, None);
+    assert_eq!(typed(&backend), ["found", "after"]);
+
+    let mut backend = MockBackend::new((40, 30));
+    backend.push_screenshot(screen_with_block(None));
+    let report = play(&mut backend, events, PlaybackOptions::default());
+    assert_eq!(report.error, None);
+    assert_eq!(typed(&backend), ["missing", "after"]);
+}
+
+#[test]
+fn image_waits_poll_until_the_screen_changes() {
+    let mut backend = MockBackend::new((40, 30));
+    backend.push_screenshot(screen_with_block(None));
+    backend.push_screenshot(screen_with_block(None));
+    backend.push_screenshot(screen_with_block(Some((3, 3))));
+    backend.push_screenshot(screen_with_block(None));
+    let events = vec![
+        wait_for_image(block_patch(), false, 1000, OnTimeout::Fail),
+        text("shown"),
+        wait_for_image(block_patch(), true, 1000, OnTimeout::Fail),
+        text("gone"),
+    ];
+
+    let report = play(&mut backend, events, PlaybackOptions::default());
+
+    assert_eq!(report.error, None);
+
+    assert_eq!(typed_at(&backend), [("shown".to_string(), 200), ("gone".to_string(), 200)]);
+}
+
+#[test]
+fn image_wait_timeouts_follow_their_policy() {
+    let events = |on_timeout| {
+        vec![
+            wait_for_image(block_patch(), false, 150, on_timeout),
+            text("next"),
+            label("end"),
+            text("end"),
+        ]
+    };
+
+    let mut backend = MockBackend::new((40, 30));
+    backend.push_screenshot(screen_with_block(None));
+    let report = play(&mut backend, events(OnTimeout::Fail), PlaybackOptions::default());
+    assert!(report.error.is_some());
+    assert!(typed(&backend).is_empty());
+
+    let mut backend = MockBackend::new((40, 30));
+    backend.push_screenshot(screen_with_block(None));
+    let report = play(&mut backend, events(OnTimeout::Continue), PlaybackOptions::default());
+    assert_eq!(report.error, None);
+    assert_eq!(typed(&backend), ["next", "end"]);
+
+    let mut backend = MockBackend::new((40, 30));
+    backend.push_screenshot(screen_with_block(None));
+    let report = play(&mut backend, events(OnTimeout::GotoLabel("end".to_string())), PlaybackOptions::default());
+    assert_eq!(report.error, None);
+    assert_eq!(typed(&backend), ["end"]);
+}
+
+/// An image wait that fails on a blank screen, with its own failure policy.
+fn failing_row(on_error: Option<OnError>) -> RecordedEvent {
+    RecordedEvent {
+        on_error,
+        ..wait_for_image(block_patch(), false, 0, OnTimeout::Fail)
+    }
+}
+
+#[test]
+fn retries_back_off_before_running_the_row_again() {
+    let mut backend = MockBackend::new((40, 30));
+    for _ in 0..4 {
+        backend.push_screenshot(screen_with_block(None));
+    }
+    backend.push_screenshot(screen_with_block(Some((3, 3))));
+    let events = vec![
+        failing_row(Some(OnError::Retry {
+            times: 2,
+            backoff_ms: 50,
+        })),
+        text("next"),
+    ];
+
+    let report = play(&mut backend, events, PlaybackOptions::default());
+
+    assert_eq!(report.error, None);
+    assert_eq!(report.rows[0].retries, 1);
+    assert_eq!(report.rows[0].outcome, RowOutcome::Passed);
+    // Two polls and the closest-candidate scan of the first attempt, the
+    // backoff, then two more polls
+    assert_eq!(typed_at(&backend), [("next".to_string(), 250)]);
+}
+
+#[test]
+fn row_policy_overrides_the_run_default() {
+    let mut backend = MockBackend::new((40, 30));
+    backend.push_screenshot(screen_with_block(None));
+    let events = vec![
+        failing_row(None),
+        text("a"),
+        failing_row(Some(OnError::GotoLabel("end".to_string()))),
+        text("b"),
+        label("end"),
+        failing_row(Some(OnError::Abort)),
+        text("c"),
+    ];
+    let options = PlaybackOptions {
+        on_error: OnError::Skip,
+        ..PlaybackOptions::default()
+    };
+
+    let report = play(&mut backend, events, options);
+
+    assert!(report.error.is_some());
+    assert_eq!(typed(&backend), ["a"]);
+    let outcomes: Vec<_> = report.rows.iter().map(|row| (row.row, row.outcome)).collect();
+    assert_eq!(
+        outcomes,
+        [
+            (0, RowOutcome::Skipped),
+            (1, RowOutcome::Passed),
+            (2, RowOutcome::Skipped),
+            (5, RowOutcome::Failed),
+        ]
+    );
+}
+
+#[test]
+fn failure_captures_are_named_after_the_editor_row() {
+    let dir = std::env::temp_dir().join(format!("mouse_recorder_{}_captures", std::process::id()));
+    let mut backend = MockBackend::new((40, 30));
+    backend.push_screenshot(screen_with_block(None));
+    let options = PlaybackOptions {
+        capture_dir: Some(dir.clone()),
+        // Played row 1 is editor row 7, e.g. after MOVES groups were merged
+        source_rows: vec![0, 7],
+        ..PlaybackOptions::default()
+    };
+
+    let report = play(&mut backend, vec![text("a"), failing_row(None)], options);
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    let folder = report.rows[1].failure_capture.clone().unwrap();
+    assert!(folder.ends_with("-row7"), "{folder}");
+}
+
+#[test]
+fn missed_searches_report_the_best_candidate_above_the_floor() {
+    let mut smudged = screen_with_block(Some((10, 5)));
+    for x in 10..14 {
+        smudged.put_pixel(x, 7, Luma([255]));
+    }
+    let mut backend = MockBackend::new((40, 30));
+    backend.push_screenshot(smudged);
+    let report = play(&mut backend, vec![find_target(0.99)], PlaybackOptions::default());
+    let error = report.rows[0].error.clone().unwrap();
+    assert!(error.contains("best match") && error.contains("at (14,9), needs 99.0%"), "{error}");
+
+    let mut backend = MockBackend::new((40, 30));
+    backend.push_screenshot(screen_with_block(None));
+    let report = play(&mut backend, vec![find_target(0.99)], PlaybackOptions::default());
+    let error = report.rows[0].error.clone().unwrap();
+    assert!(error.ends_with("nothing on screen scores 50% or more"), "{error}");
+}
+
+/// Where the cursor ended up.
+fn last_move(backend: &MockBackend) -> Option<(i32, i32)> {
+    backend.actions().iter().rev().find_map(|timed| match timed.action {
+        MockAction::Move { x, y } => Some((x, y)),
+        _ => None,
+    })
+}
+
+#[test]
+fn find_target_moves_to_the_offset_from_the_match_center() {
+    let mut event = find_target(0.9);
+    if let RecordedEventKind::FindTarget { target_offset, .. } = &mut event.kind {
+        *target_offset = (3, -2);
+    }
+    let mut backend = MockBackend::new((40, 30)).with_cursor((0, 0));
+    backend.push_screenshot(screen_with_block(Some((10, 5))));
+
+    let report = play(&mut backend, vec![event], PlaybackOptions::default());
+
+    assert_eq!(report.error, None);
+    // The block's center is (14,9).
+    assert_eq!(last_move(&backend), Some((17, 7)));
+}
+
+#[test]
+fn scale_sweeps_find_resized_templates_and_remember_the_scale() {
+    let doubled = ::image::imageops::resize(
+        &block(),
+        2 * BLOCK_SIZE,
+        2 * BLOCK_SIZE,
+        ::image::imageops::FilterType::Triangle,
+    );
+    let mut screen = screen_with_block(None);
+    ::image::imageops::replace(&mut screen, &doubled, 12, 6);
+    let mut event = find_target(0.9);
+    if let RecordedEventKind::FindTarget { scale_sweep, .. } = &mut event.kind {
+        *scale_sweep = Some(ScaleSweep {
+            min: 1.0,
+            max: 2.0,
+            step: 0.5,
+        });
+    }
+    let mut backend = MockBackend::new((40, 30)).with_cursor((0, 0));
+    backend.push_screenshot(screen);
+
+    let report = play(&mut backend, vec![event.clone(), event], PlaybackOptions::default());
+
+    assert_eq!(report.error, None);
+    assert_eq!(last_move(&backend), Some((20, 14)));
+    assert_eq!(report.rows[0].match_scale, Some(2.0));
+    // The second search starts with the scale that won the first.
+    let found: Vec<bool> = backend
+        .actions()
+        .iter()
+        .filter_map(|timed| match timed.action {
+            MockAction::FindTemplate { found, .. } => Some(found.is_some()),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(found, [false, false, true, true]);
+}
+
+#[test]
+fn remaps_apply_to_rows_moves_and_paths() {
+    let events = vec![
+        row(RecordedEventKind::Move { x: 100, y: 50 }),
+        row(RecordedEventKind::Moves {
+            points: vec![(0, 0), (200, 100)],
+        }),
+    ];
+    let options = PlaybackOptions {
+        remap: Some(ScreenRemap {
+            from: ScreenLayout {
+                origin: (0, 0),
+                size: (200, 100),
+            },
+            to: ScreenLayout {
+                origin: (0, 0),
+                size: (400, 300),
+            },
+            mode: RemapMode::Scale,
+            offset: (0, 0),
+        }),
+        ..PlaybackOptions::default()
+    };
+    let mut backend = MockBackend::new((400, 300)).with_cursor((0, 0));
+
+    let report = play(&mut backend, events, options);
+
+    assert_eq!(report.error, None);
+    let moves: Vec<_> = backend
+        .actions()
+        .iter()
+        .filter_map(|timed| match timed.action {
+            MockAction::Move { x, y } => Some((x, y)),
+            _ => None,
+        })
+        .collect();
+    assert!(moves.contains(&(200, 150)), "{moves:?}");
+    assert_eq!(moves.last(), Some(&(400, 300)));
+}
+
+fn window(id: u64, title: &str, origin: (i32, i32)) -> WindowInfo {
+    WindowInfo {
+        id,
+        title: title.to_string(),
+        class: "App".to_string(),
+        origin,
+        size: (200, 100),
+    }
+}
+
+#[test]
+fn window_blocks_follow_the_window_and_skip_the_remap() {
+    let mut backend = MockBackend::new((800, 600)).with_cursor((0, 0));
+    backend.push_window(window(1, "Terminal", (0, 0)));
+    backend.push_window(window(2, "Editor - notes.txt", (300, 200)));
+    let events = vec![
+        bind_window("^Editor"),
+        row(RecordedEventKind::Move { x: 10, y: 20 }),
+        row(RecordedEventKind::EndWindow),
+        row(RecordedEventKind::Move { x: 10, y: 20 }),
+    ];
+    let options = PlaybackOptions {
+        remap: Some(ScreenRemap {
+            from: ScreenLayout {
+                origin: (0, 0),
+                size: (800, 600),
+            },
+            to: ScreenLayout {
+                origin: (0, 0),
+                size: (800, 600),
+            },
+            mode: RemapMode::Offset,
+            offset: (1, 1),
+        }),
+        ..PlaybackOptions::default()
+    };
+
+    let report = play(&mut backend, events, options);
+
+    assert_eq!(report.error, None);
+    let actions: Vec<_> = backend.into_actions().into_iter().map(|timed| timed.action).collect();
+    assert_eq!(actions[0], MockAction::ActivateWindow { id: 2 });
+    assert!(actions.contains(&MockAction::Move { x: 310, y: 220 }), "{actions:?}");
+    assert_eq!(actions.last(), Some(&MockAction::Move { x: 11, y: 21 }));
+}
+
+#[test]
+fn window_blocks_fail_without_a_matching_window() {
+    let mut backend = MockBackend::new((800, 600));
+    backend.push_window(window(1, "Terminal", (0, 0)));
+    let events = vec![bind_window("^Editor"), text("a"), row(RecordedEventKind::EndWindow)];
+
+    let report = play(&mut backend, events, PlaybackOptions::default());
+
+    assert_eq!(report.error.as_deref(), Some("No open window matches title ~ \"^Editor\""));
+    assert!(typed(&backend).is_empty());
+}
+
+#[test]
+fn search_regions_stay_inside_their_area() {
+    let monitor = layout((-1280, 0), (1280, 1024));
+    // Centered on the anchor, at least 100 px
+    assert_eq!(
+        compute_region_around_point(monitor, (-600, 500), 40),
+        Some(layout((-650, 450), (100, 100)))
+    );
+    // Pushed back inside from an anchor off the area
+    assert_eq!(
+        compute_region_around_point(monitor, (200, -50), 300),
+        Some(layout((-300, 0), (300, 300)))
+    );
+    // Never larger than the area
+    assert_eq!(
+        compute_region_around_point(layout((0, 0), (80, 60)), (10, 10), 200),
+        Some(layout((0, 0), (80, 60)))
+    );
+    assert_eq!(compute_region_around_point(layout((0, 0), (0, 60)), (0, 0), 200), None);
+}
+
+#[test]
+fn capture_regions_are_clipped_and_relative_to_the_bounds() {
+    let bounds = layout((-1280, 0), (3200, 1080));
+    assert_eq!(capture_region(bounds, layout((-1280, 0), (1280, 1024))), Some((0, 0, 1280, 1024)));
+    assert_eq!(capture_region(bounds, layout((1800, 1000), (200, 200))), Some((3080, 1000, 120, 80)));
+    assert_eq!(capture_region(bounds, layout((1920, 0), (100, 100))), None);
+}
+
+#[test]
+fn monitor_searches_only_look_at_that_monitor() {
+    // Monitor 2 sits left of the primary, so its pixels start at x = -40.
+    let monitors = vec![layout((0, 0), (40, 30)), layout((-40, 0), (40, 30))];
+    let mut screen = GrayImage::from_pixel(80, 30, Luma([0]));
+    ::image::imageops::replace(&mut screen, &block(), 5, 5);
+    ::image::imageops::replace(&mut screen, &block(), 50, 10);
+    let search = |search_monitor| {
+        let mut event = find_target(0.9);
+        if let RecordedEventKind::FindTarget { search_monitor: target, .. } = &mut event.kind {
+            *target = search_monitor;
+        }
+        event.monitor = Some(2);
+        event
+    };
+
+    let mut backend = MockBackend::new((80, 30)).with_cursor((0, 0)).with_monitors(monitors.clone());
+    backend.push_screenshot(screen.clone());
+    let report = play(&mut backend, vec![search(MonitorTarget::Monitor(1))], PlaybackOptions::default());
+    assert_eq!(report.error, None);
+    assert_eq!(last_move(&backend), Some((14, 14)));
+
+    let mut backend = MockBackend::new((80, 30)).with_cursor((0, 0)).with_monitors(monitors.clone());
+    backend.push_screenshot(screen.clone());
+    let report = play(&mut backend, vec![search(MonitorTarget::Recorded)], PlaybackOptions::default());
+    assert_eq!(report.error, None);
+    assert_eq!(last_move(&backend), Some((-31, 9)));
+
+    let mut backend = MockBackend::new((80, 30)).with_monitors(monitors);
+    backend.push_screenshot(screen);
+    let report = play(&mut backend, vec![search(MonitorTarget::Monitor(3))], PlaybackOptions::default());
+    assert_eq!(report.error.as_deref(), Some("Monitor 3 is not connected (2 found)"));
+}
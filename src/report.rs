@@ -0,0 +1,398 @@
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum RowOutcome {
+    Passed,
+    /// Failed, then left out by an `OnError::Skip` or `OnError::GotoLabel` policy.
+    Skipped,
+    Failed,
+}
+
+/// One executed row. Rows inside repeat blocks show up once per pass.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub(crate) struct RowReport {
+    /// Index into the played events; the GUI maps it back to the table row.
+    /// Rows are numbered from 0 everywhere, as in the editor table.
+    pub row: usize,
+    pub action: String,
+    /// Milliseconds since the run started, after the row's own wait.
+    pub start_ms: u64,
+    pub end_ms: u64,
+    /// Cursor position once the row was done.
+    pub position: Option<(i32, i32)>,
+    /// Correlation of the last template match the row made, or of the best
+    /// candidate below the threshold when its search timed out.
+    pub match_score: Option<f32>,
+    /// Template scale that matched, for searches with a scale sweep.
+    pub match_scale: Option<f32>,
+    /// Time spent in template searches, for rows that searched.
+    pub search_ms: Option<u64>,
+    pub retries: u32,
+    pub outcome: RowOutcome,
+    pub error: Option<String>,
+    /// Folder with the screenshots saved when the row failed.
+    pub failure_capture: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub(crate) struct PlaybackReport {
+    /// Wall-clock start, in milliseconds since the Unix epoch.
+    pub started_at_unix_ms: u64,
+    pub duration_ms: u64,
+    pub rows: Vec<RowReport>,
+    /// Why the run stopped early; "Cancelled" when it was cancelled.
+    pub error: Option<String>,
+}
+
+impl PlaybackReport {
+    pub(crate) fn started_now() -> Self {
+        let started_at_unix_ms = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        Self {
+            started_at_unix_ms,
+            ..Self::default()
+        }
+    }
+
+    /// One line such as "Retried rows 3 (2x), 8 (1x). Skipped rows 5.";
+    /// empty when every row passed on its first try.
+    pub(crate) fn describe(&self) -> String {
+        let retried: Vec<String> = self
+            .rows
+            .iter()
+            .filter(|row| row.retries > 0)
+            .map(|row| format!("{} ({}x)", row.row, row.retries))
+            .collect();
+        let skipped: Vec<String> = self
+            .rows
+            .iter()
+            .filter(|row| row.outcome == RowOutcome::Skipped)
+            .map(|row| row.row.to_string())
+            .collect();
+
+        let mut parts = Vec::new();
+        if !retried.is_empty() {
+            parts.push(format!("Retried rows {}.", retried.join(", ")));
+        }
+        if !skipped.is_empty() {
+            parts.push(format!("Skipped rows {}.", skipped.join(", ")));
+        }
+        parts.join(" ")
+    }
+
+    /// The row that stopped the run, when it stopped on a failing row.
+    pub(crate) fn failed_row(&self) -> Option<usize> {
+        self.rows
+            .last()
+            .filter(|row| self.error.is_some() && row.outcome == RowOutcome::Failed)
+            .map(|row| row.row)
+    }
+
+    pub(crate) fn to_json(&self) -> Result<String, String> {
+        serde_json::to_string_pretty(self).map_err(|e| e.to_string())
+    }
+
+    /// A single `<testsuite>` named `suite`, with one test case per executed row.
+    pub(crate) fn to_junit_xml(&self, suite: &str) -> String {
+        let count = |outcome: RowOutcome| self.rows.iter().filter(|row| row.outcome == outcome).count();
+        let stopped_early = self.error.is_some() as usize;
+        let seconds = |ms: u64| format!("{:.3}", ms as f64 / 1000.0);
+
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str(&format!(
+            "<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{}\" timestamp=\"{}\">\n",
+            xml_escape(suite),
+            self.rows.len() + stopped_early,
+            count(RowOutcome::Failed) + stopped_early,
+            count(RowOutcome::Skipped),
+            seconds(self.duration_ms),
+            iso8601_utc(self.started_at_unix_ms / 1000),
+        ));
+
+        for row in &self.rows {
+            let mut properties = Vec::new();
+            if let Some((x, y)) = row.position {
+                properties.push(("position", format!("{x},{y}")));
+            }
+            if let Some(score) = row.match_score {
+                properties.push(("match_score", format!("{score:.4}")));
+            }
+            if let Some(scale) = row.match_scale {
+                properties.push(("match_scale", format!("{scale:.2}")));
+            }
+            if let Some(search_ms) = row.search_ms {
+                properties.push(("search_ms", search_ms.to_string()));
+            }
+            if row.retries > 0 {
+                properties.push(("retries", row.retries.to_string()));
+            }
+            if let Some(folder) = &row.failure_capture {
+                properties.push(("failure_capture", folder.clone()));
+            }
+
+            xml.push_str(&format!(
+                "  <testcase classname=\"{}\" name=\"row {}: {}\" time=\"{}\">\n",
+                xml_escape(suite),
+                row.row,
+                xml_escape(&row.action),
+                seconds(row.end_ms.saturating_sub(row.start_ms)),
+            ));
+            if !properties.is_empty() {
+                xml.push_str("    <properties>\n");
+                for (name, value) in properties {
+                    xml.push_str(&format!("      <property name=\"{name}\" value=\"{}\"/>\n", xml_escape(&value)));
+                }
+                xml.push_str("    </properties>\n");
+            }
+            let message = xml_escape(row.error.as_deref().unwrap_or_default());
+            match row.outcome {
+                RowOutcome::Passed => {}
+                RowOutcome::Skipped => xml.push_str(&format!("    <skipped message=\"{message}\"/>\n")),
+                RowOutcome::Failed => xml.push_str(&format!("    <failure message=\"{message}\"/>\n")),
+            }
+            xml.push_str("  </testcase>\n");
+        }
+
+        // Failures outside a row (cancel, bad file) still need to fail the suite.
+        if let Some(error) = &self.error {
+            if !self.rows.iter().any(|row| row.outcome == RowOutcome::Failed) {
+                xml.push_str(&format!(
+                    "  <testcase classname=\"{}\" name=\"playback\" time=\"0.000\">\n    <failure message=\"{}\"/>\n  </testcase>\n",
+                    xml_escape(suite),
+                    xml_escape(error),
+                ));
+            }
+        }
+
+        xml.push_str("</testsuite>\n");
+        xml
+    }
+}
+
+/// Writes `report` as JUnit XML when `path` ends in `.xml`, as JSON otherwise.
+pub(crate) fn save_report(report: &PlaybackReport, path: &str, suite: &str) -> Result<String, String> {
+    let contents = if path.to_ascii_lowercase().ends_with(".xml") {
+        report.to_junit_xml(suite)
+    } else {
+        report.to_json()?
+    };
+    std::fs::write(path, contents).map_err(|e| format!("{path}: {e}"))?;
+    Ok(format!("Saved run report to {path}"))
+}
+
+/// `recording.yaml` becomes `recording.<suffix>`; a project folder gets
+/// `report.<suffix>` inside it.
+pub(crate) fn report_path_for(recording_path: &str, suffix: &str) -> String {
+    let path = Path::new(recording_path);
+    if recording_path.ends_with('/') || recording_path.ends_with(std::path::MAIN_SEPARATOR) || path.is_dir() {
+        return path.join(format!("report.{suffix}")).display().to_string();
+    }
+    path.with_extension(suffix).display().to_string()
+}
+
+/// Where failure captures for `recording_path` go: `recording-failures/` next
+/// to a recording file, `failures/` inside a project folder.
+pub(crate) fn failure_capture_dir(recording_path: &str) -> PathBuf {
+    let path = Path::new(recording_path);
+    if recording_path.ends_with('/') || recording_path.ends_with(std::path::MAIN_SEPARATOR) || path.is_dir() {
+        return path.join("failures");
+    }
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "recording".to_string());
+    path.with_file_name(format!("{stem}-failures"))
+}
+
+/// Also used for the HTML failure summaries.
+pub(crate) fn xml_escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c if c.is_control() && !matches!(c, '\n' | '\t') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// `YYYY-MM-DDTHH:MM:SS` for a Unix timestamp, which is what JUnit readers expect.
+pub(crate) fn iso8601_utc(unix_secs: u64) -> String {
+    let days = (unix_secs / 86_400) as i64;
+    let secs = unix_secs % 86_400;
+
+    // Civil-from-days, valid for the proleptic Gregorian calendar.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// Report fixtures shared by the test modules.
+#[cfg(test)]
+pub(crate) mod test_support {
+    use super::*;
+
+    /// A row that took no time and left nothing behind.
+    pub(crate) fn row_report(row: usize, outcome: RowOutcome) -> RowReport {
+        RowReport {
+            row,
+            action: "Click".to_string(),
+            start_ms: 0,
+            end_ms: 0,
+            position: None,
+            match_score: None,
+            match_scale: None,
+            search_ms: None,
+            retries: 0,
+            outcome,
+            error: None,
+            failure_capture: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_support::row_report;
+    use super::*;
+
+    /// A passed search row, a row retried twice, a skipped and a failed row.
+    fn mixed_report() -> PlaybackReport {
+        PlaybackReport {
+            started_at_unix_ms: 1_700_000_000_250,
+            duration_ms: 1234,
+            rows: vec![
+                RowReport {
+                    action: "Find <\"OK\"> & 'click'".to_string(),
+                    start_ms: 10,
+                    end_ms: 70,
+                    position: Some((-5, 20)),
+                    match_score: Some(0.9375),
+                    match_scale: Some(1.25),
+                    search_ms: Some(42),
+                    ..row_report(0, RowOutcome::Passed)
+                },
+                RowReport {
+                    retries: 2,
+                    end_ms: 1500,
+                    ..row_report(1, RowOutcome::Passed)
+                },
+                RowReport {
+                    error: Some("Target not found\u{7}".to_string()),
+                    ..row_report(3, RowOutcome::Skipped)
+                },
+                RowReport {
+                    error: Some("Timed out <2s>".to_string()),
+                    failure_capture: Some("run-failures/row 4".to_string()),
+                    ..row_report(4, RowOutcome::Failed)
+                },
+            ],
+            error: Some("Timed out <2s>".to_string()),
+        }
+    }
+
+    #[test]
+    fn describe_lists_retried_and_skipped_rows() {
+        assert_eq!(mixed_report().describe(), "Retried rows 1 (2x). Skipped rows 3.");
+        assert_eq!(PlaybackReport::default().describe(), "");
+    }
+
+    #[test]
+    fn json_keeps_every_field() {
+        let json: serde_json::Value = serde_json::from_str(&mixed_report().to_json().unwrap()).unwrap();
+
+        assert_eq!(json["started_at_unix_ms"], 1_700_000_000_250u64);
+        assert_eq!(json["duration_ms"], 1234);
+        assert_eq!(json["error"], "Timed out <2s>");
+        assert_eq!(
+            json["rows"][0],
+            serde_json::json!({
+                "row": 0,
+                "action": "Find <\"OK\"> & 'click'",
+                "start_ms": 10,
+                "end_ms": 70,
+                "position": [-5, 20],
+                "match_score": 0.9375,
+                "match_scale": 1.25,
+                "search_ms": 42,
+                "retries": 0,
+                "outcome": "passed",
+                "error": null,
+                "failure_capture": null,
+            })
+        );
+        assert_eq!(json["rows"][2]["outcome"], "skipped");
+        assert_eq!(json["rows"][3]["outcome"], "failed");
+        assert_eq!(json["rows"][3]["failure_capture"], "run-failures/row 4");
+    }
+
+    #[test]
+    fn junit_xml_escapes_and_marks_outcomes() {
+        assert_eq!(
+            mixed_report().to_junit_xml("login & <checkout>"),
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+<testsuite name=\"login &amp; &lt;checkout&gt;\" tests=\"5\" failures=\"2\" skipped=\"1\" time=\"1.234\" timestamp=\"2023-11-14T22:13:20\">
+  <testcase classname=\"login &amp; &lt;checkout&gt;\" name=\"row 0: Find &lt;&quot;OK&quot;&gt; &amp; &apos;click&apos;\" time=\"0.060\">
+    <properties>
+      <property name=\"position\" value=\"-5,20\"/>
+      <property name=\"match_score\" value=\"0.9375\"/>
+      <property name=\"match_scale\" value=\"1.25\"/>
+      <property name=\"search_ms\" value=\"42\"/>
+    </properties>
+  </testcase>
+  <testcase classname=\"login &amp; &lt;checkout&gt;\" name=\"row 1: Click\" time=\"1.500\">
+    <properties>
+      <property name=\"retries\" value=\"2\"/>
+    </properties>
+  </testcase>
+  <testcase classname=\"login &amp; &lt;checkout&gt;\" name=\"row 3: Click\" time=\"0.000\">
+    <skipped message=\"Target not found\"/>
+  </testcase>
+  <testcase classname=\"login &amp; &lt;checkout&gt;\" name=\"row 4: Click\" time=\"0.000\">
+    <properties>
+      <property name=\"failure_capture\" value=\"run-failures/row 4\"/>
+    </properties>
+    <failure message=\"Timed out &lt;2s&gt;\"/>
+  </testcase>
+</testsuite>
+"
+        );
+    }
+
+    #[test]
+    fn junit_xml_fails_runs_that_stop_between_rows() {
+        let cancelled = PlaybackReport {
+            rows: vec![row_report(0, RowOutcome::Passed)],
+            error: Some("Cancelled".to_string()),
+            ..PlaybackReport::default()
+        };
+        let xml = cancelled.to_junit_xml("suite");
+
+        assert!(xml.contains("tests=\"2\" failures=\"1\" skipped=\"0\""), "{xml}");
+        assert!(
+            xml.contains("  <testcase classname=\"suite\" name=\"playback\" time=\"0.000\">\n    <failure message=\"Cancelled\"/>\n"),
+            "{xml}"
+        );
+    }
+}
@@ -1,6 +1,9 @@
-use crate::model::{MouseButton, SearchAnchor};
+use crate::model::{KeyModifiers, MatchFilter, MatchStrategy, MonitorTarget, MouseButton, RecordedEvent, SearchAnchor};
+use std::collections::VecDeque;
 use std::time::Instant;
 
+const EDIT_HISTORY_LIMIT: usize = 100;
+
 #[derive(Debug, Clone)]
 pub(crate) struct FindTargetDraft {
     pub patch_png_base64: Option<String>,
@@ -16,6 +19,22 @@ pub(crate) struct FindTargetDraft {
     pub region_size: u32,
 
     pub anchor: SearchAnchor,
+    pub match_strategy: MatchStrategy,
+    pub match_nth_text: String,
+    pub offset_x_text: String,
+    pub offset_y_text: String,
+    pub scale_sweep_enabled: bool,
+    pub scale_min_text: String,
+    pub scale_max_text: String,
+    pub scale_step_text: String,
+    pub match_filter: MatchFilter,
+    pub search_monitor: MonitorTarget,
+
+    // Mask painter over the patch preview
+    pub mask_brush: MaskBrush,
+    pub mask_painting: bool,
+    /// Cursor over the painter, as a fraction of the patch size.
+    pub mask_hover: Option<iced::Point>,
 
     // Capture flow
     pub capture_waiting: bool,
@@ -25,9 +44,61 @@ pub(crate) struct FindTargetDraft {
     // Load-from-path flow
     pub image_path: String,
 
+    // "Test match now" flow
+    pub match_testing: bool,
+    pub match_test: Option<MatchTest>,
+
     pub status: String,
 }
 
+impl Default for FindTargetDraft {
+    fn default() -> Self {
+        Self {
+            patch_png_base64: None,
+            patch_size_text: "64".to_string(),
+            patch_size: 64,
+            precision_text: "0.92".to_string(),
+            precision: 0.92,
+            timeout_ms_text: "2000".to_string(),
+            timeout_ms: 2000,
+            limit_region: false,
+            region_size_text: "600".to_string(),
+            region_size: 600,
+            anchor: SearchAnchor::RecordedClick,
+            match_strategy: MatchStrategy::BestScore,
+            match_nth_text: "1".to_string(),
+            offset_x_text: "0".to_string(),
+            offset_y_text: "0".to_string(),
+            scale_sweep_enabled: false,
+            scale_min_text: "0.5".to_string(),
+            scale_max_text: "2.0".to_string(),
+            scale_step_text: "0.25".to_string(),
+            match_filter: MatchFilter::Grayscale,
+            search_monitor: MonitorTarget::All,
+            mask_brush: MaskBrush::Ignore,
+            mask_painting: false,
+            mask_hover: None,
+            capture_waiting: false,
+            capture_left_was_down: false,
+            captured_pos: None,
+            image_path: String::new(),
+            match_testing: false,
+            match_test: None,
+            status: String::new(),
+        }
+    }
+}
+
+/// Strongest candidate for the draft's template on the current screen,
+/// whether or not it clears the precision threshold.
+#[derive(Debug, Clone)]
+pub(crate) struct MatchTest {
+    pub pos: (i32, i32),
+    pub score: f32,
+    /// The screen under the candidate, captured at the template's size.
+    pub candidate_png_base64: Option<String>,
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct WaitDraft {
     pub wait_ms_text: String,
@@ -35,6 +106,42 @@ pub(crate) struct WaitDraft {
     pub status: String,
 }
 
+/// What the mask painter does to the patch pixels under the brush.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MaskBrush {
+    /// Makes them transparent, so `MatchFilter::Masked` skips them.
+    Ignore,
+    Keep,
+}
+
+impl std::fmt::Display for MaskBrush {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Ignore => "Paint ignored",
+            Self::Keep => "Paint kept",
+        })
+    }
+}
+
+/// Editor choice for a wait row's `OnTimeout`; the label name is edited separately.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OnTimeoutChoice {
+    Fail,
+    Continue,
+    GotoLabel,
+}
+
+/// Editor choice for an `OnError` policy; retry counts and the label name are
+/// edited separately. `RunDefault` clears a row's override.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum OnErrorChoice {
+    RunDefault,
+    Abort,
+    Skip,
+    Retry,
+    GotoLabel,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub(crate) enum Mode {
     Idle,
@@ -47,10 +154,10 @@ pub(crate) struct PendingClick {
     pub button: MouseButton,
     pub pos: Option<(i32, i32)>,
     pub up_at: Instant,
-    pub synthetic_time_ms: u128,
+    pub ms_from_start: u128,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub(crate) struct RecorderState {
     pub enabled: bool,
 
@@ -67,35 +174,139 @@ pub(crate) struct RecorderState {
     pub right_down_at: Option<Instant>,
     pub middle_down_at: Option<Instant>,
 
-    pub started_by_click: bool,
+    // Time origin of the recording, set when the first row is recorded
+    pub started_at: Option<Instant>,
     pub last_click_pos: Option<(i32, i32)>,
-    pub synthetic_time_ms: u128,
     pub left_pending_click: Option<PendingClick>,
     pub right_pending_click: Option<PendingClick>,
     pub middle_pending_click: Option<PendingClick>,
+
+    // Non-modifier keys currently held, with the modifiers active when pressed
+    pub keys_down: Vec<(&'static str, KeyModifiers)>,
+    // Modifier keys currently held
+    pub modifiers_down: Vec<&'static str>,
 }
 
-impl Default for RecorderState {
-    fn default() -> Self {
-        Self {
-            enabled: false,
-            last_x: 0,
-            last_y: 0,
-            left_down: false,
-            right_down: false,
-            middle_down: false,
-            left_down_pos: None,
-            right_down_pos: None,
-            middle_down_pos: None,
-            left_down_at: None,
-            right_down_at: None,
-            middle_down_at: None,
-            started_by_click: false,
-            last_click_pos: None,
-            synthetic_time_ms: 0,
-            left_pending_click: None,
-            right_pending_click: None,
-            middle_pending_click: None,
+impl RecorderState {
+    /// Real time since the first recorded row, which itself gets 0.
+    pub fn elapsed_ms(&mut self) -> u128 {
+        self.started_at.get_or_insert_with(Instant::now).elapsed().as_millis()
+    }
+}
+
+/// Event list as it was before (undo side) or after (redo side) the labelled edit.
+#[derive(Debug, Clone)]
+pub(crate) struct EditSnapshot {
+    pub label: String,
+    pub events: Vec<RecordedEvent>,
+    pub selected_index: Option<usize>,
+}
+
+/// Bounded undo/redo stacks of whole event-list snapshots.
+#[derive(Debug, Default)]
+pub(crate) struct EditHistory {
+    undo: VecDeque<EditSnapshot>,
+    redo: Vec<EditSnapshot>,
+}
+
+impl EditHistory {
+    pub fn record(&mut self, before: EditSnapshot) {
+        self.redo.clear();
+        self.undo.push_back(before);
+        if self.undo.len() > EDIT_HISTORY_LIMIT {
+            self.undo.pop_front();
+        }
+    }
+
+    /// Swaps `current` for the state before the last edit.
+    pub fn undo(&mut self, current: EditSnapshot) -> Option<EditSnapshot> {
+        let previous = self.undo.pop_back()?;
+        self.redo.push(EditSnapshot {
+            label: previous.label.clone(),
+            ..current
+        });
+        Some(previous)
+    }
+
+    /// Swaps `current` for the state after the last undone edit.
+    pub fn redo(&mut self, current: EditSnapshot) -> Option<EditSnapshot> {
+        let next = self.redo.pop()?;
+        self.undo.push_back(EditSnapshot {
+            label: next.label.clone(),
+            ..current
+        });
+        Some(next)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::test_support::text;
+
+    /// `rows` text rows, each typing its own index.
+    fn snapshot(label: &str, rows: usize, selected_index: Option<usize>) -> EditSnapshot {
+        let events = (0..rows).map(|i| text(&i.to_string())).collect();
+        EditSnapshot {
+            label: label.to_string(),
+            events,
+            selected_index,
+        }
+    }
+
+    #[test]
+    fn history_keeps_the_last_hundred_edits() {
+        let mut history = EditHistory::default();
+        for rows in 0..EDIT_HISTORY_LIMIT + 5 {
+            history.record(snapshot(&format!("edit {rows}"), rows, None));
+        }
+
+        let mut undone = Vec::new();
+        while let Some(previous) = history.undo(snapshot("current", 0, None)) {
+            undone.push(previous.label);
         }
+        assert_eq!(undone.len(), EDIT_HISTORY_LIMIT);
+        assert_eq!(undone.first().map(String::as_str), Some("edit 104"));
+        assert_eq!(undone.last().map(String::as_str), Some("edit 5"));
+    }
+
+    #[test]
+    fn new_edit_clears_redo() {
+        let mut history = EditHistory::default();
+        history.record(snapshot("insert row", 1, Some(0)));
+        assert!(history.undo(snapshot("", 2, Some(1))).is_some());
+        assert!(history.can_redo());
+
+        history.record(snapshot("delete row", 1, Some(0)));
+        assert!(!history.can_redo());
+        assert!(history.redo(snapshot("", 0, None)).is_none());
+    }
+
+    #[test]
+    fn undo_then_redo_restores_rows_and_selection() {
+        let mut history = EditHistory::default();
+        let before = snapshot("insert row", 1, Some(0));
+        let after = snapshot("", 2, Some(1));
+        history.record(before.clone());
+
+        let undone = history.undo(after.clone()).unwrap();
+        assert_eq!(undone.events, before.events);
+        assert_eq!(undone.selected_index, Some(0));
+        assert!(!history.can_undo());
+
+        let redone = history.redo(undone).unwrap();
+        assert_eq!(redone.label, "insert row");
+        assert_eq!(redone.events, after.events);
+        assert_eq!(redone.selected_index, Some(1));
+        assert!(history.can_undo());
+        assert!(!history.can_redo());
     }
 }
@@ -1,12 +1,527 @@
-use crate::model::RecordedEvent;
+use crate::model::{block_jumps, label_rows, RecordedEvent, ScreenLayout};
+use base64::engine::general_purpose;
+use base64::Engine;
+use serde_yaml::{Mapping, Value};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
 
-pub fn save_events_to_file(path: &str, events: &[RecordedEvent]) -> Result<String, String> {
-    let yaml = serde_yaml::to_string(events).map_err(|e| e.to_string())?;
-    std::fs::write(path, yaml).map_err(|e| e.to_string())?;
-    Ok(format!("Saved {} events to {path}", events.len()))
+/// Version written by this build. Bump it together with a new entry in
+/// `MIGRATIONS` whenever the saved shape changes.
+///
+/// 1. Bare YAML list of events (no header).
+/// 2. Document with `format_version`, `header` and `events`.
+/// 3. Rows may replace `patch_png_base64` with `patch: <hash>`, pointing at
+///    `patches/<hash>.png` next to the YAML file.
+/// 4. The header records whether `ms_from_start` values are `real_timestamps`.
+/// 5. Rows may be `RepeatStart`/`RepeatEnd` block markers.
+/// 6. Rows may be `IfImage`/`Else`/`EndIf` block markers.
+/// 7. Rows may be `WaitForImage`, `WaitUntilImageGone` or `Label`.
+/// 8. Rows may carry their own `on_error` policy.
+/// 9. Template searches may pick a `match_strategy` other than the best score.
+/// 10. Template searches may move by a `target_offset` from the match center.
+/// 11. Template searches may try other scales from a `scale_sweep`.
+/// 12. Template searches may compare through a `match_filter` other than grayscale.
+/// 13. The header may record the `screen` the positions refer to.
+/// 14. Rows may be `BindWindow`/`EndWindow` block markers.
+/// 15. Rows may record their `monitor` and searches a `search_monitor`. The
+///     header `screen` is the whole virtual desktop; older values move to
+///     `legacy_screen`.
+pub const FORMAT_VERSION: u32 = 15;
+
+/// File name used inside a project directory.
+pub const PROJECT_RECORDING_FILE: &str = "recording.yaml";
+/// Sidecar patch directory, relative to the YAML file.
+pub const PATCH_DIR: &str = "patches";
+
+type Migration = fn(Value) -> Result<Value, String>;
+
+/// `MIGRATIONS[n]` upgrades a version `n + 1` document to version `n + 2`.
+const MIGRATIONS: &[Migration] = &[
+    migrate_v1_to_v2,
+    migrate_v2_to_v3,
+    migrate_v3_to_v4,
+    migrate_v4_to_v5,
+    migrate_v5_to_v6,
+    migrate_v6_to_v7,
+    migrate_v7_to_v8,
+    migrate_v8_to_v9,
+    migrate_v9_to_v10,
+    migrate_v10_to_v11,
+    migrate_v11_to_v12,
+    migrate_v12_to_v13,
+    migrate_v13_to_v14,
+    migrate_v14_to_v15,
+];
+const _: () = assert!(MIGRATIONS.len() as u32 + 1 == FORMAT_VERSION);
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RecordingHeader {
+    /// Version of the app that saved the file.
+    pub app_version: String,
+    /// Screen at record time; playback remaps positions when it differs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screen: Option<ScreenLayout>,
+    /// Whether every row's `ms_from_start` was taken while recording. Rows
+    /// added in the editor get made-up values, which faithful timing must not
+    /// replay.
+    #[serde(default)]
+    pub real_timestamps: bool,
+    /// `screen` from before format 15, which held the primary monitor on
+    /// Windows and the whole screen on X11. Read through `recording_screen`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub legacy_screen: Option<ScreenLayout>,
+}
+
+impl RecordingHeader {
+    /// The virtual desktop the positions were recorded on. A legacy screen
+    /// equal to the current desktop or primary monitor counts as recorded on
+    /// this desktop; others are used as they are.
+    pub fn recording_screen(&self, desktop: Option<ScreenLayout>, monitors: &[ScreenLayout]) -> Option<ScreenLayout> {
+        match self.legacy_screen {
+            Some(legacy) if desktop == Some(legacy) || monitors.first() == Some(&legacy) => desktop,
+            legacy => self.screen.or(legacy),
+        }
+    }
+}
+
+impl Default for RecordingHeader {
+    fn default() -> Self {
+        Self {
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            screen: None,
+            real_timestamps: false,
+            legacy_screen: None,
+        }
+    }
+}
+
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RecordingDocument {
+    format_version: u32,
+    #[serde(default)]
+    header: RecordingHeader,
+    events: Vec<RecordedEvent>,
+}
+
+/// Saves inline (patches as base64 in the YAML) unless `path` is a directory
+/// (existing, or ending in a path separator), which gets the project layout:
+/// `recording.yaml` plus one `patches/<hash>.png` per distinct patch.
+pub fn save_events_to_file(path: &str, events: &[RecordedEvent], header: RecordingHeader) -> Result<String, String> {
+    // Refuse to write a file that would fail to load again.
+    block_jumps(events)?;
+    label_rows(events)?;
+
+    let document = RecordingDocument {
+        format_version: FORMAT_VERSION,
+        header,
+        events: events.to_vec(),
+    };
+
+    if !is_project_dir(path) {
+        let yaml = serde_yaml::to_string(&document).map_err(|e| e.to_string())?;
+        std::fs::write(path, yaml).map_err(|e| e.to_string())?;
+        return Ok(format!("Saved {} events to {path}", events.len()));
+    }
+
+    let dir = Path::new(path);
+    let patch_dir = dir.join(PATCH_DIR);
+    std::fs::create_dir_all(&patch_dir).map_err(|e| format!("{}: {e}", patch_dir.display()))?;
+
+    let mut value = serde_yaml::to_value(&document).map_err(|e| e.to_string())?;
+    let mut written = 0usize;
+    for_each_event_fields(&mut value, |fields| {
+        let Some(Value::String(b64)) = fields.get("patch_png_base64") else {
+            return Ok(());
+        };
+        let png = general_purpose::STANDARD
+            .decode(b64)
+            .map_err(|e| format!("patch decode failed: {e}"))?;
+        let hash = patch_hash(&png);
+
+        let patch_path = patch_dir.join(format!("{hash}.png"));
+        if !patch_path.exists() {
+            std::fs::write(&patch_path, &png).map_err(|e| format!("{}: {e}", patch_path.display()))?;
+            written += 1;
+        }
+
+        fields.remove("patch_png_base64");
+        fields.insert("patch".into(), hash.into());
+        Ok(())
+    })?;
+
+    let yaml_path = dir.join(PROJECT_RECORDING_FILE);
+    let yaml = serde_yaml::to_string(&value).map_err(|e| e.to_string())?;
+    std::fs::write(&yaml_path, yaml).map_err(|e| format!("{}: {e}", yaml_path.display()))?;
+    Ok(format!(
+        "Saved {} events to {} ({written} new patch files)",
+        events.len(),
+        yaml_path.display()
+    ))
+}
+
+/// Accepts a YAML file or a project directory. `patch` references are resolved
+/// against the YAML file's directory.
+pub fn load_events_from_file(path: &str) -> Result<(Vec<RecordedEvent>, RecordingHeader), String> {
+    let yaml_path = if Path::new(path).is_dir() {
+        Path::new(path).join(PROJECT_RECORDING_FILE)
+    } else {
+        PathBuf::from(path)
+    };
+    let path = yaml_path.display().to_string();
+    let yaml = std::fs::read_to_string(&yaml_path).map_err(|e| format!("{path}: {e}"))?;
+    let value: Value = serde_yaml::from_str(&yaml).map_err(|e| format!("{path}: {e}"))?;
+
+    let version = detect_format_version(&value).map_err(|e| format!("{path}: {e}"))?;
+    if version > FORMAT_VERSION {
+        return Err(format!(
+            "{path} was saved by a newer version of the recorder (format {version}); \
+             this build reads formats up to {FORMAT_VERSION}. Please update to load it."
+        ));
+    }
+
+    let mut value = migrate(value, version).map_err(|e| format!("{path}: {e}"))?;
+
+    let patch_dir = yaml_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(PATCH_DIR);
+    for_each_event_fields(&mut value, |fields| {
+        let Some(reference) = fields.remove("patch") else {
+            return Ok(());
+        };
+        let Some(hash) = reference.as_str().filter(|h| is_patch_hash(h)) else {
+            return Err(format!("invalid patch reference {reference:?}"));
+        };
+
+        let patch_path = patch_dir.join(format!("{hash}.png"));
+        let png = std::fs::read(&patch_path).map_err(|e| format!("{}: {e}", patch_path.display()))?;
+        fields.insert(
+            "patch_png_base64".into(),
+            general_purpose::STANDARD.encode(png).into(),
+        );
+        Ok(())
+    })
+    .map_err(|e| format!("{path}: {e}"))?;
+
+    let document: RecordingDocument = serde_yaml::from_value(value).map_err(|e| format!("{path}: {e}"))?;
+    block_jumps(&document.events).map_err(|e| format!("{path}: {e}"))?;
+    label_rows(&document.events).map_err(|e| format!("{path}: {e}"))?;
+    Ok((document.events, document.header))
+}
+
+fn is_project_dir(path: &str) -> bool {
+    path.ends_with('/') || path.ends_with(std::path::MAIN_SEPARATOR) || Path::new(path).is_dir()
+}
+
+/// First 128 bits of the SHA-256 of the PNG bytes, as lowercase hex.
+fn patch_hash(png: &[u8]) -> String {
+    Sha256::digest(png)[..16]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+// Hashes become file names, so only plain hex is accepted when loading.
+fn is_patch_hash(hash: &str) -> bool {
+    !hash.is_empty() && hash.len() <= 64 && hash.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Runs `f` on the field mapping of every `events[*].kind` in a document.
+fn for_each_event_fields(
+    document: &mut Value,
+    mut f: impl FnMut(&mut Mapping) -> Result<(), String>,
+) -> Result<(), String> {
+    let Some(Value::Sequence(events)) = document.get_mut("events") else {
+        return Ok(());
+    };
+
+    for event in events {
+        if let Some(Value::Tagged(kind)) = event.get_mut("kind") {
+            if let Value::Mapping(fields) = &mut kind.value {
+                f(fields)?;
+            }
+        }
+    }
+    Ok(())
 }
 
-pub fn load_events_from_file(path: &str) -> Result<Vec<RecordedEvent>, String> {
-    let yaml = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
-    serde_yaml::from_str(&yaml).map_err(|e| e.to_string())
+fn detect_format_version(value: &Value) -> Result<u32, String> {
+    match value {
+        Value::Sequence(_) => Ok(1),
+        Value::Mapping(map) => match map.get("format_version") {
+            Some(Value::Number(n)) => n
+                .as_u64()
+                .and_then(|v| u32::try_from(v).ok())
+                .filter(|v| *v >= 1)
+                .ok_or_else(|| format!("invalid format_version {n}")),
+            Some(_) => Err("format_version must be a number".to_string()),
+            None => Err("missing format_version".to_string()),
+        },
+        _ => Err("not a recording file".to_string()),
+    }
+}
+
+fn migrate(mut value: Value, from_version: u32) -> Result<Value, String> {
+    for step in &MIGRATIONS[(from_version - 1) as usize..] {
+        value = step(value)?;
+    }
+    Ok(value)
+}
+
+/// Wraps the original bare event list in a versioned document.
+fn migrate_v1_to_v2(value: Value) -> Result<Value, String> {
+    let Value::Sequence(events) = value else {
+        return Err("format 1 recording must be a list of events".to_string());
+    };
+
+    let mut header = Mapping::new();
+    header.insert("app_version".into(), "unknown".into());
+
+    let mut document = Mapping::new();
+    document.insert("format_version".into(), 2.into());
+    document.insert("header".into(), Value::Mapping(header));
+    document.insert("events".into(), Value::Sequence(events));
+    Ok(Value::Mapping(document))
+}
+
+/// Version 3 only adds optional `patch` references, so the data carries over.
+fn migrate_v2_to_v3(value: Value) -> Result<Value, String> {
+    set_format_version(value, 3)
+}
+
+/// Version 4 adds the header `real_timestamps`. Older files leave it out and
+/// so count as made-up, since editor rows were saved the same way.
+fn migrate_v3_to_v4(value: Value) -> Result<Value, String> {
+    set_format_version(value, 4)
+}
+
+/// Version 5 adds repeat block rows, which older files cannot contain.
+fn migrate_v4_to_v5(value: Value) -> Result<Value, String> {
+    set_format_version(value, 5)
+}
+
+/// Version 6 adds image condition rows, which older files cannot contain.
+fn migrate_v5_to_v6(value: Value) -> Result<Value, String> {
+    set_format_version(value, 6)
+}
+
+/// Version 7 adds image wait and label rows, which older files cannot contain.
+fn migrate_v6_to_v7(value: Value) -> Result<Value, String> {
+    set_format_version(value, 7)
+}
+
+/// Version 8 adds the optional row `on_error`, so the data carries over.
+fn migrate_v7_to_v8(value: Value) -> Result<Value, String> {
+    set_format_version(value, 8)
+}
+
+/// Version 9 adds `match_strategy`; rows without one keep the best score.
+fn migrate_v8_to_v9(value: Value) -> Result<Value, String> {
+    set_format_version(value, 9)
+}
+
+/// Version 10 adds `target_offset`; rows without one use the match center.
+fn migrate_v9_to_v10(value: Value) -> Result<Value, String> {
+    set_format_version(value, 10)
+}
+
+/// Version 11 adds the optional `scale_sweep`, so the data carries over.
+fn migrate_v10_to_v11(value: Value) -> Result<Value, String> {
+    set_format_version(value, 11)
+}
+
+/// Version 12 adds `match_filter`; rows without one match in grayscale.
+fn migrate_v11_to_v12(value: Value) -> Result<Value, String> {
+    set_format_version(value, 12)
+}
+
+/// Version 13 only adds the optional header `screen`, so the data carries over.
+fn migrate_v12_to_v13(value: Value) -> Result<Value, String> {
+    set_format_version(value, 13)
+}
+
+/// Version 14 adds window block rows, which older files cannot contain.
+fn migrate_v13_to_v14(value: Value) -> Result<Value, String> {
+    set_format_version(value, 14)
+}
+
+/// Version 15 keeps positions but redefines the header `screen` as the whole
+/// virtual desktop, so an older value moves to `legacy_screen`.
+fn migrate_v14_to_v15(mut value: Value) -> Result<Value, String> {
+    if let Some(Value::Mapping(header)) = value.get_mut("header") {
+        if let Some(screen) = header.remove("screen") {
+            header.insert("legacy_screen".into(), screen);
+        }
+    }
+    set_format_version(value, 15)
+}
+
+/// Marks a document whose data carries over unchanged as `version`.
+fn set_format_version(mut value: Value, version: u32) -> Result<Value, String> {
+    let Value::Mapping(document) = &mut value else {
+        return Err(format!("format {} recording must be a mapping", version - 1));
+    };
+    document.insert("format_version".into(), version.into());
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::test_support::{layout, row};
+    use crate::model::{ClickListMeta, MatchStrategy, RecordedEventKind};
+
+    fn move_row(ms_from_start: u128, x: i32, y: i32) -> RecordedEvent {
+        RecordedEvent {
+            ms_from_start,
+            pos: Some((x, y)),
+            ..row(RecordedEventKind::Move { x, y })
+        }
+    }
+
+    /// A file path in the temp directory, unique to this test process.
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("mouse_recorder_{}_{name}", std::process::id()))
+    }
+
+    fn load_yaml(name: &str, yaml: &str) -> Result<(Vec<RecordedEvent>, RecordingHeader), String> {
+        let path = temp_path(name);
+        std::fs::write(&path, yaml).unwrap();
+        let loaded = load_events_from_file(&path.display().to_string());
+        std::fs::remove_file(&path).unwrap();
+        loaded
+    }
+
+    #[test]
+    fn bare_list_migrates_to_current_format() {
+        let yaml = "- ms_from_start: 5\n  kind: !Move\n    x: 1\n    y: 2\n  pos: [1, 2]\n  click_meta: null\n";
+        let (events, header) = load_yaml("v1.yaml", yaml).unwrap();
+        assert_eq!(events, vec![move_row(5, 1, 2)]);
+        assert_eq!(header.app_version, "unknown");
+        assert!(!header.real_timestamps);
+    }
+
+    #[test]
+    fn version_3_timestamps_count_as_made_up() {
+        let yaml = "format_version: 3\nheader:\n  app_version: 0.5.0\nevents: []\n";
+        let (_, header) = load_yaml("v3.yaml", yaml).unwrap();
+        assert_eq!(header.app_version, "0.5.0");
+        assert!(!header.real_timestamps);
+    }
+
+    #[test]
+    fn screens_before_version_15_are_legacy() {
+        let yaml = "format_version: 14\nheader:\n  app_version: 0.5.0\n  screen:\n    origin: [0, 0]\n    size: [1920, 1080]\nevents: []\n";
+        let (_, header) = load_yaml("v14.yaml", yaml).unwrap();
+        assert_eq!(header.screen, None);
+        let legacy = layout((0, 0), (1920, 1080));
+        assert_eq!(header.legacy_screen, Some(legacy));
+
+        // Windows stored the primary monitor: this desktop when it still is.
+        let primary_and_left = [legacy, layout((-1280, 0), (1280, 1024))];
+        let desktop = Some(layout((-1280, 0), (3200, 1080)));
+        assert_eq!(header.recording_screen(desktop, &primary_and_left), desktop);
+        // X11 stored the whole screen.
+        assert_eq!(header.recording_screen(Some(legacy), &[layout((0, 0), (960, 1080))]), Some(legacy));
+        // Anything else was recorded elsewhere.
+        let other = Some(layout((0, 0), (1280, 720)));
+        assert_eq!(header.recording_screen(other, &[layout((0, 0), (1280, 720))]), Some(legacy));
+    }
+
+    #[test]
+    fn header_round_trips() {
+        let path = temp_path("round_trip.yaml").display().to_string();
+        let events = vec![move_row(0, 10, 20), move_row(40, 30, 40)];
+        let header = RecordingHeader {
+            screen: Some(layout((0, 0), (1920, 1080))),
+            real_timestamps: true,
+            ..RecordingHeader::default()
+        };
+        save_events_to_file(&path, &events, header.clone()).unwrap();
+        let (loaded, loaded_header) = load_events_from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded, events);
+        assert_eq!(loaded_header.screen, header.screen);
+        assert!(loaded_header.real_timestamps);
+    }
+
+    fn click_row(meta: ClickListMeta) -> RecordedEvent {
+        RecordedEvent {
+            pos: Some((5, 5)),
+            click_meta: Some(meta),
+            ..row(RecordedEventKind::LeftClick { patch_png_base64: None })
+        }
+    }
+
+    #[test]
+    fn project_dir_stores_each_patch_once() {
+        let patched = |png: &[u8]| RecordedEvent {
+            kind: RecordedEventKind::LeftClick {
+                patch_png_base64: Some(general_purpose::STANDARD.encode(png)),
+            },
+            ..click_row(ClickListMeta::default())
+        };
+        let events = vec![patched(b"first patch"), patched(b"second patch"), patched(b"first patch")];
+        let dir = temp_path("project");
+        let path = format!("{}/", dir.display());
+
+        let saved = save_events_to_file(&path, &events, RecordingHeader::default()).unwrap();
+        assert!(saved.ends_with("(2 new patch files)"), "{saved}");
+        let hash = patch_hash(b"first patch");
+        assert!(dir.join(PATCH_DIR).join(format!("{hash}.png")).is_file());
+        assert_eq!(std::fs::read_dir(dir.join(PATCH_DIR)).unwrap().count(), 2);
+
+        let yaml = std::fs::read_to_string(dir.join(PROJECT_RECORDING_FILE)).unwrap();
+        assert!(!yaml.contains("patch_png_base64"), "{yaml}");
+        assert_eq!(yaml.matches(&format!("patch: {hash}")).count(), 2, "{yaml}");
+
+        // Saving again finds the patches already on disk.
+        let saved = save_events_to_file(&path, &events, RecordingHeader::default()).unwrap();
+        assert!(saved.ends_with("(0 new patch files)"), "{saved}");
+
+        let loaded = load_events_from_file(&dir.display().to_string());
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(loaded.unwrap().0, events);
+    }
+
+    #[test]
+    fn default_match_settings_are_left_out() {
+        let plain = serde_yaml::to_string(&click_row(ClickListMeta::default())).unwrap();
+        for field in ["match_strategy", "target_offset", "scale_sweep", "match_filter", "search_monitor", "monitor"] {
+            assert!(!plain.contains(field), "{plain}");
+        }
+
+        let offset = click_row(ClickListMeta {
+            target_offset: (-4, 0),
+            ..ClickListMeta::default()
+        });
+        let yaml = serde_yaml::to_string(&offset).unwrap();
+        assert!(yaml.contains("target_offset:"), "{yaml}");
+        assert_eq!(serde_yaml::from_str::<RecordedEvent>(&yaml).unwrap(), offset);
+
+        let nth = click_row(ClickListMeta {
+            match_strategy: MatchStrategy::Nth(2),
+            ..ClickListMeta::default()
+        });
+        let yaml = serde_yaml::to_string(&nth).unwrap();
+        assert!(yaml.contains("match_strategy: !Nth 2"), "{yaml}");
+        assert_eq!(serde_yaml::from_str::<RecordedEvent>(&yaml).unwrap(), nth);
+    }
+
+    #[test]
+    fn migrations_reach_current_version() {
+        let document = serde_yaml::from_str("format_version: 2\nevents: []\n").unwrap();
+        let migrated = migrate(document, 2).unwrap();
+        assert_eq!(detect_format_version(&migrated), Ok(FORMAT_VERSION));
+    }
+
+    #[test]
+    fn newer_format_is_refused() {
+        let yaml = format!("format_version: {}\nevents: []\n", FORMAT_VERSION + 1);
+        let err = load_yaml("newer.yaml", &yaml).unwrap_err();
+        assert!(err.contains("newer version"), "{err}");
+    }
 }
@@ -35,7 +35,10 @@ impl App {
 
     pub(crate) fn thumb_handle_for_event(&self, ev: &RecordedEvent) -> Option<iced::widget::image::Handle> {
         match &ev.kind {
-            RecordedEventKind::FindTarget { patch_png_base64, .. } => {
+            RecordedEventKind::FindTarget { patch_png_base64, .. }
+            | RecordedEventKind::IfImage { patch_png_base64, .. }
+            | RecordedEventKind::WaitForImage { patch_png_base64, .. }
+            | RecordedEventKind::WaitUntilImageGone { patch_png_base64, .. } => {
                 self.thumb_handle_from_base64(patch_png_base64)
             }
             RecordedEventKind::LeftClick { patch_png_base64: Some(b64) }
@@ -53,6 +56,22 @@ impl App {
         }
     }
 
+    /// Pixel size of the editor's preview patch, read from the PNG header.
+    pub(crate) fn preview_patch_size(&self) -> Option<(u32, u32)> {
+        use base64::engine::general_purpose;
+        use base64::Engine;
+
+        // Signature (8 bytes), IHDR length and type (8 bytes), then width and height.
+        let b64 = self.editor_static_preview_patch_b64.as_deref()?;
+        let header = general_purpose::STANDARD.decode(b64.get(..32)?).ok()?;
+        if !header.starts_with(b"\x89PNG") {
+            return None;
+        }
+        let width = u32::from_be_bytes(header[16..20].try_into().ok()?);
+        let height = u32::from_be_bytes(header[20..24].try_into().ok()?);
+        (width > 0 && height > 0).then_some((width, height))
+    }
+
     pub(crate) fn preview_handle_from_base64(&self, png_base64: &str) -> Option<iced::widget::image::Handle> {
         use base64::engine::general_purpose;
         use base64::Engine;
@@ -71,6 +90,26 @@ impl App {
         Some(handle)
     }
 
+    /// The patch at its own size with transparent (ignored) pixels tinted red,
+    /// for the mask painter. Not cached, since every brush stroke changes it.
+    pub(crate) fn mask_preview_handle(&self, png_base64: &str) -> Option<iced::widget::image::Handle> {
+        use base64::engine::general_purpose;
+        use base64::Engine;
+
+        let bytes = general_purpose::STANDARD.decode(png_base64).ok()?;
+        let mut rgba = ::image::load_from_memory(&bytes).ok()?.to_rgba8();
+        for px in rgba.pixels_mut() {
+            if px[3] < 128 {
+                px[0] = px[0] / 3 + 170;
+                px[1] /= 3;
+                px[2] /= 3;
+            }
+            px[3] = 255;
+        }
+        let (w, h) = rgba.dimensions();
+        Some(iced::widget::image::Handle::from_rgba(w, h, rgba.into_raw()))
+    }
+
     pub(crate) fn view_click_target_modes_row(
         &self,
         target: ClickTarget,
@@ -28,7 +28,13 @@ impl App {
             }
         };
 
+        let is_paused = self
+            .playback_pause
+            .as_ref()
+            .is_some_and(|pause| pause.is_paused());
+
         let lower_left_text = match self.mode {
+            Mode::Playing if is_paused => format!("Paused: {frame_progress_text}"),
             Mode::Playing => format!("Playing: {frame_progress_text}"),
             Mode::Recording => format!("Recording: {frame_progress_text}"),
             Mode::Idle => format!("Idle: {frame_progress_text}"),
@@ -36,6 +42,9 @@ impl App {
 
         let can_record = self.mode != Mode::Playing;
         let can_play = !self.events.is_empty() && self.mode != Mode::Recording;
+        let can_step = !self.events.is_empty() && (self.mode == Mode::Idle || is_paused);
+        let can_undo = self.mode == Mode::Idle && self.edit_history.can_undo();
+        let can_redo = self.mode == Mode::Idle && self.edit_history.can_redo();
 
         let record_icon = svg(iced::widget::svg::Handle::from_memory(
             include_bytes!("../../assets/icons/record.svg").as_slice(),
@@ -52,11 +61,33 @@ impl App {
         ))
         .width(Length::Fixed(18.0))
         .height(Length::Fixed(18.0));
+        let pause_icon = svg(iced::widget::svg::Handle::from_memory(if is_paused {
+            include_bytes!("../../assets/icons/play.svg").as_slice()
+        } else {
+            include_bytes!("../../assets/icons/pause.svg").as_slice()
+        }))
+        .width(Length::Fixed(18.0))
+        .height(Length::Fixed(18.0));
+        let step_icon = svg(iced::widget::svg::Handle::from_memory(
+            include_bytes!("../../assets/icons/step.svg").as_slice(),
+        ))
+        .width(Length::Fixed(18.0))
+        .height(Length::Fixed(18.0));
         let clear_icon = svg(iced::widget::svg::Handle::from_memory(
             include_bytes!("../../assets/icons/clear.svg").as_slice(),
         ))
         .width(Length::Fixed(18.0))
         .height(Length::Fixed(18.0));
+        let undo_icon = svg(iced::widget::svg::Handle::from_memory(
+            include_bytes!("../../assets/icons/undo.svg").as_slice(),
+        ))
+        .width(Length::Fixed(18.0))
+        .height(Length::Fixed(18.0));
+        let redo_icon = svg(iced::widget::svg::Handle::from_memory(
+            include_bytes!("../../assets/icons/redo.svg").as_slice(),
+        ))
+        .width(Length::Fixed(18.0))
+        .height(Length::Fixed(18.0));
 
         let run_group = row![
             tooltip(
@@ -78,11 +109,43 @@ impl App {
                 "Plays back the current event list.",
                 TooltipPosition::Top,
             ),
+            tooltip(
+                button(pause_icon).padding(8).on_press_maybe(
+                    (self.mode == Mode::Playing).then_some(Message::TogglePausePlayback)
+                ),
+                if is_paused {
+                    "Resumes playback."
+                } else {
+                    "Pauses playback before the next row or mid-wait."
+                },
+                TooltipPosition::Top,
+            ),
+            tooltip(
+                button(step_icon)
+                    .padding(8)
+                    .on_press_maybe(can_step.then_some(Message::StepPlayback)),
+                "Runs one row, then pauses. Starts paused playback when idle.",
+                TooltipPosition::Top,
+            ),
             tooltip(
                 button(clear_icon).padding(8).on_press(Message::Clear),
                 "Clears all recorded rows.",
                 TooltipPosition::Top,
             ),
+            tooltip(
+                button(undo_icon)
+                    .padding(8)
+                    .on_press_maybe(can_undo.then_some(Message::Undo)),
+                "Undoes the last edit to the event list (Ctrl+Z).",
+                TooltipPosition::Top,
+            ),
+            tooltip(
+                button(redo_icon)
+                    .padding(8)
+                    .on_press_maybe(can_redo.then_some(Message::Redo)),
+                "Redoes the last undone edit (Ctrl+Y or Ctrl+Shift+Z).",
+                TooltipPosition::Top,
+            ),
         ]
         .spacing(10)
         .align_y(alignment::Alignment::Center);
@@ -126,8 +189,24 @@ impl App {
 
         let mut i = 0usize;
         let mut shown_rows = 0usize;
+        let mut block_depth = 0usize;
         while i < self.events.len() && shown_rows < 1000 {
             let row_start = i;
+            // Block markers sit at the outer level, their contents one step in.
+            let indent = match self.events[i].kind {
+                RecordedEventKind::RepeatStart { .. }
+                | RecordedEventKind::IfImage { .. }
+                | RecordedEventKind::BindWindow { .. } => {
+                    block_depth += 1;
+                    block_depth - 1
+                }
+                RecordedEventKind::RepeatEnd | RecordedEventKind::EndIf | RecordedEventKind::EndWindow => {
+                    block_depth = block_depth.saturating_sub(1);
+                    block_depth
+                }
+                RecordedEventKind::Else => block_depth.saturating_sub(1),
+                _ => block_depth,
+            };
             let (row_end, action, value, thumb, new_last_pos) = if dynamic_moves_view {
                 if let RecordedEventKind::Move { .. } = self.events[i].kind {
                     let mut j = i;
@@ -205,8 +284,8 @@ impl App {
             last_pos = new_last_pos;
 
             let is_selected = self
-                .selected_index
-                .map(|idx| idx >= row_start && idx <= row_end)
+                .selected_range()
+                .map(|(first, last)| last >= row_start && first <= row_end)
                 .unwrap_or(false);
             let is_playing_row = self
                 .playback_active_index
@@ -218,7 +297,9 @@ impl App {
                 row![
                     text(row_start.to_string()).size(14).width(Length::Fixed(32.0)),
                     thumb,
-                    text(action).size(14).width(Length::Fixed(220.0)),
+                    container(text(action).size(14))
+                        .padding(iced::Padding::ZERO.left(indent as f32 * 16.0))
+                        .width(Length::Fixed(220.0)),
                     text(value).size(14).width(Length::Fill),
                 ]
                 .spacing(10)
@@ -257,7 +338,7 @@ impl App {
                 .spacing(10)
                 .align_y(alignment::Alignment::Center);
 
-            let is_even = row_start % 2 == 0;
+            let is_even = row_start.is_multiple_of(2);
             let styled = container(row_content)
                 .padding(6)
                 .style(move |_| iced::widget::container::Style {
@@ -349,6 +430,7 @@ impl App {
 
         let right_panel = iced::widget::column![
             self.view_scale_panel(),
+            self.view_playback_panel(),
             self.view_file_panel(),
             self.view_properties_panel(),
         ]
@@ -356,6 +438,15 @@ impl App {
         .width(Length::Fixed(self.right_panel_width_px()))
         .height(Length::Fill);
 
+        let actions_panel: Element<'_, Message> = match &self.last_report {
+            Some(report) => iced::widget::column![actions_panel, self.view_report_panel(report)]
+                .spacing(10)
+                .width(Length::FillPortion(3))
+                .height(Length::Fill)
+                .into(),
+            None => actions_panel.into(),
+        };
+
         let bottom = row![actions_panel, right_panel]
             .spacing(12)
             .height(Length::Fill);
@@ -26,9 +26,26 @@ impl App {
             "Image: (none)"
         };
 
+        // Dragging over the patch paints the mask the Masked filter uses.
+        const MASK_PAINTER_SIZE: f32 = 160.0;
         let image_preview: Element<Message> = if let Some(b64) = draft.patch_png_base64.as_deref() {
-            if let Some(handle) = self.thumb_handle_from_base64(b64) {
-                container(image(handle).width(Length::Fixed(96.0)).height(Length::Fixed(96.0)))
+            if let Some(handle) = self.mask_preview_handle(b64) {
+                let painter = mouse_area(
+                    image(handle)
+                        .filter_method(iced::widget::image::FilterMethod::Nearest)
+                        .width(Length::Fixed(MASK_PAINTER_SIZE))
+                        .height(Length::Fixed(MASK_PAINTER_SIZE)),
+                )
+                .on_move(|point| {
+                    Message::FindTargetMaskHovered(iced::Point::new(
+                        point.x / MASK_PAINTER_SIZE,
+                        point.y / MASK_PAINTER_SIZE,
+                    ))
+                })
+                .on_press(Message::FindTargetMaskPressed)
+                .on_release(Message::FindTargetMaskReleased)
+                .on_exit(Message::FindTargetMaskReleased);
+                container(painter)
                     .padding(6)
                     .style(|_| iced::widget::container::Style {
                         text_color: None,
@@ -52,12 +69,84 @@ impl App {
                 .into()
         };
 
+        let mask_row = row![
+            tooltip(
+                pick_list(
+                    [MaskBrush::Ignore, MaskBrush::Keep],
+                    Some(draft.mask_brush),
+                    Message::FindTargetMaskBrushSelected,
+                )
+                .width(Length::Fixed(140.0)),
+                "Drag over the patch to paint parts such as counters that the Masked filter should ignore.",
+                TooltipPosition::Top,
+            ),
+            button(text("Clear mask")).on_press_maybe(have_image.then_some(Message::FindTargetClearMask)),
+            text("Match:").size(14),
+            pick_list(MatchFilter::ALL, Some(draft.match_filter), Message::FindTargetMatchFilterSelected)
+                .width(Length::Fixed(120.0)),
+            text("On:").size(14),
+            pick_list(
+                MonitorTarget::options(monitors().len()),
+                Some(draft.search_monitor),
+                Message::FindTargetSearchMonitorSelected,
+            )
+            .width(Length::Fixed(150.0)),
+        ]
+        .spacing(10)
+        .align_y(alignment::Alignment::Center);
+
+        // The best candidate gets a green frame when it clears the threshold, amber otherwise.
+        let match_test_row: Element<Message> = match &draft.match_test {
+            Some(test) => {
+                let frame_color = if test.score >= draft.precision {
+                    Color::from_rgb8(0x8b, 0xd4, 0x9b)
+                } else {
+                    Color::from_rgb8(0xff, 0xc1, 0x07)
+                };
+                let candidate: Element<Message> = match test
+                    .candidate_png_base64
+                    .as_deref()
+                    .and_then(|b64| self.thumb_handle_from_base64(b64))
+                {
+                    Some(handle) => image(handle).width(Length::Fixed(96.0)).height(Length::Fixed(96.0)).into(),
+                    None => text("(no capture)").size(12).into(),
+                };
+                row![
+                    container(candidate)
+                        .padding(4)
+                        .style(move |_| iced::widget::container::Style {
+                            text_color: None,
+                            background: Some(Background::Color(Color::from_rgb8(0x18, 0x1b, 0x1f))),
+                            border: Border {
+                                color: frame_color,
+                                width: 2.0,
+                                radius: 8.0.into(),
+                            },
+                            shadow: Shadow::default(),
+                            snap: false,
+                        }),
+                    iced::widget::column![
+                        text("Best candidate").size(14),
+                        text(format!("{:.1}% at ({},{})", test.score * 100.0, test.pos.0, test.pos.1))
+                            .size(14)
+                            .color(frame_color),
+                    ]
+                    .spacing(4),
+                ]
+                .spacing(10)
+                .align_y(alignment::Alignment::Center)
+                .into()
+            }
+            None => iced::widget::Space::new().into(),
+        };
+
         let controls = iced::widget::column![
             text("Find target (move only)").size(18),
             text("Find target and move there; click events come from the main timeline rows.")
                 .size(13),
             text(image_status).size(14),
             image_preview,
+            mask_row,
             text(&draft.status).size(14),
             row![
                 button(text("Capture from screen"))
@@ -89,6 +178,54 @@ impl App {
             ]
             .spacing(10)
             .align_y(alignment::Alignment::Center),
+            row![
+                text("If several match:").size(14),
+                pick_list(
+                    MatchStrategy::all(draft.match_nth_text.trim().parse().unwrap_or(1).max(1)),
+                    Some(draft.match_strategy),
+                    Message::FindTargetMatchStrategySelected,
+                )
+                .width(Length::Fixed(150.0)),
+                text("N:").size(14),
+                text_input("1", &draft.match_nth_text)
+                    .on_input_maybe(
+                        matches!(draft.match_strategy, MatchStrategy::Nth(_))
+                            .then_some(Message::FindTargetMatchNthChanged),
+                    )
+                    .width(Length::Fixed(50.0)),
+            ]
+            .spacing(10)
+            .align_y(alignment::Alignment::Center),
+            row![
+                text("Offset:").size(14),
+                text_input("dx", &draft.offset_x_text)
+                    .on_input(Message::FindTargetOffsetXChanged)
+                    .width(Length::Fixed(60.0)),
+                text_input("dy", &draft.offset_y_text)
+                    .on_input(Message::FindTargetOffsetYChanged)
+                    .width(Length::Fixed(60.0)),
+                text("px from the match center").size(14),
+            ]
+            .spacing(10)
+            .align_y(alignment::Alignment::Center),
+            row![
+                checkbox(draft.scale_sweep_enabled)
+                    .label("Try scales")
+                    .on_toggle(Message::FindTargetScaleSweepToggled),
+                text_input("0.5", &draft.scale_min_text)
+                    .on_input_maybe(draft.scale_sweep_enabled.then_some(Message::FindTargetScaleMinChanged))
+                    .width(Length::Fixed(60.0)),
+                text("to").size(14),
+                text_input("2.0", &draft.scale_max_text)
+                    .on_input_maybe(draft.scale_sweep_enabled.then_some(Message::FindTargetScaleMaxChanged))
+                    .width(Length::Fixed(60.0)),
+                text("step").size(14),
+                text_input("0.25", &draft.scale_step_text)
+                    .on_input_maybe(draft.scale_sweep_enabled.then_some(Message::FindTargetScaleStepChanged))
+                    .width(Length::Fixed(60.0)),
+            ]
+            .spacing(10)
+            .align_y(alignment::Alignment::Center),
             row![
                 text("Patch:").size(14),
                 text_input("64", &draft.patch_size_text)
@@ -123,8 +260,16 @@ impl App {
             ]
             .spacing(10)
             .align_y(alignment::Alignment::Center),
+            match_test_row,
             row![
                 button(text("Cancel")).on_press(Message::CloseModal),
+                tooltip(
+                    button(text("Test match now")).on_press_maybe(
+                        (have_image && !draft.match_testing).then_some(Message::FindTargetTestMatch)
+                    ),
+                    "Searches the current screen once and shows the best candidate and its score, even below the precision.",
+                    TooltipPosition::Top,
+                ),
                 button(text("OK (add row)"))
                     .on_press_maybe(have_image.then_some(Message::FindTargetOk)),
             ]
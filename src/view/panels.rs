@@ -86,6 +86,28 @@ impl App {
         .align_y(alignment::Alignment::Center)
         .width(Length::Fill);
 
+        let window_row = row![
+            text("Bind to window:").size(14).width(Length::Fixed(140.0)),
+            tooltip(
+                toggler(self.recorder_bind_window).on_toggle(Message::SetRecordWindowBound),
+                "Records positions relative to the window matching the editor's Window patterns.",
+                TooltipPosition::Top,
+            ),
+            text(if self.recorder_bind_window {
+                window_pattern_label(
+                    self.editor_window_title_text.trim(),
+                    self.editor_window_class_text.trim(),
+                )
+            } else {
+                String::new()
+            })
+            .size(12)
+            .width(Length::Fill),
+        ]
+        .spacing(8)
+        .align_y(alignment::Alignment::Center)
+        .width(Length::Fill);
+
         let speed_row = row![
             text("Mouse move speed:").size(14).width(Length::Fixed(140.0)),
             container(
@@ -163,7 +185,7 @@ impl App {
         .width(Length::Fill);
 
         let mouse_path_main_pane: Element<Message> = container(
-            iced::widget::column![toggle_row]
+            iced::widget::column![toggle_row, window_row]
                 .spacing(8)
                 .width(Length::Fill),
         )
@@ -302,6 +324,106 @@ impl App {
             .into()
     }
 
+    pub(crate) fn view_playback_panel(&self) -> Element<'_, Message> {
+        let speed = self.playback_speed_percent as f32 / 100.0;
+        let faithful_note = if self.playback_faithful_timing && !self.recording_real_timestamps {
+            "rows have made-up timestamps; using row waits"
+        } else {
+            ""
+        };
+        let speed_row = row![
+            text("Speed:").size(14).width(Length::Fixed(56.0)),
+            tooltip(
+                slider(25..=1000, self.playback_speed_percent, Message::PlaybackSpeedChanged)
+                    .step(25u16)
+                    .style(|_theme, _status| speed_slider_style(false))
+                    .width(Length::Fill),
+                text(format!("{speed:.2}x")),
+                TooltipPosition::Top,
+            )
+            .gap(6)
+            .padding(8)
+            .style(|_| slider_tooltip_frame_style()),
+            text(format!("{speed:.2}x")).size(12),
+            tooltip(
+                toggler(self.playback_faithful_timing)
+                    .label("Faithful timing")
+                    .on_toggle(Message::SetFaithfulTiming),
+                "Replays the recorded gaps between rows instead of the per-row waits.",
+                TooltipPosition::Top,
+            ),
+            text(faithful_note).size(12),
+        ]
+        .spacing(8)
+        .align_y(alignment::Alignment::Center);
+
+        let on_error_row = row![
+            text("On error:").size(14).width(Length::Fixed(56.0)),
+            radio("abort", OnErrorChoice::Abort, Some(self.playback_on_error), Message::PlaybackOnErrorChanged),
+            radio("skip row", OnErrorChoice::Skip, Some(self.playback_on_error), Message::PlaybackOnErrorChanged),
+            radio("retry", OnErrorChoice::Retry, Some(self.playback_on_error), Message::PlaybackOnErrorChanged),
+            text_input("times", &self.playback_retry_times_text)
+                .on_input(Message::PlaybackRetryTimesChanged)
+                .width(Length::Fixed(50.0)),
+            text("times from").size(12),
+            text_input("ms", &self.playback_retry_backoff_ms_text)
+                .on_input(Message::PlaybackRetryBackoffMsChanged)
+                .width(Length::Fixed(60.0)),
+            text("ms backoff").size(12),
+        ]
+        .spacing(8)
+        .align_y(alignment::Alignment::Center);
+
+        let current_screen = screen_layout();
+        let screen_note = match (self.recording_screen, current_screen) {
+            (Some(recorded), Some(current)) if recorded != current => {
+                format!("recorded on {recorded}, this screen is {current}")
+            }
+            (Some(recorded), _) => format!("recorded on {recorded}"),
+            (None, _) => "recording screen unknown".to_string(),
+        };
+        let screen_row = row![
+            text("Screen:").size(14).width(Length::Fixed(56.0)),
+            tooltip(
+                pick_list(RemapMode::ALL, Some(self.playback_remap), Message::PlaybackRemapChanged)
+                    .text_size(12)
+                    .width(Length::Fixed(120.0)),
+                "How recorded positions move onto a screen of another size: stretched with it, \
+                 kept at the same distance from a corner, or shifted by the offset.",
+                TooltipPosition::Top,
+            ),
+            text_input("dx", &self.playback_remap_offset_x_text)
+                .on_input_maybe(
+                    (self.playback_remap == RemapMode::Offset).then_some(Message::PlaybackRemapOffsetXChanged),
+                )
+                .width(Length::Fixed(50.0)),
+            text_input("dy", &self.playback_remap_offset_y_text)
+                .on_input_maybe(
+                    (self.playback_remap == RemapMode::Offset).then_some(Message::PlaybackRemapOffsetYChanged),
+                )
+                .width(Length::Fixed(50.0)),
+            text(screen_note).size(12),
+        ]
+        .spacing(8)
+        .align_y(alignment::Alignment::Center);
+
+        container(iced::widget::column![speed_row, on_error_row, screen_row].spacing(8))
+            .padding(10)
+            .width(Length::Fill)
+            .style(|_| iced::widget::container::Style {
+                text_color: None,
+                background: Some(Background::Color(Color::from_rgb8(0x1f, 0x22, 0x26))),
+                border: Border {
+                    color: Color::from_rgb8(0x3a, 0x3f, 0x46),
+                    width: 1.0,
+                    radius: 8.0.into(),
+                },
+                shadow: Shadow::default(),
+                snap: false,
+            })
+            .into()
+    }
+
     pub(crate) fn view_file_panel(&self) -> Element<'_, Message> {
         let file_row = row![
             text("File:").size(14).width(Length::Fixed(56.0)),
@@ -312,14 +434,14 @@ impl App {
                 button(text("⭳").size(18))
                     .padding(8)
                     .on_press(Message::LoadFromFile),
-                "Loads a recording from the selected file.",
+                "Loads a recording from the selected file or project folder.",
                 TooltipPosition::Top,
             ),
             tooltip(
                 button(text("💾").size(18))
                     .padding(8)
                     .on_press(Message::SaveToFile),
-                "Saves the current recording to the selected file.",
+                "Saves the current recording to the selected file. A folder path (ending in /) saves a project with patches as separate PNG files.",
                 TooltipPosition::Top,
             ),
         ]
@@ -343,6 +465,115 @@ impl App {
             .into()
     }
 
+    pub(crate) fn view_report_panel<'a>(&'a self, report: &'a PlaybackReport) -> Element<'a, Message> {
+        let summary = match &report.error {
+            Some(err) => format!("Last run failed after {} ms: {err}", report.duration_ms),
+            None => format!("Last run passed in {} ms. {}", report.duration_ms, report.describe()),
+        };
+        let header = row![
+            text(summary).size(14).width(Length::Fill),
+            tooltip(
+                button(text("EXPORT JSON").size(12))
+                    .padding(6)
+                    .on_press(Message::ExportReport(false)),
+                "Writes the run report next to the recording file.",
+                TooltipPosition::Top,
+            ),
+            tooltip(
+                button(text("EXPORT JUNIT").size(12))
+                    .padding(6)
+                    .on_press(Message::ExportReport(true)),
+                "Writes the run as a JUnit test suite next to the recording file, one test case per row.",
+                TooltipPosition::Top,
+            ),
+            button(text("✕").size(12))
+                .padding(6)
+                .on_press(Message::CloseReport),
+        ]
+        .spacing(8)
+        .align_y(alignment::Alignment::Center);
+
+        let rows = report.rows.iter().map(|row| {
+            let position = row
+                .position
+                .map(|(x, y)| format!("({x},{y})"))
+                .unwrap_or_default();
+            let score = match (row.match_score, row.match_scale) {
+                (Some(score), Some(scale)) => format!("{:.1}% @{scale}x", score * 100.0),
+                (Some(score), None) => format!("{:.1}%", score * 100.0),
+                (None, _) => String::new(),
+            };
+            let search = row
+                .search_ms
+                .map(|ms| format!("{ms} ms"))
+                .unwrap_or_default();
+            let outcome = match (row.outcome, row.retries) {
+                (RowOutcome::Passed, 0) => "passed".to_string(),
+                (RowOutcome::Passed, retries) => format!("passed after {retries} retries"),
+                (RowOutcome::Skipped, _) => "skipped".to_string(),
+                (RowOutcome::Failed, _) => "FAILED".to_string(),
+            };
+            let outcome = match &row.error {
+                Some(err) if row.outcome != RowOutcome::Passed => format!("{outcome}: {err}"),
+                _ => outcome,
+            };
+            let outcome = match &row.failure_capture {
+                Some(folder) => format!("{outcome} (screenshots in {folder})"),
+                None => outcome,
+            };
+            row![
+                text(row.row.to_string()).size(12).width(Length::Fixed(40.0)),
+                text(&row.action).size(12).width(Length::Fixed(160.0)),
+                text(format!("{} ms", row.start_ms)).size(12).width(Length::Fixed(70.0)),
+                text(format!("{} ms", row.end_ms.saturating_sub(row.start_ms)))
+                    .size(12)
+                    .width(Length::Fixed(70.0)),
+                text(position).size(12).width(Length::Fixed(90.0)),
+                text(score).size(12).width(Length::Fixed(90.0)),
+                text(search).size(12).width(Length::Fixed(70.0)),
+                text(outcome).size(12).width(Length::Fill),
+            ]
+            .spacing(8)
+            .into()
+        });
+        let table_header = row![
+            text("Row").size(12).width(Length::Fixed(40.0)),
+            text("Action").size(12).width(Length::Fixed(160.0)),
+            text("Start").size(12).width(Length::Fixed(70.0)),
+            text("Took").size(12).width(Length::Fixed(70.0)),
+            text("Position").size(12).width(Length::Fixed(90.0)),
+            text("Score").size(12).width(Length::Fixed(90.0)),
+            text("Search").size(12).width(Length::Fixed(70.0)),
+            text("Outcome").size(12).width(Length::Fill),
+        ]
+        .spacing(8);
+
+        container(
+            iced::widget::column![
+                header,
+                table_header,
+                scrollable(column(rows).spacing(2))
+                    .height(Length::Fixed(160.0))
+                    .width(Length::Fill),
+            ]
+            .spacing(6),
+        )
+        .padding(10)
+        .width(Length::Fill)
+        .style(|_| iced::widget::container::Style {
+            text_color: None,
+            background: Some(Background::Color(Color::from_rgb8(0x1f, 0x22, 0x26))),
+            border: Border {
+                color: Color::from_rgb8(0x3a, 0x3f, 0x46),
+                width: 1.0,
+                radius: 8.0.into(),
+            },
+            shadow: Shadow::default(),
+            snap: false,
+        })
+        .into()
+    }
+
     pub(crate) fn view_click_editor_panel(&self) -> Element<'_, Message> {
         const PREVIEW_IMAGE_SIZE: f32 = 128.0;
         const PREVIEW_FRAME_PADDING: f32 = 2.0;
@@ -551,6 +782,95 @@ impl App {
                     shadow: Shadow::default(),
                     snap: false,
                 }),
+                row![
+                    tooltip(
+                        pick_list(
+                            MatchStrategy::all(self.editor_match_nth_text.trim().parse().unwrap_or(1).max(1)),
+                            Some(self.editor_match_strategy),
+                            Message::EditorMatchStrategySelected,
+                        )
+                        .text_size(12)
+                        .width(Length::Fill),
+                        "Which match to click when the image is on screen more than once. \
+                         Nth counts in reading order.",
+                        TooltipPosition::Top,
+                    ),
+                    text_input("N", &self.editor_match_nth_text)
+                        .on_input_maybe(
+                            matches!(self.editor_match_strategy, MatchStrategy::Nth(_))
+                                .then_some(Message::EditorMatchNthChanged),
+                        )
+                        .size(12)
+                        .width(Length::Fixed(VALUE_COL_W)),
+                ]
+                .spacing(8)
+                .align_y(alignment::Alignment::Center),
+                row![
+                    tooltip(
+                        pick_list(MatchFilter::ALL, Some(self.editor_match_filter), Message::EditorMatchFilterSelected)
+                            .text_size(12)
+                            .width(Length::Fill),
+                        "Grayscale is fastest. Edges ignores fills and colors; Masked skips the \
+                         patch's transparent pixels. Both are slower on the whole screen.",
+                        TooltipPosition::Top,
+                    ),
+                    tooltip(
+                        pick_list(
+                            MonitorTarget::options(monitors().len()),
+                            Some(self.editor_search_monitor),
+                            Message::EditorSearchMonitorSelected,
+                        )
+                        .text_size(12)
+                        .width(Length::Fill),
+                        "Where to look for the template. Monitor 1 is the primary one; \
+                         Recorded uses the monitor the click was recorded on.",
+                        TooltipPosition::Top,
+                    ),
+                ]
+                .spacing(8)
+                .align_y(alignment::Alignment::Center),
+                row![
+                    tooltip(
+                        text("Offset").size(12),
+                        "Click this far from the match center. Click the preview to pick it.",
+                        TooltipPosition::Top,
+                    ),
+                    text_input("dx", &self.editor_target_offset_x_text)
+                        .on_input(Message::EditorTargetOffsetXChanged)
+                        .size(12)
+                        .width(Length::Fill),
+                    text_input("dy", &self.editor_target_offset_y_text)
+                        .on_input(Message::EditorTargetOffsetYChanged)
+                        .size(12)
+                        .width(Length::Fill),
+                ]
+                .spacing(8)
+                .align_y(alignment::Alignment::Center),
+                row![
+                    tooltip(
+                        checkbox(self.editor_scale_sweep_enabled)
+                            .label("Scales")
+                            .text_size(12)
+                            .on_toggle(Message::EditorScaleSweepToggled),
+                        "Also try the template resized from min to max in steps, for screens \
+                         with a different DPI. The scale that matched is tried first next time.",
+                        TooltipPosition::Top,
+                    ),
+                    text_input("0.5", &self.editor_scale_min_text)
+                        .on_input_maybe(self.editor_scale_sweep_enabled.then_some(Message::EditorScaleMinChanged))
+                        .size(12)
+                        .width(Length::Fill),
+                    text_input("2.0", &self.editor_scale_max_text)
+                        .on_input_maybe(self.editor_scale_sweep_enabled.then_some(Message::EditorScaleMaxChanged))
+                        .size(12)
+                        .width(Length::Fill),
+                    text_input("0.25", &self.editor_scale_step_text)
+                        .on_input_maybe(self.editor_scale_sweep_enabled.then_some(Message::EditorScaleStepChanged))
+                        .size(12)
+                        .width(Length::Fill),
+                ]
+                .spacing(8)
+                .align_y(alignment::Alignment::Center),
             ]
             .spacing(8)
             .align_x(alignment::Alignment::Start)
@@ -574,6 +894,61 @@ impl App {
                 .into()
         }
 
+        // With FIND IMAGE on, clicking the preview sets the click offset from the
+        // match center; a dot marks the current offset.
+        let preview_picker = |handle: iced::widget::image::Handle| -> Element<Message> {
+            let picture = image(handle)
+                .width(Length::Fixed(PREVIEW_IMAGE_SIZE))
+                .height(Length::Fixed(PREVIEW_IMAGE_SIZE));
+            if !self.editor_use_find_image {
+                return picture.into();
+            }
+
+            let (dx, dy) = self.parse_editor_target_offset();
+            let marker = self.preview_patch_size().and_then(|(w, h)| {
+                let mx = (dx as f32 + w as f32 / 2.0) / w as f32 * PREVIEW_IMAGE_SIZE;
+                let my = (dy as f32 + h as f32 / 2.0) / h as f32 * PREVIEW_IMAGE_SIZE;
+                let inside = (0.0..=PREVIEW_IMAGE_SIZE).contains(&mx) && (0.0..=PREVIEW_IMAGE_SIZE).contains(&my);
+                inside.then(|| {
+                    container(
+                        container(iced::widget::Space::new())
+                            .width(Length::Fixed(7.0))
+                            .height(Length::Fixed(7.0))
+                            .style(|_| iced::widget::container::Style {
+                                text_color: None,
+                                background: Some(Background::Color(Color::from_rgb8(0xff, 0x45, 0x45))),
+                                border: Border {
+                                    color: Color::WHITE,
+                                    width: 1.0,
+                                    radius: 4.0.into(),
+                                },
+                                shadow: Shadow::default(),
+                                snap: false,
+                            }),
+                    )
+                    .padding(iced::Padding::ZERO.left((mx - 3.5).max(0.0)).top((my - 3.5).max(0.0)))
+                })
+            });
+            let content: Element<Message> = match marker {
+                Some(marker) => stack![picture, marker].into(),
+                None => picture.into(),
+            };
+
+            tooltip(
+                mouse_area(content)
+                    .on_move(|point| {
+                        Message::EditorPreviewHovered(iced::Point::new(
+                            point.x / PREVIEW_IMAGE_SIZE,
+                            point.y / PREVIEW_IMAGE_SIZE,
+                        ))
+                    })
+                    .on_press(Message::EditorPickTargetOffset),
+                "Click where the row should click, relative to the found image.",
+                TooltipPosition::Top,
+            )
+            .into()
+        };
+
         let static_preview: Element<Message> = if let Some(b64) = self.editor_static_preview_patch_b64.as_deref() {
             if let Some(handle) = self.preview_handle_from_base64(b64) {
                 container(
@@ -585,11 +960,7 @@ impl App {
                             preview_column_frame(
                                 iced::widget::column![
                                     container(
-                                        container(
-                                            image(handle)
-                                                .width(Length::Fixed(PREVIEW_IMAGE_SIZE))
-                                                .height(Length::Fixed(PREVIEW_IMAGE_SIZE)),
-                                        )
+                                        container(preview_picker(handle))
                                         .width(Length::Fixed(PREVIEW_IMAGE_SIZE))
                                         .height(Length::Fixed(PREVIEW_IMAGE_SIZE))
                                         .style(|_| iced::widget::container::Style {
@@ -922,6 +1293,304 @@ impl App {
         })
         .into();
 
+        let selected_kind = self
+            .selected_index
+            .and_then(|i| self.events.get(i))
+            .map(|ev| &ev.kind);
+        let key_btn_label = if matches!(
+            selected_kind,
+            Some(RecordedEventKind::KeyDown { .. } | RecordedEventKind::KeyUp { .. })
+        ) {
+            "APPLY KEY"
+        } else {
+            "INSERT KEY"
+        };
+        let text_btn_label = if matches!(selected_kind, Some(RecordedEventKind::TypeText { .. })) {
+            "APPLY TEXT"
+        } else {
+            "INSERT TEXT"
+        };
+
+        let modifiers = self.editor_key_modifiers;
+        let keyboard_group: Element<Message> = container(
+            iced::widget::column![
+                text("Keyboard").size(13).color(Color::from_rgb8(0xc0, 0xca, 0xd6)),
+                row![
+                    container(text("Key:").size(13)).width(Length::Fixed(TARGET_COL_W)),
+                    text_input("return, tab, s, f5 ...", &self.editor_key_text)
+                        .on_input(Message::EditorKeyChanged)
+                        .width(Length::Fill),
+                    button(text(key_btn_label).size(12)).on_press(Message::EditorInsertOrApplyKey),
+                ]
+                .spacing(8)
+                .align_y(alignment::Alignment::Center)
+                .width(Length::Fill),
+                row![
+                    container(text("Modifiers:").size(13)).width(Length::Fixed(TARGET_COL_W)),
+                    checkbox(modifiers.ctrl)
+                        .label("Ctrl")
+                        .on_toggle(move |ctrl| Message::EditorKeyModifiersChanged(KeyModifiers { ctrl, ..modifiers })),
+                    checkbox(modifiers.shift)
+                        .label("Shift")
+                        .on_toggle(move |shift| Message::EditorKeyModifiersChanged(KeyModifiers { shift, ..modifiers })),
+                    checkbox(modifiers.alt)
+                        .label("Alt")
+                        .on_toggle(move |alt| Message::EditorKeyModifiersChanged(KeyModifiers { alt, ..modifiers })),
+                    checkbox(modifiers.meta)
+                        .label("Win")
+                        .on_toggle(move |meta| Message::EditorKeyModifiersChanged(KeyModifiers { meta, ..modifiers })),
+                ]
+                .spacing(12)
+                .align_y(alignment::Alignment::Center)
+                .width(Length::Fill),
+                row![
+                    container(text("Type text:").size(13)).width(Length::Fixed(TARGET_COL_W)),
+                    text_input("text to type", &self.editor_type_text)
+                        .on_input(Message::EditorTypeTextChanged)
+                        .width(Length::Fill),
+                    button(text(text_btn_label).size(12)).on_press(Message::EditorInsertOrApplyText),
+                ]
+                .spacing(8)
+                .align_y(alignment::Alignment::Center)
+                .width(Length::Fill),
+            ]
+            .spacing(6)
+            .width(Length::Fill),
+        )
+        .padding(8)
+        .width(Length::Fill)
+        .style(|_| iced::widget::container::Style {
+            text_color: None,
+            background: Some(Background::Color(Color::from_rgb8(0x1c, 0x21, 0x28))),
+            border: Border {
+                color: Color::from_rgb8(0x76, 0x85, 0x96),
+                width: 1.0,
+                radius: 8.0.into(),
+            },
+            shadow: Shadow::default(),
+            snap: false,
+        })
+        .into();
+
+        let repeat_btn_label = if matches!(selected_kind, Some(RecordedEventKind::RepeatStart { .. })) {
+            "APPLY COUNT"
+        } else if self.selected_index.is_some() {
+            "WRAP IN REPEAT"
+        } else {
+            "INSERT REPEAT"
+        };
+        let if_btn_label = if matches!(selected_kind, Some(RecordedEventKind::IfImage { .. })) {
+            "APPLY CONDITION"
+        } else if self.selected_index.is_some() {
+            "WRAP IN IF IMAGE"
+        } else {
+            "INSERT IF IMAGE"
+        };
+        let wait_btn_label = if matches!(
+            selected_kind,
+            Some(RecordedEventKind::WaitForImage { .. } | RecordedEventKind::WaitUntilImageGone { .. })
+        ) {
+            "APPLY WAIT"
+        } else {
+            "INSERT WAIT"
+        };
+        let label_btn_label = if matches!(selected_kind, Some(RecordedEventKind::Label { .. })) {
+            "RENAME LABEL"
+        } else {
+            "INSERT LABEL"
+        };
+        let window_btn_label = if matches!(selected_kind, Some(RecordedEventKind::BindWindow { .. })) {
+            "APPLY WINDOW"
+        } else {
+            "BIND TO WINDOW"
+        };
+        let blocks_group: Element<Message> = container(
+            iced::widget::column![
+                text("Blocks").size(13).color(Color::from_rgb8(0xc0, 0xca, 0xd6)),
+                row![
+                    container(text("Repeat:").size(13)).width(Length::Fixed(TARGET_COL_W)),
+                    text_input("count", &self.editor_repeat_count_text)
+                        .on_input(Message::EditorRepeatCountChanged)
+                        .width(Length::Fixed(80.0)),
+                    text("times").size(13).width(Length::Fill),
+                    tooltip(
+                        button(text(repeat_btn_label).size(12)).on_press(Message::EditorInsertOrApplyRepeat),
+                        "Wraps the selected rows (shift-click for a range) in a repeat block.",
+                        TooltipPosition::Top,
+                    ),
+                ]
+                .spacing(8)
+                .align_y(alignment::Alignment::Center)
+                .width(Length::Fill),
+                row![
+                    container(text("If image:").size(13)).width(Length::Fixed(TARGET_COL_W)),
+                    text("GET preview, target precision and timeout")
+                        .size(12)
+                        .width(Length::Fill),
+                    tooltip(
+                        button(text(if_btn_label).size(12)).on_press(Message::EditorInsertOrApplyIfImage),
+                        "Runs the selected rows only when the GET preview image appears; \
+                         rows between ELSE and END IF run otherwise. Matches in grayscale \
+                         at 1x anywhere on the desktop, whatever the match settings above.",
+                        TooltipPosition::Top,
+                    ),
+                ]
+                .spacing(8)
+                .align_y(alignment::Alignment::Center)
+                .width(Length::Fill),
+                row![
+                    container(text("Find target:").size(13)).width(Length::Fixed(TARGET_COL_W)),
+                    text("Move-only image search row").size(12).width(Length::Fill),
+                    tooltip(
+                        button(text("ADD FIND TARGET").size(12)).on_press(Message::OpenFindTargetModal),
+                        "Opens the find-target dialog to capture or load a template, test it against the screen and add the row.",
+                        TooltipPosition::Top,
+                    ),
+                ]
+                .spacing(8)
+                .align_y(alignment::Alignment::Center)
+                .width(Length::Fill),
+                row![
+                    container(text("Wait until:").size(13)).width(Length::Fixed(TARGET_COL_W)),
+                    radio(
+                        "shown",
+                        false,
+                        Some(self.editor_wait_image_gone),
+                        Message::EditorWaitImageGoneToggled,
+                    ),
+                    radio(
+                        "gone",
+                        true,
+                        Some(self.editor_wait_image_gone),
+                        Message::EditorWaitImageGoneToggled,
+                    ),
+                    text("poll").size(13),
+                    text_input("ms", &self.editor_wait_poll_ms_text)
+                        .on_input(Message::EditorWaitPollMsChanged)
+                        .width(Length::Fixed(70.0)),
+                    text("ms").size(13).width(Length::Fill),
+                    tooltip(
+                        button(text(wait_btn_label).size(12)).on_press(Message::EditorInsertOrApplyWaitImage),
+                        "Waits for the GET preview image to appear or disappear, \
+                         up to the target timeout. Matches like IF IMAGE.",
+                        TooltipPosition::Top,
+                    ),
+                ]
+                .spacing(8)
+                .align_y(alignment::Alignment::Center)
+                .width(Length::Fill),
+                row![
+                    container(text("On timeout:").size(13)).width(Length::Fixed(TARGET_COL_W)),
+                    radio(
+                        "fail",
+                        OnTimeoutChoice::Fail,
+                        Some(self.editor_on_timeout),
+                        Message::EditorOnTimeoutChanged,
+                    ),
+                    radio(
+                        "continue",
+                        OnTimeoutChoice::Continue,
+                        Some(self.editor_on_timeout),
+                        Message::EditorOnTimeoutChanged,
+                    ),
+                    radio(
+                        "go to label",
+                        OnTimeoutChoice::GotoLabel,
+                        Some(self.editor_on_timeout),
+                        Message::EditorOnTimeoutChanged,
+                    ),
+                ]
+                .spacing(12)
+                .align_y(alignment::Alignment::Center)
+                .width(Length::Fill),
+                row![
+                    container(text("On error:").size(13)).width(Length::Fixed(TARGET_COL_W)),
+                    radio(
+                        "run default",
+                        OnErrorChoice::RunDefault,
+                        Some(self.editor_on_error),
+                        Message::EditorOnErrorChanged,
+                    ),
+                    radio("abort", OnErrorChoice::Abort, Some(self.editor_on_error), Message::EditorOnErrorChanged),
+                    radio("skip", OnErrorChoice::Skip, Some(self.editor_on_error), Message::EditorOnErrorChanged),
+                    radio("retry", OnErrorChoice::Retry, Some(self.editor_on_error), Message::EditorOnErrorChanged),
+                    radio(
+                        "go to label",
+                        OnErrorChoice::GotoLabel,
+                        Some(self.editor_on_error),
+                        Message::EditorOnErrorChanged,
+                    ),
+                ]
+                .spacing(12)
+                .align_y(alignment::Alignment::Center)
+                .width(Length::Fill),
+                row![
+                    container(text("Retry:").size(13)).width(Length::Fixed(TARGET_COL_W)),
+                    text_input("times", &self.editor_retry_times_text)
+                        .on_input(Message::EditorRetryTimesChanged)
+                        .width(Length::Fixed(60.0)),
+                    text("times from").size(13),
+                    text_input("ms", &self.editor_retry_backoff_ms_text)
+                        .on_input(Message::EditorRetryBackoffMsChanged)
+                        .width(Length::Fixed(70.0)),
+                    text("ms backoff").size(13).width(Length::Fill),
+                    tooltip(
+                        button(text("APPLY TO ROWS").size(12)).on_press(Message::EditorApplyOnError),
+                        "Sets what the selected rows (shift-click for a range) do when they fail. \
+                         Retries wait twice as long each time.",
+                        TooltipPosition::Top,
+                    ),
+                ]
+                .spacing(8)
+                .align_y(alignment::Alignment::Center)
+                .width(Length::Fill),
+                row![
+                    container(text("Label:").size(13)).width(Length::Fixed(TARGET_COL_W)),
+                    text_input("label name", &self.editor_label_text)
+                        .on_input(Message::EditorLabelChanged)
+                        .width(Length::Fill),
+                    button(text(label_btn_label).size(12)).on_press(Message::EditorInsertOrApplyLabel),
+                ]
+                .spacing(8)
+                .align_y(alignment::Alignment::Center)
+                .width(Length::Fill),
+                row![
+                    container(text("Window:").size(13)).width(Length::Fixed(TARGET_COL_W)),
+                    text_input("title regex", &self.editor_window_title_text)
+                        .on_input(Message::EditorWindowTitleChanged)
+                        .width(Length::Fill),
+                    text_input("class regex", &self.editor_window_class_text)
+                        .on_input(Message::EditorWindowClassChanged)
+                        .width(Length::Fixed(120.0)),
+                    tooltip(
+                        button(text(window_btn_label).size(12)).on_press(Message::EditorInsertOrApplyWindow),
+                        "Wraps the selected rows in a window block: their positions become relative to \
+                         the matching window, which playback brings to the front first.",
+                        TooltipPosition::Top,
+                    ),
+                ]
+                .spacing(8)
+                .align_y(alignment::Alignment::Center)
+                .width(Length::Fill),
+            ]
+            .spacing(6)
+            .width(Length::Fill),
+        )
+        .padding(8)
+        .width(Length::Fill)
+        .style(|_| iced::widget::container::Style {
+            text_color: None,
+            background: Some(Background::Color(Color::from_rgb8(0x1c, 0x21, 0x28))),
+            border: Border {
+                color: Color::from_rgb8(0x76, 0x85, 0x96),
+                width: 1.0,
+                radius: 8.0.into(),
+            },
+            shadow: Shadow::default(),
+            snap: false,
+        })
+        .into();
+
         let mut button_items: Vec<Element<Message>> = vec![
             container(button(text(insert_btn_label)).on_press(Message::EditorInsertOrApply))
                 .width(Length::Fill)
@@ -979,6 +1648,8 @@ impl App {
             preview_group,
             mode_group,
             buttons_group,
+            keyboard_group,
+            blocks_group,
         ]
         .spacing(6);
 
@@ -1001,8 +1672,33 @@ impl App {
 
     pub(crate) fn view_properties_panel(&self) -> iced::widget::Container<'_, Message> {
         let body: Element<Message> = {
-            let selection_note: Element<Message> = match self.selected_index {
-                Some(index) => text(format!("Selected row: {}", index)).size(12).into(),
+            let selection_note: Element<Message> = match self.selected_range() {
+                Some((first, last)) => {
+                    let label = if first == last {
+                        format!("Selected row: {first}")
+                    } else {
+                        format!("Selected rows: {first}-{last}")
+                    };
+                    let can_play = self.mode == Mode::Idle;
+                    row![
+                        text(label).size(12).width(Length::Fill),
+                        tooltip(
+                            button(text("PLAY FROM HERE").size(12))
+                                .on_press_maybe(can_play.then_some(Message::PlayFromSelected)),
+                            "Plays from the first selected row to the end.",
+                            TooltipPosition::Top,
+                        ),
+                        tooltip(
+                            button(text("PLAY SELECTION").size(12))
+                                .on_press_maybe(can_play.then_some(Message::PlaySelection)),
+                            "Plays only the selected rows. Shift-click a row to extend the selection.",
+                            TooltipPosition::Top,
+                        ),
+                    ]
+                    .spacing(8)
+                    .align_y(alignment::Alignment::Center)
+                    .into()
+                }
                 None => text("Selected row: none").size(12).into(),
             };
 